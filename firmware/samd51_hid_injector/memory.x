@@ -2,8 +2,8 @@
 MEMORY
 {
   /* SAMD51J20A has 1MB Flash, 256KB SRAM */
-  /* Reserve first 8KB for Apollo bootloader */
-  FLASH (rx)  : ORIGIN = 0x00002000, LENGTH = 0x000FE000  /* 1MB - 8KB */
+  /* Reserve first 8KB for Apollo bootloader and the last 8KB for the config image (nvm.rs) */
+  FLASH (rx)  : ORIGIN = 0x00002000, LENGTH = 0x000FC000  /* 1MB - 16KB */
   RAM   (rwx) : ORIGIN = 0x20000000, LENGTH = 0x00040000  /* 256KB */
 }
 
@@ -0,0 +1,38 @@
+//! FPGA Command Codes
+//! The command byte of each "[CMD:XX]" UART frame, shared by the frame
+//! builder in protocol.rs and the step queue
+
+/// Command codes carried in the "[CMD:XX]" frame header. The FPGA's
+/// CMD_* localparams in uart_command_processor.v must use the same values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FpgaCode {
+    InjectKbd = 0x10,
+    InjectMouse = 0x11,
+    SetFilter = 0x20,
+    SetMode = 0x21,
+}
+
+impl FpgaCode {
+    /// Every defined code, in ascending order; nozen.codes lists these
+    pub const ALL: [FpgaCode; 4] = [
+        FpgaCode::InjectKbd,
+        FpgaCode::InjectMouse,
+        FpgaCode::SetFilter,
+        FpgaCode::SetMode,
+    ];
+
+    pub fn from_code(code: u8) -> Option<FpgaCode> {
+        FpgaCode::ALL.iter().copied().find(|c| *c as u8 == code)
+    }
+
+    /// Name as used by the FPGA and the frame documentation
+    pub fn name(self) -> &'static str {
+        match self {
+            FpgaCode::InjectKbd => "INJECT_KBD",
+            FpgaCode::InjectMouse => "INJECT_MOUSE",
+            FpgaCode::SetFilter => "SET_FILTER",
+            FpgaCode::SetMode => "SET_MODE",
+        }
+    }
+}
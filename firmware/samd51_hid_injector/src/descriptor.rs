@@ -10,6 +10,12 @@ pub const MAX_DESCRIPTOR_SIZE: usize = 1024;
 /// Maximum number of report items we track
 pub const MAX_REPORT_ITEMS: usize = 64;
 
+/// Constant (padding) input runs remembered per descriptor; later ones are not listed
+pub const MAX_PADDING_RUNS: usize = 16;
+
+/// Maximum Usage items queued ahead of a single main item
+const MAX_LOCAL_USAGES: usize = 16;
+
 /// HID Report Types
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ReportType {
@@ -59,6 +65,27 @@ impl From<u16> for UsagePage {
     }
 }
 
+impl From<UsagePage> for u16 {
+    fn from(page: UsagePage) -> Self {
+        match page {
+            UsagePage::GenericDesktop => 0x01,
+            UsagePage::SimulationControls => 0x02,
+            UsagePage::VRControls => 0x03,
+            UsagePage::SportControls => 0x04,
+            UsagePage::GameControls => 0x05,
+            UsagePage::GenericDevice => 0x06,
+            UsagePage::Keyboard => 0x07,
+            UsagePage::LED => 0x08,
+            UsagePage::Button => 0x09,
+            UsagePage::Ordinal => 0x0A,
+            UsagePage::Telephony => 0x0B,
+            UsagePage::Consumer => 0x0C,
+            UsagePage::Digitizer => 0x0D,
+            UsagePage::Unknown(value) => value,
+        }
+    }
+}
+
 /// HID Usage (specific control within a usage page)
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Usage {
@@ -78,6 +105,7 @@ pub struct ReportField {
     pub logical_max: i32,
     pub is_relative: bool,        // True for relative values (mouse movement)
     pub is_array: bool,           // True for arrays (keyboard keys)
+    pub string_index: Option<u8>, // String descriptor labelling the control, if declared
 }
 
 /// Parsed HID descriptor information
@@ -93,6 +121,19 @@ pub struct HidDescriptor {
     pub is_keyboard: bool,
     pub is_mouse: bool,
     pub is_gamepad: bool,
+    pub is_consumer: bool,
+    pub is_digitizer: bool,
+    /// Keyboard and mouse input carried under different report IDs
+    pub is_composite: bool,
+    /// Digitizer Contact Identifier (0x51) inputs, one per touch slot
+    pub contact_ids: u8,
+    /// A Digitizer Contact Count (0x54) input is declared
+    pub has_contact_count: bool,
+    /// Constant input items as (report_id, bit_offset, bit_size); not stored as fields
+    pub padding: Vec<(u8, u16, u16), MAX_PADDING_RUNS>,
+    /// More than MAX_REPORT_ITEMS fields were declared; the rest were dropped
+    /// but still counted towards device type detection
+    pub truncated: bool,
 }
 
 impl HidDescriptor {
@@ -104,6 +145,187 @@ impl HidDescriptor {
             is_keyboard: false,
             is_mouse: false,
             is_gamepad: false,
+            is_consumer: false,
+            is_digitizer: false,
+            is_composite: false,
+            contact_ids: 0,
+            has_contact_count: false,
+            padding: Vec::new(),
+            truncated: false,
+        }
+    }
+
+    /// Padding runs of an input report as (bit_offset, bit_size)
+    /// Together with the report's fields these cover the whole report.
+    pub fn report_gaps(&self, report_id: u8) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.padding.iter()
+            .filter(move |&&(id, _, _)| id == report_id)
+            .map(|&(_, offset, size)| (offset, size))
+    }
+
+    /// Number of distinct report IDs carrying input fields
+    pub fn report_id_count(&self) -> usize {
+        let mut ids: Vec<u8, MAX_REPORT_ITEMS> = Vec::new();
+        for field in self.fields.iter().filter(|f| f.report_type == ReportType::Input) {
+            if !ids.contains(&field.report_id) {
+                let _ = ids.push(field.report_id);
+            }
+        }
+        ids.len()
+    }
+
+    /// Buttons the device reports, taken from the highest Button-page input usage
+    /// (0 when the descriptor declares no buttons)
+    pub fn button_count(&self) -> u8 {
+        self.fields.iter()
+            .filter(|f| f.report_type == ReportType::Input && f.usage.page == UsagePage::Button)
+            .map(|f| f.usage.id.min(u8::MAX as u16) as u8)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Any field, input or output, carries the given usage
+    pub fn has_usage(&self, page: UsagePage, id: u16) -> bool {
+        self.fields.iter().any(|f| f.usage.page == page && f.usage.id == id)
+    }
+
+    /// X and Y inputs are both present and absolute (tablet, touchscreen,
+    /// absolute pointer) rather than relative motion like a mouse
+    pub fn is_absolute_pointer(&self) -> bool {
+        let x = self.find_input(UsagePage::GenericDesktop, 0x30);
+        let y = self.find_input(UsagePage::GenericDesktop, 0x31);
+        matches!((x, y), (Some(x), Some(y)) if !x.is_relative && !y.is_relative)
+    }
+
+    /// Simultaneous touches the device can report: one per Contact Identifier
+    /// slot, or 1 if it only declares a Contact Count; 0 for non-touch devices
+    pub fn max_contacts(&self) -> u8 {
+        match (self.contact_ids, self.has_contact_count) {
+            (0, true) => 1,
+            (slots, _) => slots,
+        }
+    }
+
+    /// Input reports start with a report ID byte
+    pub fn uses_report_ids(&self) -> bool {
+        self.fields.iter().any(|f| f.report_id != 0)
+    }
+
+    /// Bit position of an input field in its report, after the report ID byte
+    /// Parsed offsets run on across report IDs and output items, so this is
+    /// the input bits (fields and padding) of the same report before it.
+    pub fn report_position(&self, field: &ReportField) -> u16 {
+        let fields: u16 = self.fields.iter()
+            .filter(|f| f.report_type == ReportType::Input && f.report_id == field.report_id)
+            .filter(|f| f.bit_offset < field.bit_offset)
+            .map(|f| f.bit_size as u16)
+            .sum();
+        let padding: u16 = self.report_gaps(field.report_id)
+            .filter(|&(offset, _)| offset < field.bit_offset)
+            .map(|(_, size)| size)
+            .sum();
+        fields + padding
+    }
+
+    /// Bytes in an input report, not counting the report ID byte
+    pub fn input_report_len(&self, report_id: u8) -> usize {
+        self.fields.iter()
+            .filter(|f| f.report_type == ReportType::Input && f.report_id == report_id)
+            .map(|f| (self.report_position(f) + f.bit_size as u16).div_ceil(8) as usize)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Lay out an absolute-pointer input report in `out`: report ID if the
+    /// descriptor uses them, `buttons` (bit 0 = button 1) and X/Y as logical
+    /// values. Returns its length, or None without X/Y or if `out` is too short.
+    pub fn absolute_report(&self, buttons: u8, x: u16, y: u16, out: &mut [u8]) -> Option<usize> {
+        let x_field = self.find_input(UsagePage::GenericDesktop, 0x30)?;
+        let y_field = self.find_input(UsagePage::GenericDesktop, 0x31)?;
+        let id = x_field.report_id;
+        let prefix = self.uses_report_ids() as usize;
+        let len = prefix + self.input_report_len(id);
+        if len > out.len() {
+            return None;
+        }
+
+        out[..len].fill(0);
+        if prefix == 1 {
+            out[0] = id;
+        }
+        let data = &mut out[prefix..len];
+        for field in self.fields.iter().filter(|f| {
+            f.report_type == ReportType::Input && f.report_id == id
+                && f.usage.page == UsagePage::Button && !f.is_array
+        }) {
+            let pressed = (1..=8).contains(&field.usage.id) && buttons & (1 << (field.usage.id - 1)) != 0;
+            write_bits(data, self.report_position(field), field.bit_size, pressed as u32);
+        }
+        write_bits(data, self.report_position(x_field), x_field.bit_size, x as u32);
+        if y_field.report_id == id {
+            write_bits(data, self.report_position(y_field), y_field.bit_size, y as u32);
+        }
+        Some(len)
+    }
+
+    /// Modifier bits and pressed scancodes in a keyboard input report
+    /// `report` starts with the report ID when the descriptor declares IDs.
+    /// None if the report carries no keyboard fields or is too short for them.
+    pub fn decode_keys(&self, report: &[u8]) -> Option<(u8, Vec<u8, 16>)> {
+        let (id, data) = match self.uses_report_ids() {
+            true => (*report.first()?, &report[1..]),
+            false => (0, report),
+        };
+
+        let mut modifiers = 0;
+        let mut keys = Vec::new();
+        let mut found = false;
+        for field in self.fields.iter().filter(|f| {
+            f.report_type == ReportType::Input && f.report_id == id && f.usage.page == UsagePage::Keyboard
+        }) {
+            found = true;
+            let value = read_bits(data, self.report_position(field), field.bit_size)?;
+            // Key arrays carry scancodes; 0-3 mean no key or a rollover error
+            if field.is_array && value > 3 {
+                let _ = keys.push(value as u8);
+            } else if !field.is_array && (0xE0..=0xE7).contains(&field.usage.id) && value != 0 {
+                modifiers |= 1 << (field.usage.id - 0xE0);
+            }
+        }
+        found.then_some((modifiers, keys))
+    }
+
+    /// Find the first input field carrying the given usage
+    pub fn find_input(&self, page: UsagePage, id: u16) -> Option<&ReportField> {
+        self.fields.iter().find(|f| {
+            f.report_type == ReportType::Input && f.usage.page == page && f.usage.id == id
+        })
+    }
+}
+
+/// Read `size` bits (up to 32) at `offset` from a little-endian report
+fn read_bits(data: &[u8], offset: u16, size: u8) -> Option<u32> {
+    let mut value = 0u32;
+    for bit in 0..size.min(32) as u16 {
+        let pos = offset + bit;
+        let byte = *data.get((pos / 8) as usize)?;
+        value |= (((byte >> (pos % 8)) & 1) as u32) << bit;
+    }
+    Some(value)
+}
+
+/// Write the low `size` bits of `value` at `offset` into a little-endian
+/// report; bits past the end of `data` are dropped
+fn write_bits(data: &mut [u8], offset: u16, size: u8, value: u32) {
+    for bit in 0..size.min(32) as u16 {
+        let pos = offset + bit;
+        if let Some(byte) = data.get_mut((pos / 8) as usize) {
+            let mask = 1 << (pos % 8);
+            if (value >> bit) & 1 != 0 {
+                *byte |= mask;
+            } else {
+                *byte &= !mask;
+            }
         }
     }
 }
@@ -113,13 +335,32 @@ pub struct DescriptorParser {
     descriptor: HidDescriptor,
     // Parser state
     current_usage_page: u16,
-    current_usage: u16,
     current_report_id: u8,
     current_bit_offset: u16,
     logical_minimum: i32,
     logical_maximum: i32,
     report_size: u8,
     report_count: u8,
+    // Local item state (reset after every main item)
+    /// Queued usages; a non-zero high word is an explicit usage page
+    usages: Vec<u32, MAX_LOCAL_USAGES>,
+    usage_minimum: u16,
+    usage_maximum: u16,
+    has_usage_range: bool,
+    /// Usage page from an extended (4-byte) Usage Minimum, for the whole range
+    usage_range_page: Option<u16>,
+    /// Queued String Index items, consumed like usages
+    string_indexes: Vec<u8, MAX_LOCAL_USAGES>,
+    string_minimum: u8,
+    string_maximum: u8,
+    has_string_range: bool,
+    /// Open Collection items
+    collection_depth: u8,
+    /// Report IDs seen carrying each class, for composite detection
+    keyboard_ids: Vec<u8, MAX_REPORT_ITEMS>,
+    mouse_ids: Vec<u8, MAX_REPORT_ITEMS>,
+    /// Reject anything the lenient parse would skip or tolerate
+    strict: bool,
 }
 
 impl DescriptorParser {
@@ -127,13 +368,34 @@ impl DescriptorParser {
         DescriptorParser {
             descriptor: HidDescriptor::new(),
             current_usage_page: 0,
-            current_usage: 0,
             current_report_id: 0,
             current_bit_offset: 0,
             logical_minimum: 0,
             logical_maximum: 0,
             report_size: 0,
             report_count: 0,
+            usages: Vec::new(),
+            usage_minimum: 0,
+            usage_maximum: 0,
+            has_usage_range: false,
+            usage_range_page: None,
+            string_indexes: Vec::new(),
+            string_minimum: 0,
+            string_maximum: 0,
+            has_string_range: false,
+            collection_depth: 0,
+            keyboard_ids: Vec::new(),
+            mouse_ids: Vec::new(),
+            strict: false,
+        }
+    }
+
+    /// Parser that also errors on unknown items and reports that don't end
+    /// on a byte boundary
+    pub fn new_strict() -> Self {
+        DescriptorParser {
+            strict: true,
+            ..Self::new()
         }
     }
 
@@ -141,6 +403,7 @@ impl DescriptorParser {
     pub fn parse(&mut self, data: &[u8]) -> Result<(), ParseError> {
         let mut i = 0;
         while i < data.len() {
+            let item_start = i;
             let item_header = data[i];
             i += 1;
 
@@ -149,17 +412,22 @@ impl DescriptorParser {
             let item_type = (item_header >> 2) & 0x03;
             let tag = (item_header >> 4) & 0x0F;
 
-            // Handle long items (rare)
-            let actual_size = if size == 3 {
-                if i >= data.len() {
+            if self.strict && (item_header == 0xFE || !is_known_item(item_type, tag)) {
+                return Err(ParseError::UnknownItem(item_start));
+            }
+
+            // Long items (rare) carry their own size and tag; skip them
+            if item_header == 0xFE {
+                if i + 1 >= data.len() {
                     return Err(ParseError::UnexpectedEnd);
                 }
                 let long_size = data[i] as usize;
-                i += 1;
-                long_size
-            } else {
-                size
-            };
+                i += 2 + long_size;
+                continue;
+            }
+
+            // Short item size code 3 means 4 data bytes
+            let actual_size = if size == 3 { 4 } else { size };
 
             // Extract data value
             if i + actual_size > data.len() {
@@ -181,12 +449,20 @@ impl DescriptorParser {
             // Process item based on type and tag
             match item_type {
                 0 => self.handle_main_item(tag, value)?,
-                1 => self.handle_global_item(tag, value)?,
-                2 => self.handle_local_item(tag, value)?,
+                1 => self.handle_global_item(tag, value, actual_size)?,
+                2 => self.handle_local_item(tag, value, actual_size)?,
                 _ => {} // Reserved
             }
         }
 
+        // A Collection left open means the descriptor was cut short or is malformed
+        if self.collection_depth != 0 {
+            return Err(self.unbalanced());
+        }
+        if self.strict && !self.current_bit_offset.is_multiple_of(8) {
+            return Err(ParseError::UnalignedReport);
+        }
+
         // Detect device types
         self.detect_device_types();
 
@@ -195,69 +471,167 @@ impl DescriptorParser {
 
     /// Handle Main Items (Input, Output, Feature, Collection, End Collection)
     fn handle_main_item(&mut self, tag: u8, value: u32) -> Result<(), ParseError> {
-        match tag {
+        let result = match tag {
             0x08 => self.add_input_item(value),      // Input
             0x09 => self.add_output_item(value),     // Output
             0x0B => self.add_feature_item(value),    // Feature
             0x0A => self.handle_collection(value),   // Collection
             0x0C => self.handle_end_collection(),    // End Collection
             _ => Ok(()),
-        }
+        };
+
+        // Local items only apply to the main item that follows them
+        self.reset_local_state();
+        result
     }
 
     /// Handle Global Items (Usage Page, Logical Min/Max, Report Size, etc.)
-    fn handle_global_item(&mut self, tag: u8, value: u32) -> Result<(), ParseError> {
+    fn handle_global_item(&mut self, tag: u8, value: u32, size: usize) -> Result<(), ParseError> {
+        let bits = (size as u32 * 8).clamp(8, 32);
         match tag {
             0x00 => self.current_usage_page = value as u16,
-            0x01 => self.logical_minimum = sign_extend(value, 32),
-            0x02 => self.logical_maximum = sign_extend(value, 32),
+            0x01 => self.logical_minimum = sign_extend(value, bits),
+            0x02 => self.logical_maximum = sign_extend(value, bits),
             0x07 => self.report_size = value as u8,
             0x09 => self.report_count = value as u8,
-            0x08 => self.current_report_id = value as u8,
+            0x08 => {
+                // The report being left must have filled whole bytes
+                if self.strict && !self.current_bit_offset.is_multiple_of(8) {
+                    return Err(ParseError::UnalignedReport);
+                }
+                self.current_report_id = value as u8;
+            }
             _ => {}
         }
         Ok(())
     }
 
-    /// Handle Local Items (Usage, Usage Min/Max)
-    fn handle_local_item(&mut self, tag: u8, value: u32) -> Result<(), ParseError> {
+    /// Handle Local Items (Usage, Usage Min/Max, String Index, String Min/Max)
+    fn handle_local_item(&mut self, tag: u8, value: u32, size: usize) -> Result<(), ParseError> {
         match tag {
-            0x00 => self.current_usage = value as u16,
+            0x00 => {
+                // A 4-byte Usage is extended: usage page in the high word.
+                // Shorter usages pick up the Usage Page global.
+                let usage = if size == 4 { value } else { value & 0xFFFF };
+                // Extra usages beyond the queue repeat the last one anyway
+                let _ = self.usages.push(usage);
+            }
+            0x01 => {
+                self.usage_minimum = value as u16;
+                self.has_usage_range = true;
+                if size == 4 && value >> 16 != 0 {
+                    self.usage_range_page = Some((value >> 16) as u16);
+                }
+            }
+            0x02 => {
+                self.usage_maximum = value as u16;
+                self.has_usage_range = true;
+            }
+            0x07 => {
+                let _ = self.string_indexes.push(value as u8);
+            }
+            0x08 => {
+                self.string_minimum = value as u8;
+                self.has_string_range = true;
+            }
+            0x09 => {
+                self.string_maximum = value as u8;
+                self.has_string_range = true;
+            }
             _ => {}
         }
         Ok(())
     }
 
+    /// Usage for the Nth field of the current main item
+    /// Explicit Usage items are consumed in order (the last one repeats),
+    /// otherwise Usage Minimum/Maximum is walked
+    fn usage_for_field(&self, index: u8) -> Usage {
+        let queued = self.usages.get(index as usize).or(self.usages.last());
+        let (page, id) = match queued {
+            Some(&usage) if usage >> 16 != 0 => ((usage >> 16) as u16, usage as u16),
+            Some(&usage) => (self.current_usage_page, usage as u16),
+            None if self.has_usage_range => {
+                let max = self.usage_maximum.max(self.usage_minimum);
+                let page = self.usage_range_page.unwrap_or(self.current_usage_page);
+                (page, self.usage_minimum.saturating_add(index as u16).min(max))
+            }
+            None => (self.current_usage_page, 0),
+        };
+
+        Usage {
+            page: UsagePage::from(page),
+            id,
+        }
+    }
+
+    /// String index for the Nth field of the current main item
+    /// Walked the same way as usages: String Index items in order (the last
+    /// one repeats), otherwise String Minimum/Maximum
+    fn string_for_field(&self, index: u8) -> Option<u8> {
+        if let Some(&string) = self.string_indexes.get(index as usize).or(self.string_indexes.last()) {
+            return Some(string);
+        }
+        if self.has_string_range {
+            let max = self.string_maximum.max(self.string_minimum);
+            return Some(self.string_minimum.saturating_add(index).min(max));
+        }
+        None
+    }
+
+    fn reset_local_state(&mut self) {
+        self.usages.clear();
+        self.usage_minimum = 0;
+        self.usage_maximum = 0;
+        self.has_usage_range = false;
+        self.usage_range_page = None;
+        self.string_indexes.clear();
+        self.string_minimum = 0;
+        self.string_maximum = 0;
+        self.has_string_range = false;
+    }
+
     /// Add an Input item (data from device to host)
     fn add_input_item(&mut self, flags: u32) -> Result<(), ParseError> {
         let is_constant = (flags & 0x01) != 0;
         let is_relative = (flags & 0x04) != 0;
         let is_array = (flags & 0x02) == 0; // Variable = not array
 
-        // Skip constant fields (padding)
+        // A zero-count item declares no data: no field, no gap, no report size
+        if self.report_count == 0 {
+            return Ok(());
+        }
+
+        // Constant fields are padding: remembered as a gap, not as fields
         if is_constant {
-            self.current_bit_offset += (self.report_size as u16) * (self.report_count as u16);
+            let size = (self.report_size as u16) * (self.report_count as u16);
+            if size > 0 {
+                let _ = self.descriptor.padding.push((self.current_report_id, self.current_bit_offset, size));
+            }
+            self.current_bit_offset += size;
             return Ok(());
         }
 
         // Add fields
-        for _ in 0..self.report_count {
+        for index in 0..self.report_count {
             let field = ReportField {
                 report_type: ReportType::Input,
                 report_id: self.current_report_id,
-                usage: Usage {
-                    page: UsagePage::from(self.current_usage_page),
-                    id: self.current_usage,
-                },
+                usage: self.usage_for_field(index),
                 bit_offset: self.current_bit_offset,
                 bit_size: self.report_size,
                 logical_min: self.logical_minimum,
                 logical_max: self.logical_maximum,
                 is_relative,
                 is_array,
+                string_index: self.string_for_field(index),
             };
 
-            self.descriptor.fields.push(field).map_err(|_| ParseError::TooManyFields)?;
+            // Past the field limit the field is dropped, but still classified
+            self.classify_field(&field);
+            if self.descriptor.fields.push(field).is_err() {
+                self.descriptor.truncated = true;
+            }
             self.current_bit_offset += self.report_size as u16;
         }
 
@@ -269,6 +643,9 @@ impl DescriptorParser {
 
     /// Add an Output item (data from host to device)
     fn add_output_item(&mut self, _flags: u32) -> Result<(), ParseError> {
+        if self.report_count == 0 {
+            return Ok(());
+        }
         self.current_bit_offset += (self.report_size as u16) * (self.report_count as u16);
         self.update_report_size(ReportType::Output);
         Ok(())
@@ -281,14 +658,28 @@ impl DescriptorParser {
     }
 
     fn handle_collection(&mut self, _flags: u32) -> Result<(), ParseError> {
-        // Collections group related items, but we don't need deep tracking for now
+        // Collections group related items; only the nesting depth is tracked
+        self.collection_depth = self.collection_depth.saturating_add(1);
         Ok(())
     }
 
     fn handle_end_collection(&mut self) -> Result<(), ParseError> {
+        if self.collection_depth == 0 {
+            return Err(self.unbalanced());
+        }
+        self.collection_depth -= 1;
         Ok(())
     }
 
+    /// Error for unbalanced collections: strict parses name the problem
+    fn unbalanced(&self) -> ParseError {
+        if self.strict {
+            ParseError::UnbalancedCollection
+        } else {
+            ParseError::InvalidData
+        }
+    }
+
     /// Update report size tracking
     fn update_report_size(&mut self, report_type: ReportType) {
         let size_bits = self.current_bit_offset;
@@ -308,25 +699,45 @@ impl DescriptorParser {
         }
     }
 
-    /// Detect device types based on usage pages
-    fn detect_device_types(&mut self) {
-        for field in &self.descriptor.fields {
-            match field.usage.page {
-                UsagePage::Keyboard => self.descriptor.is_keyboard = true,
-                UsagePage::GenericDesktop => {
-                    // Mouse usage IDs: 0x30=X, 0x31=Y, 0x38=Wheel
-                    if field.usage.id == 0x30 || field.usage.id == 0x31 || field.usage.id == 0x38 {
-                        self.descriptor.is_mouse = true;
-                    }
+    /// Detect device types based on the usage page of an input field
+    fn classify_field(&mut self, field: &ReportField) {
+        match field.usage.page {
+            UsagePage::Keyboard => {
+                self.descriptor.is_keyboard = true;
+                if !self.keyboard_ids.contains(&field.report_id) {
+                    let _ = self.keyboard_ids.push(field.report_id);
                 }
-                UsagePage::Button | UsagePage::GameControls => {
-                    self.descriptor.is_gamepad = true;
+            }
+            // Mouse usage IDs: 0x30=X, 0x31=Y, 0x38=Wheel
+            UsagePage::GenericDesktop if matches!(field.usage.id, 0x30 | 0x31 | 0x38) => {
+                self.descriptor.is_mouse = true;
+                if !self.mouse_ids.contains(&field.report_id) {
+                    let _ = self.mouse_ids.push(field.report_id);
                 }
-                _ => {}
             }
+            UsagePage::Button | UsagePage::GameControls => {
+                self.descriptor.is_gamepad = true;
+            }
+            UsagePage::Consumer => self.descriptor.is_consumer = true,
+            UsagePage::Digitizer => {
+                self.descriptor.is_digitizer = true;
+                match field.usage.id {
+                    0x51 => self.descriptor.contact_ids = self.descriptor.contact_ids.saturating_add(1),
+                    0x54 => self.descriptor.has_contact_count = true,
+                    _ => {}
+                }
+            }
+            _ => {}
         }
     }
 
+    /// Finish device type detection once every field has been classified
+    fn detect_device_types(&mut self) {
+        // Both classes in a single report is more likely a misparse than a composite
+        self.descriptor.is_composite = self.keyboard_ids.iter()
+            .any(|k| self.mouse_ids.iter().any(|m| m != k));
+    }
+
     /// Consume parser and return descriptor
     pub fn into_descriptor(self) -> HidDescriptor {
         self.descriptor
@@ -337,8 +748,28 @@ impl DescriptorParser {
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ParseError {
     UnexpectedEnd,
-    TooManyFields,
     InvalidData,
+    /// Descriptor cache is full and every entry is pinned
+    CacheFull,
+    /// Strict parse: reserved, long or undefined item at this byte offset
+    UnknownItem(usize),
+    /// Strict parse: End Collection without a Collection, or one left open
+    /// (the normal parse reports InvalidData)
+    UnbalancedCollection,
+    /// Strict parse: a report's fields don't add up to whole bytes
+    UnalignedReport,
+    /// Descriptor of this many bytes is too short to describe a device
+    TooShort(usize),
+}
+
+/// Whether a short item's type and tag are defined by the HID spec
+fn is_known_item(item_type: u8, tag: u8) -> bool {
+    match item_type {
+        0 => matches!(tag, 0x08..=0x0C),                 // Input..End Collection
+        1 => tag <= 0x0B,                                // Usage Page..Pop
+        2 => matches!(tag, 0x00..=0x05 | 0x07..=0x0A),   // Usage..Delimiter
+        _ => false,
+    }
 }
 
 /// Sign-extend a value to i32
@@ -390,4 +821,452 @@ mod tests {
         assert!(desc.is_mouse);
         assert!(!desc.is_keyboard);
     }
+
+    #[test]
+    fn test_usages_assigned_per_field() {
+        let descriptor = [
+            0x05, 0x09,        // Usage Page (Button)
+            0x19, 0x01,        // Usage Minimum (Button 1)
+            0x29, 0x03,        // Usage Maximum (Button 3)
+            0x95, 0x03,        // Report Count (3)
+            0x75, 0x01,        // Report Size (1)
+            0x81, 0x02,        // Input (Data, Variable, Absolute)
+            0x95, 0x01,        // Report Count (1)
+            0x75, 0x05,        // Report Size (5)
+            0x81, 0x03,        // Input (Constant) - padding
+            0x05, 0x01,        // Usage Page (Generic Desktop)
+            0x09, 0x30,        // Usage (X)
+            0x09, 0x31,        // Usage (Y)
+            0x15, 0x81,        // Logical Minimum (-127)
+            0x25, 0x7F,        // Logical Maximum (127)
+            0x75, 0x08,        // Report Size (8)
+            0x95, 0x02,        // Report Count (2)
+            0x81, 0x06,        // Input (Data, Variable, Relative)
+        ];
+
+        let mut parser = DescriptorParser::new();
+        parser.parse(&descriptor).unwrap();
+        let desc = parser.into_descriptor();
+
+        let ids: Vec<u16, 8> = desc.fields.iter().map(|f| f.usage.id).collect();
+        assert_eq!(&ids[..], &[1, 2, 3, 0x30, 0x31]);
+
+        let x = desc.find_input(UsagePage::GenericDesktop, 0x30).unwrap();
+        assert_eq!(x.bit_offset, 8);
+        assert_eq!(x.logical_min, -127);
+        assert_eq!(x.logical_max, 127);
+        assert!(desc.find_input(UsagePage::GenericDesktop, 0x38).is_none());
+    }
+
+    #[test]
+    fn test_string_index_stored_on_field() {
+        let descriptor = [
+            0x05, 0x01,        // Usage Page (Generic Desktop)
+            0x09, 0x30,        // Usage (X)
+            0x79, 0x04,        // String Index (4)
+            0x09, 0x31,        // Usage (Y)
+            0x79, 0x05,        // String Index (5)
+            0x15, 0x81,        // Logical Minimum (-127)
+            0x25, 0x7F,        // Logical Maximum (127)
+            0x75, 0x08,        // Report Size (8)
+            0x95, 0x02,        // Report Count (2)
+            0x81, 0x06,        // Input (Data, Variable, Relative)
+            0x09, 0x38,        // Usage (Wheel)
+            0x95, 0x01,        // Report Count (1)
+            0x81, 0x06,        // Input (Data, Variable, Relative)
+            0x05, 0x09,        // Usage Page (Button)
+            0x19, 0x01,        // Usage Minimum (Button 1)
+            0x29, 0x03,        // Usage Maximum (Button 3)
+            0x89, 0x10,        // String Minimum (16)
+            0x99, 0x12,        // String Maximum (18)
+            0x75, 0x01,        // Report Size (1)
+            0x95, 0x03,        // Report Count (3)
+            0x81, 0x02,        // Input (Data, Variable, Absolute)
+        ];
+
+        let mut parser = DescriptorParser::new();
+        parser.parse(&descriptor).unwrap();
+        let desc = parser.into_descriptor();
+
+        let strings: Vec<Option<u8>, 8> = desc.fields.iter().map(|f| f.string_index).collect();
+        assert_eq!(&strings[..], &[Some(4), Some(5), None, Some(16), Some(17), Some(18)]);
+    }
+
+    /// Mouse descriptor from test_simple_mouse_descriptor
+    const MOUSE: [u8; 50] = [
+        0x05, 0x01, 0x09, 0x02, 0xA1, 0x01, 0x09, 0x01, 0xA1, 0x00,
+        0x05, 0x09, 0x19, 0x01, 0x29, 0x03, 0x15, 0x00, 0x25, 0x01,
+        0x95, 0x03, 0x75, 0x01, 0x81, 0x02, 0x95, 0x01, 0x75, 0x05,
+        0x81, 0x03, 0x05, 0x01, 0x09, 0x30, 0x09, 0x31, 0x15, 0x81,
+        0x25, 0x7F, 0x75, 0x08, 0x95, 0x02, 0x81, 0x06, 0xC0, 0xC0,
+    ];
+
+    #[test]
+    fn test_padding_after_buttons_is_a_gap() {
+        let mut parser = DescriptorParser::new();
+        parser.parse(&MOUSE).unwrap();
+        let desc = parser.into_descriptor();
+
+        let gaps: Vec<(u16, u16), 4> = desc.report_gaps(0).collect();
+        assert_eq!(&gaps[..], &[(3, 5)]);
+        assert_eq!(desc.report_gaps(1).count(), 0);
+
+        // Fields and gaps cover the 3-byte report without overlap
+        let bits: u16 = desc.fields.iter().map(|f| f.bit_size as u16).sum::<u16>()
+            + gaps.iter().map(|&(_, size)| size).sum::<u16>();
+        assert_eq!(bits, 24);
+    }
+
+    #[test]
+    fn test_absolute_pointer_detection() {
+        let mut parser = DescriptorParser::new();
+        parser.parse(&MOUSE).unwrap();
+        assert!(!parser.into_descriptor().is_absolute_pointer());
+
+        // Same layout with X/Y as Data,Var,Abs
+        let mut absolute = MOUSE;
+        absolute[47] = 0x02;
+        let mut parser = DescriptorParser::new();
+        parser.parse(&absolute).unwrap();
+        assert!(parser.into_descriptor().is_absolute_pointer());
+
+        // An absolute X alone is not a pointer
+        let x_only = [0x05, 0x01, 0x09, 0x30, 0x75, 0x08, 0x95, 0x01, 0x81, 0x02];
+        let mut parser = DescriptorParser::new();
+        parser.parse(&x_only).unwrap();
+        assert!(!parser.into_descriptor().is_absolute_pointer());
+    }
+
+    #[test]
+    fn test_absolute_report_layout() {
+        let mut absolute = MOUSE;
+        absolute[47] = 0x02;
+        let mut parser = DescriptorParser::new();
+        parser.parse(&absolute).unwrap();
+        let desc = parser.into_descriptor();
+        assert_eq!(desc.input_report_len(0), 3);
+
+        // Buttons 1 and 3 held; button bits, padding, then X and Y
+        let mut out = [0xAAu8; 5];
+        assert_eq!(desc.absolute_report(0x05, 0x12, 0x34, &mut out), Some(3));
+        assert_eq!(&out[..3], &[0x05, 0x12, 0x34]);
+        assert_eq!(desc.absolute_report(0, 0, 0, &mut out[..2]), None);
+    }
+
+    #[test]
+    fn test_strict_accepts_well_formed() {
+        assert_eq!(DescriptorParser::new_strict().parse(&MOUSE), Ok(()));
+    }
+
+    #[test]
+    fn test_strict_rejects_malformed() {
+        // Padding removed: 3 button bits then X/Y
+        let mut unaligned = Vec::<u8, 64>::from_slice(&MOUSE[..26]).unwrap();
+        unaligned.extend_from_slice(&MOUSE[32..]).unwrap();
+        assert_eq!(DescriptorParser::new_strict().parse(&unaligned), Err(ParseError::UnalignedReport));
+
+        // Reserved local tag 6 at offset 2
+        let unknown = [0x05, 0x01, 0x69, 0x00];
+        assert_eq!(DescriptorParser::new_strict().parse(&unknown), Err(ParseError::UnknownItem(2)));
+        assert_eq!(DescriptorParser::new().parse(&unknown), Ok(()));
+    }
+
+    #[test]
+    fn test_unbalanced_collections_rejected() {
+        // Last End Collection missing
+        let open = &MOUSE[..MOUSE.len() - 1];
+        assert_eq!(DescriptorParser::new().parse(open), Err(ParseError::InvalidData));
+        assert_eq!(DescriptorParser::new_strict().parse(open), Err(ParseError::UnbalancedCollection));
+
+        // One End Collection too many
+        let mut extra = Vec::<u8, 64>::from_slice(&MOUSE).unwrap();
+        extra.push(0xC0).unwrap();
+        assert_eq!(DescriptorParser::new().parse(&extra), Err(ParseError::InvalidData));
+        assert_eq!(DescriptorParser::new_strict().parse(&extra), Err(ParseError::UnbalancedCollection));
+
+        // Extra End Collection before any Collection
+        assert_eq!(DescriptorParser::new().parse(&[0xC0, 0xA1, 0x01, 0xC0]), Err(ParseError::InvalidData));
+    }
+
+    #[test]
+    fn test_sign_extend_by_item_size() {
+        assert_eq!(sign_extend(0x81, 8), -127);
+        assert_eq!(sign_extend(0x7FFF, 16), 32767);
+        assert_eq!(sign_extend(0x8000, 16), -32768);
+        assert_eq!(sign_extend(0xFFFF_FFFF, 32), -1);
+    }
+
+    #[test]
+    fn test_extended_usage_overrides_page() {
+        let descriptor = [
+            0x05, 0x09,                    // Usage Page (Button)
+            0x0B, 0x38, 0x00, 0x01, 0x00,  // Usage (Generic Desktop: Wheel), 32-bit
+            0x09, 0x01,                    // Usage (Button 1), 8-bit
+            0x15, 0x81,                    // Logical Minimum (-127)
+            0x25, 0x7F,                    // Logical Maximum (127)
+            0x75, 0x08,                    // Report Size (8)
+            0x95, 0x02,                    // Report Count (2)
+            0x81, 0x06,                    // Input (Data, Variable, Relative)
+        ];
+
+        let mut parser = DescriptorParser::new();
+        parser.parse(&descriptor).unwrap();
+        let desc = parser.into_descriptor();
+
+        assert_eq!(desc.fields.len(), 2);
+        assert_eq!(desc.fields[0].usage.page, UsagePage::GenericDesktop);
+        assert_eq!(desc.fields[0].usage.id, 0x38);
+        // The override only applies to the extended usage itself
+        assert_eq!(desc.fields[1].usage.page, UsagePage::Button);
+        assert_eq!(desc.fields[1].usage.id, 0x01);
+        assert!(desc.is_mouse);
+    }
+
+    #[test]
+    fn test_vendor_usage_page_is_unknown() {
+        let descriptor = [
+            0x06, 0x00, 0xFF,              // Usage Page (Vendor 0xFF00), 16-bit
+            0x09, 0x30,                    // Usage (0x30, X on Generic Desktop)
+            0x0B, 0x38, 0x00, 0x02, 0xFF,  // Usage (Vendor 0xFF02: 0x38), 32-bit
+            0x15, 0x00,                    // Logical Minimum (0)
+            0x26, 0xFF, 0x00,              // Logical Maximum (255)
+            0x75, 0x08,                    // Report Size (8)
+            0x95, 0x02,                    // Report Count (2)
+            0x81, 0x02,                    // Input (Data, Variable, Absolute)
+            0x1B, 0x01, 0x00, 0x01, 0xFF,  // Usage Minimum (Vendor 0xFF01: 1), 32-bit
+            0x29, 0x02,                    // Usage Maximum (2)
+            0x81, 0x02,                    // Input (Data, Variable, Absolute)
+        ];
+
+        let mut parser = DescriptorParser::new();
+        parser.parse(&descriptor).unwrap();
+        let desc = parser.into_descriptor();
+
+        let pages: Vec<(UsagePage, u16), 4> = desc.fields.iter().map(|f| (f.usage.page, f.usage.id)).collect();
+        assert_eq!(&pages[..], &[
+            (UsagePage::Unknown(0xFF00), 0x30),
+            (UsagePage::Unknown(0xFF02), 0x38),
+            (UsagePage::Unknown(0xFF01), 0x01),
+            (UsagePage::Unknown(0xFF01), 0x02),
+        ]);
+        assert_eq!(u16::from(UsagePage::Unknown(0xFF00)), 0xFF00);
+        assert!(!desc.is_mouse && !desc.is_keyboard && !desc.is_gamepad);
+        assert!(!desc.is_consumer && !desc.is_digitizer);
+    }
+
+    #[test]
+    fn test_too_many_fields_truncates_but_detects() {
+        let descriptor = [
+            0x06, 0x00, 0xFF,  // Usage Page (Vendor 0xFF00)
+            0x09, 0x01,        // Usage (1)
+            0x75, 0x01,        // Report Size (1)
+            0x95, 0x46,        // Report Count (70)
+            0x81, 0x02,        // Input (Data, Variable, Absolute)
+            0x05, 0x01,        // Usage Page (Generic Desktop)
+            0x09, 0x30,        // Usage (X)
+            0x09, 0x31,        // Usage (Y)
+            0x75, 0x08,        // Report Size (8)
+            0x95, 0x02,        // Report Count (2)
+            0x81, 0x06,        // Input (Data, Variable, Relative)
+        ];
+
+        let mut parser = DescriptorParser::new();
+        assert!(parser.parse(&descriptor).is_ok());
+        let desc = parser.into_descriptor();
+
+        assert!(desc.truncated);
+        assert_eq!(desc.fields.len(), MAX_REPORT_ITEMS);
+        // X/Y were dropped but still seen
+        assert!(desc.is_mouse);
+        assert_eq!(&desc.input_report_sizes[..], &[(0, 11)]);
+
+        let mut parser = DescriptorParser::new();
+        parser.parse(&MOUSE).unwrap();
+        assert!(!parser.into_descriptor().truncated);
+    }
+
+    #[test]
+    fn test_zero_count_input_is_skipped() {
+        let descriptor = [
+            0x05, 0x01,        // Usage Page (Generic Desktop)
+            0x75, 0x08,        // Report Size (8)
+            0x95, 0x01,        // Report Count (1)
+            0x09, 0x30,        // Usage (X)
+            0x81, 0x06,        // Input (Data, Variable, Relative)
+            0x95, 0x00,        // Report Count (0)
+            0x81, 0x06,        // Input (Data, Variable, Relative)
+            0x81, 0x01,        // Input (Constant)
+            0x85, 0x02,        // Report ID (2)
+            0x81, 0x06,        // Input (Data, Variable, Relative)
+            0x85, 0x00,        // Report ID (0)
+            0x95, 0x01,        // Report Count (1)
+            0x09, 0x31,        // Usage (Y)
+            0x81, 0x06,        // Input (Data, Variable, Relative)
+        ];
+
+        let mut parser = DescriptorParser::new();
+        parser.parse(&descriptor).unwrap();
+        let desc = parser.into_descriptor();
+
+        assert_eq!(desc.fields.len(), 2);
+        assert_eq!(desc.fields[1].usage.id, 0x31);
+        assert_eq!(desc.fields[1].bit_offset, 8);
+        assert!(desc.padding.is_empty());
+        assert_eq!(&desc.input_report_sizes[..], &[(0, 2)]);
+    }
+
+    #[test]
+    fn test_composite_keyboard_mouse() {
+        let descriptor = [
+            0x05, 0x01,        // Usage Page (Generic Desktop)
+            0x09, 0x06,        // Usage (Keyboard)
+            0xA1, 0x01,        // Collection (Application)
+            0x85, 0x01,        //   Report ID (1)
+            0x05, 0x07,        //   Usage Page (Keyboard)
+            0x19, 0xE0,        //   Usage Minimum (Left Control)
+            0x29, 0xE7,        //   Usage Maximum (Right GUI)
+            0x15, 0x00,        //   Logical Minimum (0)
+            0x25, 0x01,        //   Logical Maximum (1)
+            0x75, 0x01,        //   Report Size (1)
+            0x95, 0x08,        //   Report Count (8)
+            0x81, 0x02,        //   Input (Data, Variable, Absolute)
+            0xC0,              // End Collection
+            0x05, 0x01,        // Usage Page (Generic Desktop)
+            0x09, 0x02,        // Usage (Mouse)
+            0xA1, 0x01,        // Collection (Application)
+            0x85, 0x02,        //   Report ID (2)
+            0x09, 0x30,        //   Usage (X)
+            0x09, 0x31,        //   Usage (Y)
+            0x15, 0x81,        //   Logical Minimum (-127)
+            0x25, 0x7F,        //   Logical Maximum (127)
+            0x75, 0x08,        //   Report Size (8)
+            0x95, 0x02,        //   Report Count (2)
+            0x81, 0x06,        //   Input (Data, Variable, Relative)
+            0xC0,              // End Collection
+        ];
+
+        let mut parser = DescriptorParser::new();
+        parser.parse(&descriptor).unwrap();
+        let desc = parser.into_descriptor();
+
+        assert!(desc.is_keyboard);
+        assert!(desc.is_mouse);
+        assert!(desc.is_composite);
+        assert_eq!(desc.report_id_count(), 2);
+
+        // Report 2's X follows report 1's bits in parse order but starts its own report
+        let x = desc.find_input(UsagePage::GenericDesktop, 0x30).unwrap();
+        assert_eq!((x.bit_offset, desc.report_position(x)), (8, 0));
+        assert!(desc.uses_report_ids());
+
+        // Keys are read from report 1 only, after its ID byte
+        assert_eq!(desc.decode_keys(&[0x01, 0x05]), Some((0x05, Vec::new())));
+        assert_eq!(desc.decode_keys(&[0x02, 0x05, 0x00]), None);
+        assert_eq!(desc.decode_keys(&[0x01]), None);
+    }
+
+    #[test]
+    fn test_single_report_not_composite() {
+        let descriptor = [
+            0x05, 0x01,        // Usage Page (Generic Desktop)
+            0x09, 0x30,        // Usage (X)
+            0x15, 0x81,        // Logical Minimum (-127)
+            0x25, 0x7F,        // Logical Maximum (127)
+            0x75, 0x08,        // Report Size (8)
+            0x95, 0x01,        // Report Count (1)
+            0x81, 0x06,        // Input (Data, Variable, Relative)
+            0x05, 0x07,        // Usage Page (Keyboard)
+            0x09, 0x04,        // Usage (A)
+            0x81, 0x02,        // Input (Data, Variable, Absolute)
+        ];
+
+        let mut parser = DescriptorParser::new();
+        parser.parse(&descriptor).unwrap();
+        let desc = parser.into_descriptor();
+
+        assert!(desc.is_keyboard && desc.is_mouse);
+        assert!(!desc.is_composite);
+        assert_eq!(desc.report_id_count(), 1);
+    }
+
+    #[test]
+    fn test_consumer_and_digitizer_detection() {
+        let descriptor = [
+            0x05, 0x0C,        // Usage Page (Consumer)
+            0x09, 0xE9,        // Usage (Volume Increment)
+            0x15, 0x00,        // Logical Minimum (0)
+            0x25, 0x01,        // Logical Maximum (1)
+            0x75, 0x01,        // Report Size (1)
+            0x95, 0x01,        // Report Count (1)
+            0x81, 0x02,        // Input (Data, Variable, Absolute)
+            0x05, 0x0D,        // Usage Page (Digitizer)
+            0x09, 0x42,        // Usage (Tip Switch)
+            0x81, 0x02,        // Input (Data, Variable, Absolute)
+        ];
+
+        let mut parser = DescriptorParser::new();
+        parser.parse(&descriptor).unwrap();
+        let desc = parser.into_descriptor();
+
+        assert!(desc.is_consumer);
+        assert!(desc.is_digitizer);
+        assert!(!desc.is_mouse && !desc.is_keyboard);
+        assert_eq!(desc.max_contacts(), 0);
+    }
+
+    #[test]
+    fn test_two_contact_digitizer() {
+        let finger = [
+            0x09, 0x22,        //   Usage (Finger)
+            0xA1, 0x02,        //   Collection (Logical)
+            0x09, 0x42,        //     Usage (Tip Switch)
+            0x15, 0x00,        //     Logical Minimum (0)
+            0x25, 0x01,        //     Logical Maximum (1)
+            0x75, 0x01,        //     Report Size (1)
+            0x95, 0x01,        //     Report Count (1)
+            0x81, 0x02,        //     Input (Data, Variable, Absolute)
+            0x95, 0x07,        //     Report Count (7)
+            0x81, 0x03,        //     Input (Constant) - padding
+            0x09, 0x51,        //     Usage (Contact Identifier)
+            0x25, 0x0A,        //     Logical Maximum (10)
+            0x75, 0x08,        //     Report Size (8)
+            0x95, 0x01,        //     Report Count (1)
+            0x81, 0x02,        //     Input (Data, Variable, Absolute)
+            0x05, 0x01,        //     Usage Page (Generic Desktop)
+            0x09, 0x30,        //     Usage (X)
+            0x09, 0x31,        //     Usage (Y)
+            0x26, 0xFF, 0x7F,  //     Logical Maximum (32767)
+            0x75, 0x10,        //     Report Size (16)
+            0x95, 0x02,        //     Report Count (2)
+            0x81, 0x02,        //     Input (Data, Variable, Absolute)
+            0x05, 0x0D,        //     Usage Page (Digitizer)
+            0xC0,              //   End Collection
+        ];
+        let mut descriptor: Vec<u8, 128> = Vec::new();
+        descriptor.extend_from_slice(&[
+            0x05, 0x0D,        // Usage Page (Digitizer)
+            0x09, 0x04,        // Usage (Touch Screen)
+            0xA1, 0x01,        // Collection (Application)
+            0x85, 0x01,        //   Report ID (1)
+        ]).unwrap();
+        descriptor.extend_from_slice(&finger).unwrap();
+        descriptor.extend_from_slice(&finger).unwrap();
+        descriptor.extend_from_slice(&[
+            0x09, 0x54,        //   Usage (Contact Count)
+            0x25, 0x02,        //   Logical Maximum (2)
+            0x75, 0x08,        //   Report Size (8)
+            0x95, 0x01,        //   Report Count (1)
+            0x81, 0x02,        //   Input (Data, Variable, Absolute)
+            0xC0,              // End Collection
+        ]).unwrap();
+
+        let mut parser = DescriptorParser::new();
+        parser.parse(&descriptor).unwrap();
+        let desc = parser.into_descriptor();
+
+        assert!(desc.is_digitizer);
+        assert!(desc.has_contact_count);
+        assert_eq!(desc.contact_ids, 2);
+        assert_eq!(desc.max_contacts(), 2);
+    }
 }
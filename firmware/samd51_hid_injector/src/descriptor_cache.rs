@@ -8,6 +8,10 @@ use crate::descriptor::{HidDescriptor, DescriptorParser, ParseError, MAX_DESCRIP
 /// Maximum number of cached device descriptors
 pub const MAX_CACHED_DEVICES: usize = 8;
 
+/// Shortest descriptor accepted: a Usage Page and a Usage item
+/// Anything shorter would be cached as a device with nothing to match on
+pub const MIN_DESCRIPTOR_SIZE: usize = 4;
+
 /// Cached descriptor entry
 #[derive(Clone)]
 pub struct CachedDescriptor {
@@ -16,12 +20,17 @@ pub struct CachedDescriptor {
     pub descriptor: HidDescriptor,
     pub raw_descriptor: Vec<u8, MAX_DESCRIPTOR_SIZE>,
     pub timestamp: u32,  // For LRU eviction
+    pub pinned: bool,    // Never evicted while set
+    pub sensitivity: u16, // Percent applied to moves while this is the target
 }
 
 /// Descriptor cache manager
 pub struct DescriptorCache {
     entries: Vec<CachedDescriptor, MAX_CACHED_DEVICES>,
     current_time: u32,
+    // Lifetime counters
+    total_adds: u32,
+    total_evictions: u32,
 }
 
 impl DescriptorCache {
@@ -30,6 +39,8 @@ impl DescriptorCache {
         DescriptorCache {
             entries: Vec::new(),
             current_time: 0,
+            total_adds: 0,
+            total_evictions: 0,
         }
     }
 
@@ -37,6 +48,10 @@ impl DescriptorCache {
     pub fn add(&mut self, device_address: u8, interface_num: u8, raw_descriptor: &[u8]) 
         -> Result<(), ParseError> {
         
+        if raw_descriptor.len() < MIN_DESCRIPTOR_SIZE {
+            return Err(ParseError::TooShort(raw_descriptor.len()));
+        }
+        
         // Parse descriptor
         let mut parser = DescriptorParser::new();
         parser.parse(raw_descriptor)?;
@@ -49,6 +64,7 @@ impl DescriptorCache {
         }
 
         self.current_time += 1;
+        self.total_adds = self.total_adds.wrapping_add(1);
 
         // Check if already exists
         if let Some(entry) = self.entries.iter_mut()
@@ -67,11 +83,12 @@ impl DescriptorCache {
             descriptor,
             raw_descriptor: raw_vec,
             timestamp: self.current_time,
+            pinned: false,
+            sensitivity: 100,
         };
 
-        if self.entries.is_full() {
-            // Evict least recently used
-            self.evict_lru();
+        if self.entries.is_full() && !self.evict_lru() {
+            return Err(ParseError::CacheFull);
         }
 
         self.entries.push(entry).map_err(|_| ParseError::InvalidData)?;
@@ -92,24 +109,149 @@ impl DescriptorCache {
         }
     }
 
+    /// Raw descriptor bytes as received, without touching the LRU timestamp
+    pub fn raw(&self, device_address: u8, interface_num: u8) -> Option<&[u8]> {
+        self.entries.iter()
+            .find(|e| e.device_address == device_address && e.interface_num == interface_num)
+            .map(|e| &e.raw_descriptor[..])
+    }
+
+    /// Iterate cached entries without touching their LRU timestamps
+    pub fn iter(&self) -> impl Iterator<Item = &CachedDescriptor> {
+        self.entries.iter()
+    }
+
     /// Check if cache is empty
     pub fn is_empty(&self) -> bool {
         self.entries.is_empty()
     }
 
-    /// Evict least recently used entry
-    fn evict_lru(&mut self) {
+    /// Keep an entry from being evicted; returns false if it isn't cached
+    pub fn pin(&mut self, device_address: u8, interface_num: u8) -> bool {
+        self.set_pinned(device_address, interface_num, true)
+    }
+
+    /// Make an entry evictable again; returns false if it isn't cached
+    pub fn unpin(&mut self, device_address: u8, interface_num: u8) -> bool {
+        self.set_pinned(device_address, interface_num, false)
+    }
+
+    /// Movement scale in percent for a cached device, without touching the LRU timestamp
+    pub fn sensitivity(&self, device_address: u8, interface_num: u8) -> Option<u16> {
+        self.entries.iter()
+            .find(|e| e.device_address == device_address && e.interface_num == interface_num)
+            .map(|e| e.sensitivity)
+    }
+
+    /// Set a cached device's movement scale; returns false if it isn't cached
+    /// Kept when the device's descriptor is re-sent
+    pub fn set_sensitivity(&mut self, device_address: u8, interface_num: u8, percent: u16) -> bool {
+        match self.entries.iter_mut()
+            .find(|e| e.device_address == device_address && e.interface_num == interface_num) {
+            Some(entry) => {
+                entry.sensitivity = percent;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn set_pinned(&mut self, device_address: u8, interface_num: u8, pinned: bool) -> bool {
+        match self.entries.iter_mut()
+            .find(|e| e.device_address == device_address && e.interface_num == interface_num) {
+            Some(entry) => {
+                entry.pinned = pinned;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Evict least recently used unpinned entry
+    /// Returns false if every entry is pinned
+    fn evict_lru(&mut self) -> bool {
         if let Some((idx, _)) = self.entries.iter()
             .enumerate()
+            .filter(|(_, e)| !e.pinned)
             .min_by_key(|(_, e)| e.timestamp) {
             self.entries.remove(idx);
+            self.total_evictions = self.total_evictions.wrapping_add(1);
+            return true;
+        }
+        false
+    }
+
+    /// Serialize every cached raw descriptor into `out`
+    /// Layout: [count] then per entry [addr][iface][len_lo][len_hi][raw...]
+    /// Returns the number of bytes written, or None if `out` is too small
+    pub fn export(&self, out: &mut [u8]) -> Option<usize> {
+        *out.first_mut()? = self.entries.len() as u8;
+        let mut len = 1;
+
+        for entry in &self.entries {
+            let raw = &entry.raw_descriptor;
+            let record = out.get_mut(len..len + 4 + raw.len())?;
+            record[0] = entry.device_address;
+            record[1] = entry.interface_num;
+            record[2..4].copy_from_slice(&(raw.len() as u16).to_le_bytes());
+            record[4..].copy_from_slice(raw);
+            len += record.len();
+        }
+
+        Some(len)
+    }
+
+    /// Add every descriptor from an export() blob, returning how many were added
+    /// All records are framed, re-parsed and checked to fit first; nothing is
+    /// added if any fails.
+    pub fn import(&mut self, blob: &[u8]) -> Result<usize, ParseError> {
+        let records = || ExportRecords::new(blob);
+        let count = blob.first().copied().ok_or(ParseError::UnexpectedEnd)? as usize;
+
+        let mut seen = 0;
+        for record in records() {
+            let (_, _, raw) = record?;
+            DescriptorParser::new().parse(raw)?;
+            seen += 1;
         }
+        if seen != count {
+            return Err(ParseError::InvalidData);
+        }
+
+        let mut new_entries = 0;
+        for (index, record) in records().enumerate() {
+            let (addr, iface, raw) = record?;
+            if raw.len() < MIN_DESCRIPTOR_SIZE {
+                return Err(ParseError::TooShort(raw.len()));
+            }
+            let cached = self.entries.iter()
+                .any(|e| e.device_address == addr && e.interface_num == iface);
+            let repeated = records().take(index)
+                .any(|r| matches!(r, Ok((a, i, _)) if a == addr && i == iface));
+            if !cached && !repeated {
+                new_entries += 1;
+            }
+        }
+        // Only unpinned entries can be evicted to make room
+        let pinned = self.entries.iter().filter(|e| e.pinned).count();
+        if new_entries > MAX_CACHED_DEVICES - pinned {
+            return Err(ParseError::CacheFull);
+        }
+
+        for record in records() {
+            let (addr, iface, raw) = record?;
+            self.add(addr, iface, raw)?;
+        }
+        Ok(count)
     }
 
     /// Get statistics about cached devices
     pub fn get_stats(&self) -> CacheStats {
         let mut stats = CacheStats {
             total_devices: self.entries.len(),
+            capacity: MAX_CACHED_DEVICES,
+            total_adds: self.total_adds,
+            total_evictions: self.total_evictions,
             keyboards: 0,
             mice: 0,
             gamepads: 0,
@@ -137,10 +279,48 @@ impl DescriptorCache {
     }
 }
 
+/// Iterator over the (addr, iface, raw) records of an export() blob
+struct ExportRecords<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> ExportRecords<'a> {
+    fn new(blob: &'a [u8]) -> Self {
+        ExportRecords { rest: blob.get(1..).unwrap_or(&[]) }
+    }
+}
+
+impl<'a> Iterator for ExportRecords<'a> {
+    type Item = Result<(u8, u8, &'a [u8]), ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        if self.rest.len() < 4 {
+            self.rest = &[];
+            return Some(Err(ParseError::UnexpectedEnd));
+        }
+
+        let len = u16::from_le_bytes([self.rest[2], self.rest[3]]) as usize;
+        let Some(raw) = self.rest.get(4..4 + len) else {
+            self.rest = &[];
+            return Some(Err(ParseError::UnexpectedEnd));
+        };
+        let record = (self.rest[0], self.rest[1], raw);
+        self.rest = &self.rest[4 + len..];
+        Some(Ok(record))
+    }
+}
+
 /// Cache statistics
 #[derive(Debug, Clone, Copy)]
 pub struct CacheStats {
     pub total_devices: usize,
+    pub capacity: usize,
+    /// Successful adds (including refreshes) over the cache's lifetime
+    pub total_adds: u32,
+    pub total_evictions: u32,
     pub keyboards: usize,
     pub mice: usize,
     pub gamepads: usize,
@@ -152,12 +332,15 @@ impl CacheStats {
     pub fn format(&self) -> heapless::String<128> {
         use core::fmt::Write;
         let mut s = heapless::String::new();
-        let _ = write!(s, "Devices:{} K:{} M:{} G:{} O:{}", 
+        let _ = write!(s, "Devices:{}/{} K:{} M:{} G:{} O:{} Adds:{} Evictions:{}", 
             self.total_devices,
+            self.capacity,
             self.keyboards,
             self.mice,
             self.gamepads,
-            self.other
+            self.other,
+            self.total_adds,
+            self.total_evictions
         );
         s
     }
@@ -215,6 +398,18 @@ mod tests {
         assert!(desc.is_mouse, "Descriptor should be recognized as mouse");
     }
 
+    #[test]
+    fn test_short_descriptor_rejected() {
+        let mut cache = DescriptorCache::new();
+        assert_eq!(cache.add(1, 0, &[]), Err(ParseError::TooShort(0)));
+        assert_eq!(cache.add(1, 0, &[0x05, 0x01]), Err(ParseError::TooShort(2)));
+        assert!(cache.is_empty());
+        assert_eq!(cache.get_stats().total_adds, 0);
+
+        assert!(cache.add(1, 0, &[0x05, 0x01, 0x09, 0x02]).is_ok());
+        assert!(cache.get(1, 0).is_some());
+    }
+
     #[test]
     fn test_cache_eviction() {
         let mut cache = DescriptorCache::new();
@@ -228,4 +423,155 @@ mod tests {
         // Should have evicted oldest entry
         assert_eq!(cache.entries.len(), MAX_CACHED_DEVICES);
     }
+
+    #[test]
+    fn test_pinned_entry_survives_eviction() {
+        let mut cache = DescriptorCache::new();
+        let descriptor = [0x05, 0x01, 0x09, 0x02];
+
+        for i in 0..MAX_CACHED_DEVICES {
+            cache.add(i as u8, 0, &descriptor).unwrap();
+        }
+        // Device 0 is the LRU entry, but pinned
+        assert!(cache.pin(0, 0));
+        assert!(!cache.pin(99, 0));
+
+        cache.add(100, 0, &descriptor).unwrap();
+        assert!(cache.get(0, 0).is_some());
+        assert!(cache.get(1, 0).is_none());
+        assert_eq!(cache.get_stats().total_evictions, 1);
+
+        assert!(cache.unpin(0, 0));
+        assert!(!cache.iter().any(|e| e.pinned));
+    }
+
+    #[test]
+    fn test_sensitivity_survives_update() {
+        let mut cache = DescriptorCache::new();
+        let descriptor = [0x05, 0x01, 0x09, 0x02];
+
+        cache.add(1, 0, &descriptor).unwrap();
+        assert_eq!(cache.sensitivity(1, 0), Some(100));
+        assert!(cache.set_sensitivity(1, 0, 80));
+        assert!(!cache.set_sensitivity(2, 0, 80));
+
+        cache.add(1, 0, &descriptor).unwrap();
+        assert_eq!(cache.sensitivity(1, 0), Some(80));
+        assert_eq!(cache.sensitivity(2, 0), None);
+    }
+
+    #[test]
+    fn test_add_fails_when_all_pinned() {
+        let mut cache = DescriptorCache::new();
+        let descriptor = [0x05, 0x01, 0x09, 0x02];
+
+        for i in 0..MAX_CACHED_DEVICES {
+            cache.add(i as u8, 0, &descriptor).unwrap();
+            cache.pin(i as u8, 0);
+        }
+        assert_eq!(cache.add(100, 0, &descriptor), Err(ParseError::CacheFull));
+        assert_eq!(cache.entries.len(), MAX_CACHED_DEVICES);
+
+        // Updating an existing pinned entry still works
+        assert!(cache.add(3, 0, &descriptor).is_ok());
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let mouse = [0x05, 0x01, 0x09, 0x30, 0x75, 0x08, 0x95, 0x01, 0x81, 0x06];
+        let keyboard = [0x05, 0x07, 0x09, 0x04, 0x75, 0x08, 0x95, 0x01, 0x81, 0x00];
+
+        let mut cache = DescriptorCache::new();
+        cache.add(3, 0, &mouse).unwrap();
+        cache.add(5, 1, &keyboard).unwrap();
+
+        let mut blob = [0u8; 64];
+        let len = cache.export(&mut blob).unwrap();
+        assert_eq!(len, 1 + 2 * (4 + 10));
+
+        let mut restored = DescriptorCache::new();
+        assert_eq!(restored.import(&blob[..len]), Ok(2));
+        assert_eq!(restored.entries.len(), 2);
+        for (addr, iface, raw) in [(3, 0, &mouse), (5, 1, &keyboard)] {
+            let entry = restored.entries.iter()
+                .find(|e| e.device_address == addr && e.interface_num == iface)
+                .unwrap();
+            assert_eq!(entry.raw_descriptor.as_slice(), raw);
+        }
+        assert!(restored.get(3, 0).unwrap().is_mouse);
+        assert!(restored.get(5, 1).unwrap().is_keyboard);
+
+        // Too small a buffer is refused rather than truncated
+        assert_eq!(cache.export(&mut blob[..20]), None);
+    }
+
+    #[test]
+    fn test_import_rejects_bad_blob() {
+        let mut cache = DescriptorCache::new();
+
+        // Count says two records, only one present
+        let blob = [2, 1, 0, 2, 0, 0x05, 0x01];
+        assert_eq!(cache.import(&blob), Err(ParseError::InvalidData));
+        // Length runs past the end
+        let blob = [1, 1, 0, 9, 0, 0x05, 0x01];
+        assert_eq!(cache.import(&blob), Err(ParseError::UnexpectedEnd));
+        // Descriptor that doesn't re-parse (truncated item)
+        let blob = [1, 1, 0, 1, 0, 0x05];
+        assert_eq!(cache.import(&blob), Err(ParseError::UnexpectedEnd));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_import_is_all_or_nothing_when_full() {
+        let descriptor = [0x05, 0x01, 0x09, 0x02];
+        let mut source = DescriptorCache::new();
+        source.add(100, 0, &descriptor).unwrap();
+        source.add(101, 0, &descriptor).unwrap();
+        let mut blob = [0u8; 64];
+        let len = source.export(&mut blob).unwrap();
+
+        // One free slot, two new entries: neither is added
+        let mut cache = DescriptorCache::new();
+        for i in 0..MAX_CACHED_DEVICES - 1 {
+            cache.add(i as u8, 0, &descriptor).unwrap();
+            cache.pin(i as u8, 0);
+        }
+        assert_eq!(cache.import(&blob[..len]), Err(ParseError::CacheFull));
+        assert_eq!(cache.entries.len(), MAX_CACHED_DEVICES - 1);
+        assert!(cache.raw(100, 0).is_none());
+
+        // Updating entries already cached needs no room
+        cache.add(100, 0, &descriptor).unwrap();
+        cache.pin(100, 0);
+        let mut update = DescriptorCache::new();
+        update.add(0, 0, &descriptor).unwrap();
+        update.add(100, 0, &descriptor).unwrap();
+        let len = update.export(&mut blob).unwrap();
+        assert_eq!(cache.import(&blob[..len]), Ok(2));
+    }
+
+    #[test]
+    fn test_stats_count_adds_and_evictions() {
+        let mut cache = DescriptorCache::new();
+        let descriptor = [0x05, 0x01, 0x09, 0x02];
+
+        for i in 0..MAX_CACHED_DEVICES {
+            cache.add(i as u8, 0, &descriptor).unwrap();
+        }
+        let stats = cache.get_stats();
+        assert_eq!(stats.total_adds, MAX_CACHED_DEVICES as u32);
+        assert_eq!(stats.total_evictions, 0);
+        assert_eq!(stats.capacity, MAX_CACHED_DEVICES);
+
+        // Refreshing an existing entry is an add but never evicts
+        cache.add(0, 0, &descriptor).unwrap();
+        assert_eq!(cache.get_stats().total_evictions, 0);
+
+        cache.add(100, 0, &descriptor).unwrap();
+        cache.add(101, 0, &descriptor).unwrap();
+        let stats = cache.get_stats();
+        assert_eq!(stats.total_adds, MAX_CACHED_DEVICES as u32 + 3);
+        assert_eq!(stats.total_evictions, 2);
+        assert_eq!(stats.total_devices, MAX_CACHED_DEVICES);
+    }
 }
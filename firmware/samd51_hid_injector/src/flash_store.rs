@@ -0,0 +1,441 @@
+//! Flash Store
+//! Serializes recoil patterns and device config into flash images and loads
+//! them back. Each image carries a CRC32 so a torn write or bit rot loads as
+//! empty instead of replaying garbage movements.
+//!
+//! Image layout (little-endian):
+//!   [magic:4][version:2][payload_len:2][crc32:4][payload...]
+//!   recoil payload = [count:1] then per pattern [name_len:1][name][flags:1][value_count:1][values:i16...]
+//!   recoil flags: bit 0 = absolute
+//!   config payload = [vid:2][pid:2][banner:1][serial_len:1][serial][product_len:1][product][manufacturer_len:1][manufacturer]
+
+use crate::recoil::RecoilManager;
+
+/// Marks a programmed recoil store ("NZRC")
+pub const STORE_MAGIC: [u8; 4] = *b"NZRC";
+pub const STORE_VERSION: u16 = 2;
+pub const STORE_HEADER_LEN: usize = 12;
+
+/// Recoil pattern flag bits
+const FLAG_ABSOLUTE: u8 = 0x01;
+
+/// Marks a programmed config region ("NZCF")
+pub const CONFIG_MAGIC: [u8; 4] = *b"NZCF";
+pub const CONFIG_VERSION: u16 = 4;
+
+/// Longest USB serial number accepted by nozen.serial
+pub const MAX_SERIAL_LEN: usize = 16;
+
+/// Serial number used until one is stored
+pub const DEFAULT_SERIAL: &str = "HID-INJ-001";
+
+/// Longest USB product or manufacturer string accepted by nozen.usb.strings
+pub const MAX_USB_STRING_LEN: usize = 32;
+
+/// Product and manufacturer strings used until others are stored
+pub const DEFAULT_PRODUCT: &str = "Cynthion HID Injector";
+pub const DEFAULT_MANUFACTURER: &str = "Great Scott Gadgets";
+
+/// USB vendor and product IDs used until others are stored
+pub const DEFAULT_VID: u16 = 0x1d50;
+pub const DEFAULT_PID: u16 = 0x615c;
+
+/// Bytes of flash holding the config image
+pub const CONFIG_IMAGE_SIZE: usize = STORE_HEADER_LEN + 5 + 3 + MAX_SERIAL_LEN + 2 * MAX_USB_STRING_LEN;
+
+/// USB identity kept in the config image, applied at enumeration,
+/// and whether the CDC startup banner is sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceConfig {
+    pub vid: u16,
+    pub pid: u16,
+    pub banner: bool,
+    pub serial: heapless::String<MAX_SERIAL_LEN>,
+    pub product: heapless::String<MAX_USB_STRING_LEN>,
+    pub manufacturer: heapless::String<MAX_USB_STRING_LEN>,
+}
+
+impl Default for DeviceConfig {
+    /// The built-in identity
+    fn default() -> Self {
+        DeviceConfig {
+            vid: DEFAULT_VID,
+            pid: DEFAULT_PID,
+            banner: true,
+            serial: heapless::String::try_from(DEFAULT_SERIAL).unwrap(),
+            product: heapless::String::try_from(DEFAULT_PRODUCT).unwrap(),
+            manufacturer: heapless::String::try_from(DEFAULT_MANUFACTURER).unwrap(),
+        }
+    }
+}
+
+impl DeviceConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StoreError {
+    BufferTooSmall,
+    BadMagic,        // Erased or never written
+    UnsupportedVersion,
+    Truncated,
+    CrcMismatch,
+    Malformed,
+}
+
+impl StoreError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StoreError::BufferTooSmall => "Buffer too small",
+            StoreError::BadMagic => "No store present",
+            StoreError::UnsupportedVersion => "Unsupported store version",
+            StoreError::Truncated => "Store truncated",
+            StoreError::CrcMismatch => "Store CRC mismatch",
+            StoreError::Malformed => "Store malformed",
+        }
+    }
+}
+
+/// CRC-32 (IEEE 802.3, reflected, as used by zlib)
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Write all patterns into `image`, returning the number of bytes used
+pub fn save_recoil_store(manager: &RecoilManager, image: &mut [u8]) -> Result<usize, StoreError> {
+    if image.len() < STORE_HEADER_LEN + 1 {
+        return Err(StoreError::BufferTooSmall);
+    }
+
+    let payload = &mut image[STORE_HEADER_LEN..];
+    let mut len = 0;
+    payload[len] = manager.count() as u8;
+    len += 1;
+
+    for pattern in manager.list_patterns() {
+        let name = pattern.name.as_bytes();
+        let needed = 3 + name.len() + pattern.steps.len() * 2;
+        if len + needed > payload.len() {
+            return Err(StoreError::BufferTooSmall);
+        }
+
+        payload[len] = name.len() as u8;
+        len += 1;
+        payload[len..len + name.len()].copy_from_slice(name);
+        len += name.len();
+        payload[len] = if pattern.absolute { FLAG_ABSOLUTE } else { 0 };
+        len += 1;
+        payload[len] = pattern.steps.len() as u8;
+        len += 1;
+        for &value in pattern.steps.iter() {
+            payload[len..len + 2].copy_from_slice(&value.to_le_bytes());
+            len += 2;
+        }
+    }
+
+    if len > u16::MAX as usize {
+        return Err(StoreError::BufferTooSmall);
+    }
+    seal(image, STORE_MAGIC, STORE_VERSION, len);
+
+    Ok(STORE_HEADER_LEN + len)
+}
+
+/// Write the header for a payload of `len` bytes already in place after it
+fn seal(image: &mut [u8], magic: [u8; 4], version: u16, len: usize) {
+    let crc = crc32(&image[STORE_HEADER_LEN..STORE_HEADER_LEN + len]);
+    image[0..4].copy_from_slice(&magic);
+    image[4..6].copy_from_slice(&version.to_le_bytes());
+    image[6..8].copy_from_slice(&(len as u16).to_le_bytes());
+    image[8..12].copy_from_slice(&crc.to_le_bytes());
+}
+
+/// Check an image's header and CRC and return its payload
+fn open(image: &[u8], magic: [u8; 4], version: u16) -> Result<&[u8], StoreError> {
+    if image.len() < STORE_HEADER_LEN {
+        return Err(StoreError::Truncated);
+    }
+    if image[0..4] != magic {
+        return Err(StoreError::BadMagic);
+    }
+    if u16::from_le_bytes([image[4], image[5]]) != version {
+        return Err(StoreError::UnsupportedVersion);
+    }
+
+    let len = u16::from_le_bytes([image[6], image[7]]) as usize;
+    let crc = u32::from_le_bytes([image[8], image[9], image[10], image[11]]);
+    let payload = image.get(STORE_HEADER_LEN..STORE_HEADER_LEN + len).ok_or(StoreError::Truncated)?;
+    if crc32(payload) != crc {
+        return Err(StoreError::CrcMismatch);
+    }
+    Ok(payload)
+}
+
+/// A serial number must be 1-MAX_SERIAL_LEN printable ASCII characters
+pub fn is_valid_serial(serial: &[u8]) -> bool {
+    (1..=MAX_SERIAL_LEN).contains(&serial.len()) && serial.iter().all(|b| b.is_ascii_graphic())
+}
+
+/// A product or manufacturer string must be 1-MAX_USB_STRING_LEN printable
+/// ASCII characters; spaces are allowed
+pub fn is_valid_usb_string(s: &[u8]) -> bool {
+    (1..=MAX_USB_STRING_LEN).contains(&s.len()) && s.iter().all(|&b| (b' '..=b'~').contains(&b))
+}
+
+/// Write the config image holding `config`, returning the number of bytes used
+pub fn save_config(config: &DeviceConfig, image: &mut [u8]) -> Result<usize, StoreError> {
+    if !is_valid_serial(config.serial.as_bytes())
+        || !is_valid_usb_string(config.product.as_bytes())
+        || !is_valid_usb_string(config.manufacturer.as_bytes())
+    {
+        return Err(StoreError::Malformed);
+    }
+    let fields = [config.serial.as_bytes(), config.product.as_bytes(), config.manufacturer.as_bytes()];
+    let len: usize = 5 + fields.iter().map(|f| 1 + f.len()).sum::<usize>();
+    if image.len() < STORE_HEADER_LEN + len {
+        return Err(StoreError::BufferTooSmall);
+    }
+
+    let mut at = STORE_HEADER_LEN;
+    image[at..at + 2].copy_from_slice(&config.vid.to_le_bytes());
+    image[at + 2..at + 4].copy_from_slice(&config.pid.to_le_bytes());
+    image[at + 4] = config.banner as u8;
+    at += 5;
+    for field in fields {
+        image[at] = field.len() as u8;
+        image[at + 1..at + 1 + field.len()].copy_from_slice(field);
+        at += 1 + field.len();
+    }
+    seal(image, CONFIG_MAGIC, CONFIG_VERSION, len);
+
+    Ok(STORE_HEADER_LEN + len)
+}
+
+/// Device config stored in a config image
+pub fn load_config(image: &[u8]) -> Result<DeviceConfig, StoreError> {
+    let payload = open(image, CONFIG_MAGIC, CONFIG_VERSION)?;
+    let ids = payload.get(..5).ok_or(StoreError::Malformed)?;
+    let banner = match ids[4] {
+        0 => false,
+        1 => true,
+        _ => return Err(StoreError::Malformed),
+    };
+    let mut rest = &payload[5..];
+    let mut field = |valid: fn(&[u8]) -> bool| {
+        let (&len, tail) = rest.split_first().ok_or(StoreError::Malformed)?;
+        let value = tail.get(..len as usize).filter(|v| valid(v)).ok_or(StoreError::Malformed)?;
+        rest = &tail[len as usize..];
+        // Valid fields are ASCII, so always UTF-8
+        core::str::from_utf8(value).map_err(|_| StoreError::Malformed)
+    };
+
+    let serial = field(is_valid_serial)?;
+    let product = field(is_valid_usb_string)?;
+    let manufacturer = field(is_valid_usb_string)?;
+    // Lengths were checked against the field sizes above
+    Ok(DeviceConfig {
+        vid: u16::from_le_bytes([ids[0], ids[1]]),
+        pid: u16::from_le_bytes([ids[2], ids[3]]),
+        banner,
+        serial: heapless::String::try_from(serial).map_err(|_| StoreError::Malformed)?,
+        product: heapless::String::try_from(product).map_err(|_| StoreError::Malformed)?,
+        manufacturer: heapless::String::try_from(manufacturer).map_err(|_| StoreError::Malformed)?,
+    })
+}
+
+/// Replace the manager's patterns with those in `image`
+/// On any error the manager is left empty. Returns the pattern count.
+pub fn load_recoil_store(image: &[u8], manager: &mut RecoilManager) -> Result<usize, StoreError> {
+    manager.clear();
+
+    let result = load_into(image, manager);
+    if result.is_err() {
+        manager.clear();
+    }
+    result
+}
+
+fn load_into(image: &[u8], manager: &mut RecoilManager) -> Result<usize, StoreError> {
+    let payload = open(image, STORE_MAGIC, STORE_VERSION)?;
+
+    // CRC passed, so anything odd below means a writer bug rather than corruption
+    let (&count, mut rest) = payload.split_first().ok_or(StoreError::Malformed)?;
+    for _ in 0..count {
+        let (&name_len, tail) = rest.split_first().ok_or(StoreError::Malformed)?;
+        let name = tail.get(..name_len as usize).ok_or(StoreError::Malformed)?;
+        let name = core::str::from_utf8(name).map_err(|_| StoreError::Malformed)?;
+        let tail = &tail[name_len as usize..];
+
+        let (&flags, tail) = tail.split_first().ok_or(StoreError::Malformed)?;
+        if flags & !FLAG_ABSOLUTE != 0 {
+            return Err(StoreError::Malformed);
+        }
+        let (&value_count, tail) = tail.split_first().ok_or(StoreError::Malformed)?;
+        let raw = tail.get(..value_count as usize * 2).ok_or(StoreError::Malformed)?;
+        let mut values = [0i16; 255];
+        for (value, bytes) in values.iter_mut().zip(raw.chunks_exact(2)) {
+            *value = i16::from_le_bytes([bytes[0], bytes[1]]);
+        }
+        manager.add_pattern(name, &values[..value_count as usize])
+            .map_err(|_| StoreError::Malformed)?;
+        if let Some(pattern) = manager.get_pattern_mut(name) {
+            pattern.absolute = flags & FLAG_ABSOLUTE != 0;
+        }
+
+        rest = &tail[raw.len()..];
+    }
+
+    Ok(count as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mock flash sector, erased to 0xFF like real NVM
+    fn erased_flash() -> [u8; 1024] {
+        [0xFFu8; 1024]
+    }
+
+    #[test]
+    fn test_crc32_known_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut manager = RecoilManager::new();
+        manager.add_pattern("ak47", &[2, -3, 50, 1, -2, 50]).unwrap();
+        manager.add_pattern("m4", &[-1, 4, 40]).unwrap();
+        manager.get_pattern_mut("m4").unwrap().absolute = true;
+
+        let mut flash = erased_flash();
+        save_recoil_store(&manager, &mut flash).unwrap();
+
+        let mut loaded = RecoilManager::new();
+        assert_eq!(load_recoil_store(&flash, &mut loaded), Ok(2));
+        assert_eq!(loaded.get_pattern("ak47").unwrap().steps.as_slice(), &[2, -3, 50, 1, -2, 50]);
+        assert_eq!(loaded.get_pattern("m4").unwrap().steps.as_slice(), &[-1, 4, 40]);
+        assert!(!loaded.get_pattern("ak47").unwrap().absolute);
+        assert!(loaded.get_pattern("m4").unwrap().absolute);
+    }
+
+    #[test]
+    fn test_corrupted_byte_loads_empty() {
+        let mut manager = RecoilManager::new();
+        manager.add_pattern("ak47", &[2, -3, 50, 1, -2, 50]).unwrap();
+
+        let mut flash = erased_flash();
+        let used = save_recoil_store(&manager, &mut flash).unwrap();
+        flash[used - 1] ^= 0x40;
+
+        let mut loaded = RecoilManager::new();
+        loaded.add_pattern("stale", &[1, 1, 1]).unwrap();
+        assert_eq!(load_recoil_store(&flash, &mut loaded), Err(StoreError::CrcMismatch));
+        assert_eq!(loaded.count(), 0);
+    }
+
+    fn config(serial: &str) -> DeviceConfig {
+        let mut config = DeviceConfig::new();
+        config.serial = heapless::String::try_from(serial).unwrap();
+        config
+    }
+
+    #[test]
+    fn test_config_serial_round_trip() {
+        let mut flash = erased_flash();
+        let used = save_config(&config("ABC123"), &mut flash).unwrap();
+        assert_eq!(used, STORE_HEADER_LEN + 5 + 7 + 1 + DEFAULT_PRODUCT.len() + 1 + DEFAULT_MANUFACTURER.len());
+        assert!(used <= CONFIG_IMAGE_SIZE);
+        assert_eq!(load_config(&flash).unwrap().serial.as_str(), "ABC123");
+
+        // A recoil image is not a config image
+        let mut recoil = erased_flash();
+        save_recoil_store(&RecoilManager::new(), &mut recoil).unwrap();
+        assert_eq!(load_config(&recoil), Err(StoreError::BadMagic));
+        flash[STORE_HEADER_LEN + 5] ^= 0x01;
+        assert_eq!(load_config(&flash), Err(StoreError::CrcMismatch));
+    }
+
+    #[test]
+    fn test_config_rejects_bad_serial() {
+        let mut flash = erased_flash();
+        let mut bad = config("ABC");
+        bad.serial.clear();
+        assert_eq!(save_config(&bad, &mut flash), Err(StoreError::Malformed));
+        assert_eq!(save_config(&config("has space"), &mut flash), Err(StoreError::Malformed));
+        assert!(save_config(&config("ABCDEFGHIJKLMNOP"), &mut flash).is_ok());
+        assert_eq!(save_config(&config("ABC"), &mut [0xFF; 8]), Err(StoreError::BufferTooSmall));
+    }
+
+    #[test]
+    fn test_config_usb_ids_round_trip() {
+        let mut flash = erased_flash();
+        assert_eq!(load_config(&flash), Err(StoreError::BadMagic));
+        let defaults = config("ABC");
+        assert_eq!((defaults.vid, defaults.pid), (DEFAULT_VID, DEFAULT_PID));
+
+        let mut written = config("ABC");
+        written.vid = 0x046d;
+        written.pid = 0xc08b;
+        save_config(&written, &mut flash).unwrap();
+        assert_eq!(&flash[STORE_HEADER_LEN..STORE_HEADER_LEN + 4], &[0x6d, 0x04, 0x8b, 0xc0]);
+        let loaded = load_config(&flash).unwrap();
+        assert_eq!((loaded.vid, loaded.pid), (0x046d, 0xc08b));
+    }
+
+    #[test]
+    fn test_config_banner_flag_round_trip() {
+        let mut flash = erased_flash();
+        assert!(config("ABC").banner);
+        save_config(&config("ABC"), &mut flash).unwrap();
+        assert!(load_config(&flash).unwrap().banner);
+
+        let mut quiet = config("ABC");
+        quiet.banner = false;
+        save_config(&quiet, &mut flash).unwrap();
+        assert_eq!(flash[STORE_HEADER_LEN + 4], 0);
+        assert_eq!(load_config(&flash), Ok(quiet));
+
+        // An image from before the flag existed is not read as this layout
+        flash[4..6].copy_from_slice(&3u16.to_le_bytes());
+        assert_eq!(load_config(&flash), Err(StoreError::UnsupportedVersion));
+    }
+
+    #[test]
+    fn test_config_usb_strings_round_trip() {
+        let mut flash = erased_flash();
+        let mut written = config("ABC123");
+        written.product = heapless::String::try_from("Acme Gaming Mouse").unwrap();
+        written.manufacturer = heapless::String::try_from("Acme Corp.").unwrap();
+        save_config(&written, &mut flash).unwrap();
+        assert_eq!(load_config(&flash), Ok(written.clone()));
+
+        // Strings may hold spaces but not control characters
+        assert!(is_valid_usb_string(b"Acme Gaming Mouse"));
+        assert!(!is_valid_usb_string(b"tab\there"));
+        assert!(!is_valid_usb_string(b""));
+        assert!(!is_valid_usb_string(&[b'x'; MAX_USB_STRING_LEN + 1]));
+        written.product.clear();
+        let _ = written.product.push('\n');
+        assert_eq!(save_config(&written, &mut flash), Err(StoreError::Malformed));
+    }
+
+    #[test]
+    fn test_erased_flash_loads_empty() {
+        let mut loaded = RecoilManager::new();
+        assert_eq!(load_recoil_store(&erased_flash(), &mut loaded), Err(StoreError::BadMagic));
+        assert_eq!(loaded.count(), 0);
+    }
+}
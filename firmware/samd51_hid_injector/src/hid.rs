@@ -99,6 +99,149 @@ impl MouseReport {
     }
 }
 
+/// Modifier bit for a name such as "shift" or "rctrl"
+/// Unprefixed names are the left-hand keys.
+pub fn modifier_from_name(name: &[u8]) -> Option<u8> {
+    use scancodes::*;
+
+    match name {
+        b"ctrl" | b"lctrl" => Some(MOD_LCTRL),
+        b"shift" | b"lshift" => Some(MOD_LSHIFT),
+        b"alt" | b"lalt" => Some(MOD_LALT),
+        b"gui" | b"lgui" => Some(MOD_LGUI),
+        b"rctrl" => Some(MOD_RCTRL),
+        b"rshift" => Some(MOD_RSHIFT),
+        b"ralt" => Some(MOD_RALT),
+        b"rgui" => Some(MOD_RGUI),
+        _ => None,
+    }
+}
+
+/// Parse a chord such as "ctrl+k" or "ctrl+shift+esc" to (scancode, modifiers)
+/// Every part but the last is a modifier name; the last is one character
+/// (see ascii_to_scancode) or a key name: enter, esc, tab, space, backspace,
+/// f1-f12, up, down, left, right, home, end, pgup, pgdn, insert or delete.
+pub fn parse_chord(text: &[u8]) -> Option<(u8, u8)> {
+    use scancodes::*;
+
+    let mut parts = text.rsplit(|&c| c == b'+');
+    let (scancode, mut modifiers) = match parts.next()? {
+        b"enter" => (ENTER, 0),
+        b"esc" => (ESCAPE, 0),
+        b"tab" => (TAB, 0),
+        b"space" => (SPACE, 0),
+        b"backspace" => (BACKSPACE, 0),
+        b"up" => (UP_ARROW, 0),
+        b"down" => (DOWN_ARROW, 0),
+        b"left" => (LEFT_ARROW, 0),
+        b"right" => (RIGHT_ARROW, 0),
+        b"home" => (HOME, 0),
+        b"end" => (END, 0),
+        b"pgup" => (PAGE_UP, 0),
+        b"pgdn" => (PAGE_DOWN, 0),
+        b"insert" => (INSERT, 0),
+        b"delete" => (DELETE, 0),
+        &[c] => ascii_to_scancode(c)?,
+        [b'f', digits @ ..] => match digits {
+            b"1" | b"2" | b"3" | b"4" | b"5" | b"6" | b"7" | b"8" | b"9" => (F1 + digits[0] - b'1', 0),
+            b"10" | b"11" | b"12" => (F10 + digits[1] - b'0', 0),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    for name in parts {
+        modifiers |= modifier_from_name(name)?;
+    }
+    Some((scancode, modifiers))
+}
+
+/// Map a printable ASCII character to (scancode, modifiers) on a US layout
+/// Returns None for characters with no single-key equivalent.
+pub fn ascii_to_scancode(c: u8) -> Option<(u8, u8)> {
+    use scancodes::*;
+
+    let unshifted = |code| Some((code, 0));
+    let shifted = |code| Some((code, MOD_LSHIFT));
+    match c {
+        b'a'..=b'z' => unshifted(A + (c - b'a')),
+        b'A'..=b'Z' => shifted(A + (c - b'A')),
+        b'1'..=b'9' => unshifted(KEY_1 + (c - b'1')),
+        b'0' => unshifted(KEY_0),
+        b'\n' => unshifted(ENTER),
+        b'\t' => unshifted(TAB),
+        0x08 => unshifted(BACKSPACE),
+        0x1B => unshifted(ESCAPE),
+        0x7F => unshifted(DELETE),
+        b' ' => unshifted(SPACE),
+        b'-' => unshifted(0x2D),
+        b'=' => unshifted(0x2E),
+        b'[' => unshifted(0x2F),
+        b']' => unshifted(0x30),
+        b'\\' => unshifted(0x31),
+        b';' => unshifted(0x33),
+        b'\'' => unshifted(0x34),
+        b'`' => unshifted(0x35),
+        b',' => unshifted(0x36),
+        b'.' => unshifted(0x37),
+        b'/' => unshifted(0x38),
+        b'!' => shifted(KEY_1),
+        b'@' => shifted(KEY_2),
+        b'#' => shifted(KEY_3),
+        b'$' => shifted(KEY_4),
+        b'%' => shifted(KEY_5),
+        b'^' => shifted(KEY_6),
+        b'&' => shifted(KEY_7),
+        b'*' => shifted(KEY_8),
+        b'(' => shifted(KEY_9),
+        b')' => shifted(KEY_0),
+        b'_' => shifted(0x2D),
+        b'+' => shifted(0x2E),
+        b'{' => shifted(0x2F),
+        b'}' => shifted(0x30),
+        b'|' => shifted(0x31),
+        b':' => shifted(0x33),
+        b'"' => shifted(0x34),
+        b'~' => shifted(0x35),
+        b'<' => shifted(0x36),
+        b'>' => shifted(0x37),
+        b'?' => shifted(0x38),
+        _ => None,
+    }
+}
+
+/// Map a scancode to the character it types without Shift on a US layout
+/// The inverse of ascii_to_scancode; None for keys that type nothing.
+pub fn scancode_to_ascii(scancode: u8) -> Option<u8> {
+    (0..0x80).find(|&c| ascii_to_scancode(c) == Some((scancode, 0)))
+}
+
+/// Display name of a key that is better named than printed, as in "Ctrl+Enter"
+pub fn key_name(scancode: u8) -> Option<&'static str> {
+    use scancodes::*;
+
+    const F_KEYS: [&str; 12] = ["F1", "F2", "F3", "F4", "F5", "F6", "F7", "F8", "F9", "F10", "F11", "F12"];
+    match scancode {
+        ENTER => Some("Enter"),
+        ESCAPE => Some("Esc"),
+        TAB => Some("Tab"),
+        SPACE => Some("Space"),
+        BACKSPACE => Some("Backspace"),
+        CAPS_LOCK => Some("CapsLock"),
+        F1..=F12 => Some(F_KEYS[(scancode - F1) as usize]),
+        UP_ARROW => Some("Up"),
+        DOWN_ARROW => Some("Down"),
+        LEFT_ARROW => Some("Left"),
+        RIGHT_ARROW => Some("Right"),
+        HOME => Some("Home"),
+        END => Some("End"),
+        PAGE_UP => Some("PgUp"),
+        PAGE_DOWN => Some("PgDn"),
+        INSERT => Some("Insert"),
+        DELETE => Some("Delete"),
+        _ => None,
+    }
+}
+
 /// HID Keyboard Scancode Constants
 pub mod scancodes {
     // Letters A-Z
@@ -147,6 +290,55 @@ pub mod scancodes {
     pub const BACKSPACE: u8 = 0x2A;
     pub const TAB: u8 = 0x2B;
     pub const SPACE: u8 = 0x2C;
+    pub const CAPS_LOCK: u8 = 0x39;
+    
+    // Function keys F1-F12
+    pub const F1: u8 = 0x3A;
+    pub const F2: u8 = 0x3B;
+    pub const F3: u8 = 0x3C;
+    pub const F4: u8 = 0x3D;
+    pub const F5: u8 = 0x3E;
+    pub const F6: u8 = 0x3F;
+    pub const F7: u8 = 0x40;
+    pub const F8: u8 = 0x41;
+    pub const F9: u8 = 0x42;
+    pub const F10: u8 = 0x43;
+    pub const F11: u8 = 0x44;
+    pub const F12: u8 = 0x45;
+    
+    // Navigation and editing
+    pub const PRINT_SCREEN: u8 = 0x46;
+    pub const SCROLL_LOCK: u8 = 0x47;
+    pub const PAUSE: u8 = 0x48;
+    pub const INSERT: u8 = 0x49;
+    pub const HOME: u8 = 0x4A;
+    pub const PAGE_UP: u8 = 0x4B;
+    pub const DELETE: u8 = 0x4C;
+    pub const END: u8 = 0x4D;
+    pub const PAGE_DOWN: u8 = 0x4E;
+    pub const RIGHT_ARROW: u8 = 0x4F;
+    pub const LEFT_ARROW: u8 = 0x50;
+    pub const DOWN_ARROW: u8 = 0x51;
+    pub const UP_ARROW: u8 = 0x52;
+    
+    // Numeric keypad
+    pub const NUM_LOCK: u8 = 0x53;
+    pub const KP_SLASH: u8 = 0x54;
+    pub const KP_ASTERISK: u8 = 0x55;
+    pub const KP_MINUS: u8 = 0x56;
+    pub const KP_PLUS: u8 = 0x57;
+    pub const KP_ENTER: u8 = 0x58;
+    pub const KP_1: u8 = 0x59;
+    pub const KP_2: u8 = 0x5A;
+    pub const KP_3: u8 = 0x5B;
+    pub const KP_4: u8 = 0x5C;
+    pub const KP_5: u8 = 0x5D;
+    pub const KP_6: u8 = 0x5E;
+    pub const KP_7: u8 = 0x5F;
+    pub const KP_8: u8 = 0x60;
+    pub const KP_9: u8 = 0x61;
+    pub const KP_0: u8 = 0x62;
+    pub const KP_DOT: u8 = 0x63;
     
     // Modifier bits
     pub const MOD_LCTRL: u8 = 0x01;
@@ -164,6 +356,16 @@ mod tests {
     use super::*;
     use super::scancodes::*;
 
+    #[test]
+    fn test_scancode_to_ascii() {
+        assert_eq!(scancode_to_ascii(A), Some(b'a'));
+        assert_eq!(scancode_to_ascii(KEY_0), Some(b'0'));
+        assert_eq!(scancode_to_ascii(0x38), Some(b'/'));
+        assert_eq!(scancode_to_ascii(F1), None);
+        assert_eq!(key_name(F10), Some("F10"));
+        assert_eq!(key_name(A), None);
+    }
+
     #[test]
     fn test_keyboard_report_empty() {
         let report = KeyboardReport::empty();
@@ -289,4 +491,53 @@ mod tests {
         assert_eq!(MOD_LALT, 0x04);
         assert_eq!(MOD_LGUI, 0x08);
     }
+
+    #[test]
+    fn test_ascii_to_scancode() {
+        assert_eq!(ascii_to_scancode(b'a'), Some((A, 0)));
+        assert_eq!(ascii_to_scancode(b'Z'), Some((Z, MOD_LSHIFT)));
+        assert_eq!(ascii_to_scancode(b'0'), Some((KEY_0, 0)));
+        assert_eq!(ascii_to_scancode(b'9'), Some((KEY_9, 0)));
+        assert_eq!(ascii_to_scancode(b'!'), Some((KEY_1, MOD_LSHIFT)));
+        assert_eq!(ascii_to_scancode(b' '), Some((SPACE, 0)));
+        assert_eq!(ascii_to_scancode(0x7F), Some((DELETE, 0)));
+        assert_eq!(ascii_to_scancode(0x1B), Some((ESCAPE, 0)));
+        assert_eq!(ascii_to_scancode(0x00), None);
+        assert_eq!(ascii_to_scancode(0x80), None);
+    }
+
+    #[test]
+    fn test_extended_scancode_constants() {
+        assert_eq!(F1, 0x3A);
+        assert_eq!(F12, 0x45);
+        assert_eq!(RIGHT_ARROW, 0x4F);
+        assert_eq!(UP_ARROW, 0x52);
+        assert_eq!(PAGE_DOWN, 0x4E);
+        assert_eq!(KP_1, 0x59);
+        assert_eq!(KP_0, 0x62);
+        assert_eq!(KP_DOT, 0x63);
+    }
+
+    #[test]
+    fn test_modifier_from_name() {
+        assert_eq!(modifier_from_name(b"shift"), Some(MOD_LSHIFT));
+        assert_eq!(modifier_from_name(b"lctrl"), Some(MOD_LCTRL));
+        assert_eq!(modifier_from_name(b"rgui"), Some(MOD_RGUI));
+        assert_eq!(modifier_from_name(b"hyper"), None);
+    }
+
+    #[test]
+    fn test_parse_chord() {
+        assert_eq!(parse_chord(b"ctrl+k"), Some((K, MOD_LCTRL)));
+        assert_eq!(parse_chord(b"ctrl+shift+esc"), Some((ESCAPE, MOD_LCTRL | MOD_LSHIFT)));
+        assert_eq!(parse_chord(b"alt+A"), Some((A, MOD_LALT | MOD_LSHIFT)));
+        assert_eq!(parse_chord(b"enter"), Some((ENTER, 0)));
+        assert_eq!(parse_chord(b"alt+f4"), Some((F4, MOD_LALT)));
+        assert_eq!(parse_chord(b"f12"), Some((F12, 0)));
+        assert_eq!(parse_chord(b"shift+right"), Some((RIGHT_ARROW, MOD_LSHIFT)));
+        assert_eq!(parse_chord(b"f13"), None);
+        assert_eq!(parse_chord(b"hyper+k"), None);
+        assert_eq!(parse_chord(b"ctrl+"), None);
+        assert_eq!(parse_chord(b""), None);
+    }
 }
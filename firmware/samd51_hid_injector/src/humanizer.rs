@@ -0,0 +1,264 @@
+//! Injection Humanizer
+//! One profile setting that picks movement jitter, moveto easing and
+//! inter-report timing variance together
+
+use heapless::Vec;
+
+/// Most segments a single eased moveto is split into
+pub const MAX_EASING_STEPS: usize = 16;
+
+/// Time between eased moveto segments
+pub const EASING_INTERVAL_MS: u32 = 8;
+
+/// Humanizer preset selected with nozen.human(...)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum HumanProfile {
+    #[default]
+    Off,
+    Low,
+    Med,
+    High,
+}
+
+/// Effective humanizer parameters for a profile
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HumanParams {
+    /// Maximum random offset added to each axis of a move, in counts
+    pub jitter: i16,
+    /// Segments a relative moveto is eased over (1 = single report)
+    pub easing_steps: u8,
+    /// Maximum random delay added between timed reports, in ms
+    pub timing_variance_ms: u16,
+}
+
+impl HumanProfile {
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"off" => Some(HumanProfile::Off),
+            b"low" => Some(HumanProfile::Low),
+            b"med" => Some(HumanProfile::Med),
+            b"high" => Some(HumanProfile::High),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HumanProfile::Off => "off",
+            HumanProfile::Low => "low",
+            HumanProfile::Med => "med",
+            HumanProfile::High => "high",
+        }
+    }
+
+    /// Preset parameters (documented in NOZEN_COMMAND_REFERENCE.md)
+    pub fn params(&self) -> HumanParams {
+        let (jitter, easing_steps, timing_variance_ms) = match self {
+            HumanProfile::Off => (0, 1, 0),
+            HumanProfile::Low => (1, 4, 2),
+            HumanProfile::Med => (2, 8, 5),
+            HumanProfile::High => (3, 12, 10),
+        };
+        HumanParams { jitter, easing_steps, timing_variance_ms }
+    }
+}
+
+/// Small xorshift32 PRNG; plenty for jitter and cheap on a Cortex-M4
+pub struct Xorshift32 {
+    state: u32,
+}
+
+impl Xorshift32 {
+    pub fn new(seed: u32) -> Self {
+        // Zero is a fixed point of xorshift
+        Xorshift32 { state: if seed == 0 { 0x9E37_79B9 } else { seed } }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// Uniform value in -max..=max
+    pub fn symmetric(&mut self, max: i16) -> i16 {
+        if max <= 0 {
+            return 0;
+        }
+        let span = 2 * max as u32 + 1;
+        (self.next_u32() % span) as i16 - max
+    }
+}
+
+pub struct Humanizer {
+    pub profile: HumanProfile,
+    rng: Xorshift32,
+}
+
+impl Humanizer {
+    pub fn new(seed: u32) -> Self {
+        Humanizer {
+            profile: HumanProfile::Off,
+            rng: Xorshift32::new(seed),
+        }
+    }
+
+    pub fn params(&self) -> HumanParams {
+        self.profile.params()
+    }
+
+    /// Add jitter to a movement; zero movement stays still
+    pub fn jitter(&mut self, dx: i16, dy: i16) -> (i16, i16) {
+        let jitter = self.params().jitter;
+        if jitter == 0 || (dx == 0 && dy == 0) {
+            return (dx, dy);
+        }
+        (
+            dx.saturating_add(self.rng.symmetric(jitter)),
+            dy.saturating_add(self.rng.symmetric(jitter)),
+        )
+    }
+
+    /// Random extra delay for the next timed report
+    pub fn timing_offset(&mut self) -> u32 {
+        let variance = self.params().timing_variance_ms as u32;
+        if variance == 0 {
+            return 0;
+        }
+        self.rng.next_u32() % (variance + 1)
+    }
+
+    /// Split (dx, dy) into smoothstep-eased segments that sum exactly to it
+    pub fn ease(&self, dx: i16, dy: i16) -> Vec<(i16, i16), MAX_EASING_STEPS> {
+        let steps = (self.params().easing_steps as usize).clamp(1, MAX_EASING_STEPS);
+        let mut segments = Vec::new();
+        let (mut last_x, mut last_y) = (0i64, 0i64);
+
+        for i in 1..=steps {
+            // smoothstep(t) = 3t^2 - 2t^3, with t in thousandths
+            let t = (i * 1000 / steps) as i64;
+            let eased = (3 * t * t * 1000 - 2 * t * t * t) / 1_000_000;
+            let x = dx as i64 * eased / 1000;
+            let y = dy as i64 * eased / 1000;
+            let _ = segments.push(((x - last_x) as i16, (y - last_y) as i16));
+            last_x = x;
+            last_y = y;
+        }
+        segments
+    }
+}
+
+/// Random drift for nozen.wander: one-count steps that stay within `radius`
+/// of where wandering started, pulled back harder the further out they get
+pub struct Wander {
+    pub radius: i16,
+    /// Offset from the starting point, the sum of every step so far
+    offset: (i16, i16),
+    rng: Xorshift32,
+}
+
+impl Wander {
+    pub fn new(radius: i16, seed: u32) -> Self {
+        Wander { radius: radius.max(1), offset: (0, 0), rng: Xorshift32::new(seed) }
+    }
+
+    pub fn offset(&self) -> (i16, i16) {
+        self.offset
+    }
+
+    /// Next move, -1..=1 per axis
+    pub fn next_step(&mut self) -> (i16, i16) {
+        let mut step = (self.drift(self.offset.0), self.drift(self.offset.1));
+        let next = (self.offset.0 + step.0, self.offset.1 + step.1);
+        // Never leave the circle: head straight back instead, which only
+        // ever shortens the distance
+        if !self.inside(next) {
+            step = (-self.offset.0.signum(), -self.offset.1.signum());
+        }
+        self.offset = (self.offset.0 + step.0, self.offset.1 + step.1);
+        step
+    }
+
+    /// Random step on one axis, towards the centre with a chance that grows
+    /// from 0 at the centre to certain at the edge
+    fn drift(&mut self, offset: i16) -> i16 {
+        if (self.rng.next_u32() % self.radius as u32) < offset.unsigned_abs() as u32 {
+            -offset.signum()
+        } else {
+            self.rng.symmetric(1)
+        }
+    }
+
+    fn inside(&self, (x, y): (i16, i16)) -> bool {
+        let r = self.radius as i32;
+        (x as i32).pow(2) + (y as i32).pow(2) <= r * r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_presets() {
+        assert_eq!(HumanProfile::Off.params(), HumanParams { jitter: 0, easing_steps: 1, timing_variance_ms: 0 });
+        assert_eq!(HumanProfile::Low.params(), HumanParams { jitter: 1, easing_steps: 4, timing_variance_ms: 2 });
+        assert_eq!(HumanProfile::Med.params(), HumanParams { jitter: 2, easing_steps: 8, timing_variance_ms: 5 });
+        assert_eq!(HumanProfile::High.params(), HumanParams { jitter: 3, easing_steps: 12, timing_variance_ms: 10 });
+        assert_eq!(HumanProfile::from_name(b"med"), Some(HumanProfile::Med));
+        assert_eq!(HumanProfile::from_name(b"max"), None);
+    }
+
+    #[test]
+    fn test_jitter_stays_in_bounds() {
+        let mut human = Humanizer::new(1234);
+        assert_eq!(human.jitter(10, -10), (10, -10));
+
+        human.profile = HumanProfile::High;
+        let mut moved = false;
+        for _ in 0..200 {
+            let (x, y) = human.jitter(10, -10);
+            assert!((7..=13).contains(&x) && (-13..=-7).contains(&y));
+            moved |= (x, y) != (10, -10);
+            assert!(human.timing_offset() <= 10);
+        }
+        assert!(moved);
+        assert_eq!(human.jitter(0, 0), (0, 0));
+    }
+
+    #[test]
+    fn test_ease_sums_to_delta() {
+        let mut human = Humanizer::new(1);
+        assert_eq!(human.ease(100, -40).as_slice(), &[(100, -40)]);
+
+        human.profile = HumanProfile::Med;
+        let segments = human.ease(100, -40);
+        assert_eq!(segments.len(), 8);
+        let total = segments.iter().fold((0, 0), |(x, y), &(dx, dy)| (x + dx, y + dy));
+        assert_eq!(total, (100, -40));
+        // Slow at the ends, fast in the middle
+        assert!(segments[0].0 < segments[3].0 && segments[7].0 < segments[4].0);
+    }
+
+    #[test]
+    fn test_wander_stays_inside_radius() {
+        for radius in [1, 5, 40] {
+            let mut wander = Wander::new(radius, 0x2545_F491);
+            let (mut x, mut y) = (0i32, 0i32);
+            let mut furthest = 0;
+            for _ in 0..10_000 {
+                let (dx, dy) = wander.next_step();
+                assert!(dx.abs() <= 1 && dy.abs() <= 1);
+                x += dx as i32;
+                y += dy as i32;
+                assert!(x * x + y * y <= (radius as i32).pow(2));
+                furthest = furthest.max(x * x + y * y);
+            }
+            assert_eq!(wander.offset(), (x as i16, y as i16));
+            assert!(furthest > 0);
+        }
+    }
+}
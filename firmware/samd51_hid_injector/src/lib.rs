@@ -7,3 +7,11 @@ pub mod state;
 pub mod protocol;
 pub mod descriptor;
 pub mod descriptor_cache;
+pub mod timebase;
+pub mod queue;
+pub mod codes;
+pub mod flash_store;
+pub mod uart_config;
+pub mod uart_rx;
+pub mod humanizer;
+pub mod settle;
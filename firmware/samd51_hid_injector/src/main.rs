@@ -3,6 +3,7 @@
 
 use panic_halt as _;
 
+use cortex_m::peripheral::DWT;
 use cortex_m_rt::entry;
 
 use atsamd_hal as hal;
@@ -17,11 +18,22 @@ use usbd_serial::{SerialPort, USB_CLASS_CDC};
 use usb_device::bus::UsbBusAllocator;
 use heapless;
 
+mod nvm;
 mod uart;
 
-use uart::UartInterface;
+use nvm::ConfigFlash;
+use uart::{DefaultUartPins, UartInterface};
 use samd51_hid_injector::protocol::{CommandProcessor, CommandType};
 use samd51_hid_injector::descriptor_cache::DescriptorCache;
+use samd51_hid_injector::timebase::{CycleTimer, Timebase};
+use samd51_hid_injector::uart_config::FlowControl;
+use samd51_hid_injector::flash_store::{load_config, save_config, DeviceConfig, CONFIG_IMAGE_SIZE};
+
+/// Core clock after GenericClockController::with_internal_32kosc (GCLK0)
+const CPU_HZ: u32 = 120_000_000;
+
+/// How long D+ stays detached so the host registers the disconnect
+const USB_REATTACH_MS: u8 = 100;
 
 /// Debug output macro for USB-CDC serial
 macro_rules! debug_write {
@@ -37,7 +49,7 @@ macro_rules! debug_write {
 fn main() -> ! {
     // Get peripheral instances
     let mut peripherals = Peripherals::take().unwrap();
-    let core = CorePeripherals::take().unwrap();
+    let mut core = CorePeripherals::take().unwrap();
 
     // Configure clocks
     let mut clocks = GenericClockController::with_internal_32kosc(
@@ -50,9 +62,29 @@ fn main() -> ! {
 
     let mut delay = Delay::new(core.SYST, &mut clocks);
 
+    // Millisecond timebase from the DWT cycle counter (SysTick belongs to Delay)
+    core.DCB.enable_trace();
+    core.DWT.enable_cycle_counter();
+    let mut timebase = Timebase::new(CPU_HZ, DWT::cycle_count());
+
     // Configure pins
     let pins = Pins::new(peripherals.PORT);
 
+    // =======================================================================
+    // Config Region
+    // =======================================================================
+    // An erased or corrupt image loads as the default IDs, serial and strings
+    let mut config_flash = ConfigFlash::new(peripherals.NVMCTRL);
+    let mut config_image = [0xFFu8; CONFIG_IMAGE_SIZE];
+    config_flash.read(&mut config_image);
+    
+    // The USB device keeps 'static references to its strings
+    static mut DEVICE_CONFIG: Option<DeviceConfig> = None;
+    let device_config: &'static DeviceConfig = unsafe {
+        DEVICE_CONFIG = Some(load_config(&config_image).unwrap_or_else(|_| DeviceConfig::new()));
+        DEVICE_CONFIG.as_ref().unwrap()
+    };
+
     // =======================================================================
     // USB CDC-ACM Setup (Host PC Communication)
     // =======================================================================
@@ -75,34 +107,41 @@ fn main() -> ! {
 
     let mut serial = SerialPort::new(bus_allocator);
 
-    let mut usb_dev = UsbDeviceBuilder::new(bus_allocator, UsbVidPid(0x1d50, 0x615c))
-        .manufacturer("Great Scott Gadgets")
-        .product("Cynthion HID Injector")
-        .serial_number("HID-INJ-001")
+    let mut usb_dev = UsbDeviceBuilder::new(bus_allocator, UsbVidPid(device_config.vid, device_config.pid))
+        .manufacturer(device_config.manufacturer.as_str())
+        .product(device_config.product.as_str())
+        .serial_number(device_config.serial.as_str())
         .device_class(USB_CLASS_CDC)
         .build();
     
     let mut usb_configured = false;
-    let mut startup_sent = false;
 
     // =======================================================================
     // UART0 Setup (FPGA Communication)
     // =======================================================================
     // UART0 on pins R14 (TX) and T14 (RX) connected to FPGA
+    // Other wiring: pass a different supported pair, e.g. (pins.pa08, pins.pa09)
+    // For RTS/CTS also pass the PAD2/PAD3 pins, e.g. (pins.pa04, pins.pa05, pins.pa06, pins.pa07)
     
-    let uart = UartInterface::new(
-        peripherals.SERCOM0,
-        &mut clocks,
-        115200,  // Baud rate
+    let uart_pins: DefaultUartPins = (
         pins.pa04,  // TX (maps to R14 on Cynthion)
         pins.pa05,  // RX (maps to T14 on Cynthion)
     );
+    let mut uart = UartInterface::new(
+        peripherals.SERCOM0,
+        &mut clocks,
+        115200,  // Baud rate
+        uart_pins,
+        FlowControl::None,
+    ).unwrap();
 
     // =======================================================================
     // Command Processor
     // =======================================================================
     
     let mut cmd_processor = CommandProcessor::new();
+    cmd_processor.config = device_config.clone();
+    cmd_processor.cycle_timer = CycleTimer::new(DWT::cycle_count, CPU_HZ);
     
     // =======================================================================
     // HID Descriptor Cache
@@ -125,6 +164,13 @@ fn main() -> ! {
     
     loop {
         loop_counter = loop_counter.wrapping_add(1);
+        let now_ms = timebase.update(DWT::cycle_count());
+        cmd_processor.tick(now_ms, loop_counter);
+        
+        // Send queued steps (key repeats, etc.) that have come due
+        while let Some(cmd) = cmd_processor.next_due() {
+            let _ = uart.write(&cmd.to_uart_frame());
+        }
         
         // Poll USB and detect state changes
         let poll_result = usb_dev.poll(&mut [&mut serial]);
@@ -137,7 +183,6 @@ fn main() -> ! {
                 UsbDeviceState::Default => {
                     debug_write!(serial, "[USB] State: Default (device reset)\r\n");
                     usb_configured = false;
-                    startup_sent = false;
                 }
                 UsbDeviceState::Addressed => {
                     debug_write!(serial, "[USB] State: Addressed (address assigned)\r\n");
@@ -148,25 +193,33 @@ fn main() -> ! {
                 }
                 UsbDeviceState::Suspend => {
                     debug_write!(serial, "[USB] State: Suspend (low power)\r\n");
+                    // Don't leave the target with stuck buttons/keys on resume
+                    let _ = uart.write(&cmd_processor.on_suspend().to_uart_frame());
                 }
             }
         }
         
-        // Send startup banner once after configuration
-        if usb_configured && !startup_sent {
-            startup_sent = true;
+        // Send startup banner once after configuration, unless nozen.banner(off) saved
+        if cmd_processor.take_startup_banner(usb_configured) {
             debug_write!(serial, "\r\n");
             debug_write!(serial, "========================================\r\n");
             debug_write!(serial, "Cynthion HID Injector v0.1.0\r\n");
             debug_write!(serial, "USB-CDC Debug Mode Enabled\r\n");
             debug_write!(serial, "========================================\r\n");
             debug_write!(serial, "[INIT] UART Baud: 115200\r\n");
+            let (tx_pin, rx_pin) = uart.pinout().pin_names();
+            debug_write!(serial, "[INIT] UART Pins: TX={} RX={}\r\n", tx_pin, rx_pin);
+            if uart.flow_control() == FlowControl::RtsCts {
+                let (rts_pin, cts_pin) = uart.pinout().flow_pin_names();
+                debug_write!(serial, "[INIT] UART Flow: RTS={} CTS={}\r\n", rts_pin, cts_pin);
+            }
             debug_write!(serial, "[INIT] Buffer sizes: RX=256, TX=64\r\n");
             debug_write!(serial, "[INIT] Ready for commands\r\n\r\n");
         }
         
         if poll_result {
             // Read commands from USB CDC-ACM
+            let received_at = DWT::cycle_count();
             match serial.read(&mut rx_buffer) {
                 Ok(count) if count > 0 => {
                     debug_write!(serial, "[USB-RX] Received {} bytes: ", count);
@@ -196,11 +249,22 @@ fn main() -> ! {
                             // Format command for FPGA and send via UART
                             let uart_msg = cmd.to_uart_frame();
                             debug_write!(serial, "[UART-TX] Sending to FPGA...\r\n");
-                            uart.write(&uart_msg);
+                            if uart.write(&uart_msg) < uart_msg.len() {
+                                cmd_processor.link.tx_stalls += 1;
+                                debug_write!(serial, "[WARN] UART write stalled (CTS deasserted)\r\n");
+                            }
+                            cmd_processor.link.tx_frames += 1;
+                            let elapsed = DWT::cycle_count().wrapping_sub(received_at);
+                            cmd_processor.latency.record(elapsed / (CPU_HZ / 1_000_000));
                             
                             // Echo acknowledgment back to USB
                             let ack = b"[OK] Command sent to FPGA\r\n";
                             let _ = serial.write(ack);
+
+                            // Some commands (e.g. reset) also leave a message for the host
+                            if let Some(response) = cmd_processor.get_response() {
+                                let _ = serial.write(response);
+                            }
                         }
                         CommandType::Response => {
                             debug_write!(serial, "[CMD] Type: Response\r\n");
@@ -215,13 +279,57 @@ fn main() -> ! {
                         }
                         CommandType::Restart => {
                             debug_write!(serial, "[CMD] Type: Restart\r\n");
+                            // Config changed earlier in this batch of lines would
+                            // otherwise be lost, since it is saved at the end of the loop
+                            if cmd_processor.config_dirty {
+                                cmd_processor.config_dirty = false;
+                                if let Err(e) = persist_config(&cmd_processor.config, &mut config_flash) {
+                                    debug_write!(serial, "[ERROR] Config not saved: {}\r\n", e);
+                                }
+                            }
                             // Send restart acknowledgment then restart
                             let msg = b"[SYS] Restarting device...\r\n";
                             let _ = serial.write(msg);
                             delay.delay_ms(100u8);
-                            // TODO: Implement system reset via SCB
-                            // cortex_m::peripheral::SCB::sys_reset();
-                            debug_write!(serial, "[WARN] Restart not implemented\r\n");
+                            // Boots again from the top of main, so USB is rebuilt
+                            // from the saved config and enumerates with it
+                            cortex_m::peripheral::SCB::sys_reset();
+                        }
+                        CommandType::UartFlush => {
+                            let discarded = uart.flush_rx();
+                            cmd_processor.link.flushed += discarded as u32;
+                            debug_write!(serial, "[OK] UART RX flushed: {} bytes discarded\r\n", discarded);
+                        }
+                        CommandType::UartRegs => {
+                            cmd_processor.finish_uart_regs(&uart.registers());
+                            if let Some(response) = cmd_processor.get_response() {
+                                let _ = serial.write(response);
+                            }
+                        }
+                        CommandType::UsbReattach => {
+                            let _ = serial.write(b"[OK] USB reattaching\r\n");
+                            // Give the acknowledgment time to leave before dropping off the bus
+                            delay.delay_ms(10u8);
+                            usb_reattach(&mut delay);
+                            usb_configured = false;
+                        }
+                        CommandType::Bench(count) => {
+                            let report = cmd_processor.bench_report();
+                            let (frame, len) = (report.to_uart_frame(), report.uart_frame_len());
+                            let start_ms = timebase.update(DWT::cycle_count());
+                            for _ in 0..count {
+                                if uart.write(&frame[..len]) < len {
+                                    cmd_processor.link.tx_stalls += 1;
+                                }
+                                cmd_processor.link.tx_frames += 1;
+                                // Keep sampling so CYCCNT can't wrap unseen
+                                timebase.update(DWT::cycle_count());
+                            }
+                            let elapsed_ms = timebase.update(DWT::cycle_count()).wrapping_sub(start_ms);
+                            cmd_processor.finish_bench(count, elapsed_ms);
+                            if let Some(response) = cmd_processor.get_response() {
+                                let _ = serial.write(response);
+                            }
                         }
                         CommandType::NoOp => {
                             debug_write!(serial, "[CMD] Type: NoOp (ignored)\r\n");
@@ -242,13 +350,46 @@ fn main() -> ! {
                     debug_write!(serial, "[ERROR] USB read failed: {:?}\r\n", e);
                 }
             }
-            
-            // Read status from FPGA UART
-            if let Some(status) = uart.read_line() {
-                debug_write!(serial, "[UART-RX] Received from FPGA: ");
-                // Forward FPGA status to USB host
-                let _ = serial.write(&status);
-                let _ = serial.write(b"\r\n");
+        }
+        
+        // Read status from FPGA UART
+        let mut status = [0u8; 256];
+        if let Some(len) = uart.read_line(&mut status) {
+            debug_write!(serial, "[UART-RX] Received from FPGA: ");
+            // Forward FPGA status to USB host
+            let _ = serial.write(&status[..len]);
+            let _ = serial.write(b"\r\n");
+            cmd_processor.on_uart_line(&status[..len]);
+            // e.g. the outcome of nozen.moveto.verify
+            if let Some(response) = cmd_processor.get_response() {
+                let _ = serial.write(response);
+            }
+        }
+        
+        // Report that nozen.deadman released everything
+        if cmd_processor.take_deadman_trip() {
+            debug_write!(serial, "[WARN] Deadman: no command for {} ms, released all inputs\r\n", cmd_processor.deadman_ms);
+        }
+        
+        // Report a run of identical errors that was coalesced
+        if let Some(count) = cmd_processor.take_error_run() {
+            debug_write!(serial, "[ERR] x{}\r\n", count);
+        }
+        
+        // Persist config changed by nozen.serial / nozen.usb.strings / nozen.usb.ids / nozen.banner
+        if cmd_processor.config_dirty {
+            cmd_processor.config_dirty = false;
+            match persist_config(&cmd_processor.config, &mut config_flash) {
+                Ok(()) => debug_write!(serial, "[CONFIG] Saved to flash\r\n"),
+                Err(e) => debug_write!(serial, "[ERROR] Config not saved: {}\r\n", e),
+            }
+        }
+        
+        // Send the nozen.uart.capture trace once its window closes
+        if let Some((lines, dropped)) = cmd_processor.finish_capture() {
+            debug_write!(serial, "[Capture] {} lines, {} dropped\r\n", lines, dropped);
+            for chunk in cmd_processor.capture.trace().chunks(64) {
+                let _ = serial.write(chunk);
             }
         }
         
@@ -267,3 +408,21 @@ fn main() -> ! {
         delay.delay_ms(1u8);
     }
 }
+
+/// Serialize the device config and program it into the config flash block
+fn persist_config(config: &DeviceConfig, config_flash: &mut ConfigFlash) -> Result<(), &'static str> {
+    let mut image = [0xFFu8; CONFIG_IMAGE_SIZE];
+    save_config(config, &mut image).map_err(|e| e.as_str())?;
+    config_flash.write(&image).map_err(|e| e.as_str())
+}
+
+/// Release the D+ pullup long enough for the host to see a disconnect, then
+/// reattach so it enumerates the device again. Firmware state is untouched,
+/// and so is usb_dev: the host sees the identity from boot, not a changed config.
+/// atsamd-hal's UsbBus doesn't expose detach, so CTRLB.DETACH is set directly.
+fn usb_reattach(delay: &mut Delay) {
+    let usb = unsafe { &*hal::pac::USB::ptr() };
+    usb.device().ctrlb.modify(|_, w| w.detach().set_bit());
+    delay.delay_ms(USB_REATTACH_MS);
+    usb.device().ctrlb.modify(|_, w| w.detach().clear_bit());
+}
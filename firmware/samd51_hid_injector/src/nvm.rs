@@ -0,0 +1,97 @@
+//! NVM Config Storage
+//! Erases, programs and reads the flash block holding the config image.
+//! The image format lives in flash_store.rs; this only moves bytes.
+
+use atsamd_hal as hal;
+use hal::pac::NVMCTRL;
+
+/// Start of the last 8KB erase block, kept out of FLASH in memory.x
+pub const CONFIG_ADDR: u32 = 0x000F_E000;
+
+/// Flash page, the most one WP command programs
+const PAGE_SIZE: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NvmError {
+    TooLarge,
+    Locked,
+    Program,
+}
+
+impl NvmError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NvmError::TooLarge => "Image larger than a flash page",
+            NvmError::Locked => "Config region locked",
+            NvmError::Program => "Flash program error",
+        }
+    }
+}
+
+pub struct ConfigFlash {
+    nvmctrl: NVMCTRL,
+}
+
+impl ConfigFlash {
+    pub fn new(nvmctrl: NVMCTRL) -> Self {
+        // Manual write mode: the page buffer is only committed by WP
+        nvmctrl.ctrla.modify(|_, w| w.wmode().man());
+        ConfigFlash { nvmctrl }
+    }
+
+    /// Copy the stored image into `image`; an erased block reads as 0xFF
+    pub fn read(&self, image: &mut [u8]) {
+        let base = CONFIG_ADDR as *const u8;
+        for (i, byte) in image.iter_mut().enumerate() {
+            *byte = unsafe { core::ptr::read_volatile(base.add(i)) };
+        }
+    }
+
+    /// Erase the config block and program `image` at its start
+    pub fn write(&mut self, image: &[u8]) -> Result<(), NvmError> {
+        if image.len() > PAGE_SIZE {
+            return Err(NvmError::TooLarge);
+        }
+
+        self.start();
+        self.nvmctrl.addr.write(|w| unsafe { w.addr().bits(CONFIG_ADDR) });
+        self.nvmctrl.ctrlb.write(|w| w.cmdex().key().cmd().eb());
+        self.finish()?;
+
+        self.start();
+        self.nvmctrl.ctrlb.write(|w| w.cmdex().key().cmd().pbc());
+        self.finish()?;
+
+        // The page buffer is loaded with whole 32-bit words at their flash address
+        let base = CONFIG_ADDR as *mut u32;
+        for (i, chunk) in image.chunks(4).enumerate() {
+            let mut word = [0xFFu8; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            unsafe { core::ptr::write_volatile(base.add(i), u32::from_le_bytes(word)) };
+        }
+
+        self.start();
+        self.nvmctrl.addr.write(|w| unsafe { w.addr().bits(CONFIG_ADDR) });
+        self.nvmctrl.ctrlb.write(|w| w.cmdex().key().cmd().wp());
+        self.finish()
+    }
+
+    /// Wait for the previous command and clear its status flags
+    fn start(&self) {
+        while self.nvmctrl.status.read().ready().bit_is_clear() {}
+        self.nvmctrl.intflag.write(|w| unsafe { w.bits(0xFFFF) });
+    }
+
+    /// Wait for the command just issued and report how it went
+    fn finish(&self) -> Result<(), NvmError> {
+        while self.nvmctrl.status.read().ready().bit_is_clear() {}
+        let flags = self.nvmctrl.intflag.read();
+        if flags.locke().bit_is_set() {
+            return Err(NvmError::Locked);
+        }
+        if flags.proge().bit_is_set() || flags.addre().bit_is_set() || flags.nvme().bit_is_set() {
+            return Err(NvmError::Program);
+        }
+        Ok(())
+    }
+}
@@ -1,23 +1,190 @@
 /// Command Protocol Parser
 /// Parses commands from USB CDC-ACM and formats them for FPGA UART
 
-use crate::recoil::{RecoilManager, parse_recoil_add, parse_recoil_name};
-use crate::state::MouseState;
+use crate::recoil::{RecoilManager, RecoilError, TimeUnit, parse_batch_segment, parse_recoil_add, parse_recoil_name, split_pattern_flags, MAX_PATTERNS, RECOIL_ADD_FORMAT_ERROR, MAX_PATTERN_NAME_LEN, MAX_PATTERN_STEPS, RECOIL_TICK_MS};
+use crate::state::{HeldInput, MouseState};
 use crate::descriptor_cache::DescriptorCache;
+use crate::descriptor::{DescriptorParser, ParseError, ReportField, ReportType, UsagePage, MAX_PADDING_RUNS, MAX_REPORT_ITEMS};
+use crate::timebase::{reports_per_second, CycleTimer, LatencyStats, LoopRate, Uptime};
+use crate::queue::{PendingStep, StepKind, StepQueue, MAX_QUEUE_DEPTH};
+use crate::hid::{ascii_to_scancode, key_name, modifier_from_name, parse_chord, scancode_to_ascii, KeyboardReport};
+use crate::humanizer::{HumanProfile, Humanizer, Wander, EASING_INTERVAL_MS};
+use crate::uart_rx::{LinkStats, UartCapture, UartHistory};
+use crate::settle::MoveSettle;
+use crate::uart_config::SercomRegs;
+use crate::flash_store::{is_valid_serial, is_valid_usb_string, DeviceConfig};
+use crate::codes::FpgaCode;
+
+/// Default screen size used to scale absolute moveto coordinates
+pub const DEFAULT_SCREEN_SIZE: (u16, u16) = (1920, 1080);
+
+/// Time between queued wheel notches when nozen.wheel.step is above 1
+pub const WHEEL_NOTCH_INTERVAL_MS: u32 = 8;
+
+/// How long nozen.click holds the button before the queued release
+pub const CLICK_HOLD_MS: u32 = 20;
+
+/// How long nozen.keyseq holds each chord, and the pause before the next one
+pub const CHORD_HOLD_MS: u32 = 20;
+pub const CHORD_GAP_MS: u32 = 50;
+
+/// Most chords one nozen.keyseq takes
+const KEYSEQ_MAX: usize = 16;
+
+/// Time between the reports of a moveto split by nozen.maxspeed
+/// (easing keeps its own EASING_INTERVAL_MS spacing)
+pub const SPLIT_INTERVAL_MS: u32 = 1;
+
+/// Pause at each corner of the nozen.calibrate box
+pub const CALIBRATE_PAUSE_MS: u32 = 250;
+
+/// Bytes of the last bad line kept for nozen.errors
+pub const BAD_LINE_KEEP: usize = 32;
+
+/// Most reports one nozen.bench may send; USB isn't polled while it runs
+pub const BENCH_MAX_COUNT: u16 = 1000;
+
+/// Shortest nozen.antiidle interval, so the jiggle never floods the link
+const ANTI_IDLE_MIN_MS: i16 = 100;
+
+/// Shortest nozen.deadman timeout, so it can't trip between normal commands
+const DEADMAN_MIN_MS: i16 = 100;
+
+/// Largest nozen.wander radius, and the shortest interval between its moves
+const WANDER_MAX_RADIUS: i16 = 1000;
+const WANDER_MIN_MS: i16 = 10;
+
+/// Longest token nozen.rtt echoes back
+pub const RTT_TOKEN_MAX: usize = 32;
+
+/// Identical "[ERROR]" responses less than this far apart are sent once
+pub const ERROR_COALESCE_MS: u32 = 500;
+
+/// Largest descriptor.export blob, sized so the import line still fits the
+/// 256-byte line buffer at two hex digits per byte
+pub const DESCRIPTOR_EXPORT_MAX: usize = (256 - b"nozen.descriptor.import{}".len()) / 2;
+
+/// Longest error response that can be coalesced
+const ERROR_KEEP: usize = 64;
 
 pub struct CommandProcessor {
     buffer: [u8; 256],
     index: usize,
     pub recoil_manager: RecoilManager,
     pub mouse_state: MouseState,
+    pub held: HeldInput,
     pub response_buffer: [u8; 256],
     pub response_len: usize,
+    /// Device (address, interface) that injections are aimed at
+    pub active_target: Option<(u8, u8)>,
+    /// Screen size (width, height) that moveto coordinates refer to
+    pub screen_size: (u16, u16),
+    /// Milliseconds from the main loop timebase, updated by tick()
+    pub now_ms: u32,
+    pub loop_rate: LoopRate,
+    /// Milliseconds since boot, extended past the 32-bit wrap
+    pub uptime: Uptime,
+    /// Host command to FPGA frame latency, recorded by the main loop
+    pub latency: LatencyStats,
+    /// Cycle counter for nozen.profile, installed by the main loop
+    pub cycle_timer: CycleTimer,
+    /// FPGA UART traffic counters
+    pub link: LinkStats,
+    /// Reports scheduled for later, drained by next_due()
+    pub pending: StepQueue,
+    /// Jitter / easing / timing variance applied to injected movement
+    pub humanizer: Humanizer,
+    /// Wheel units per notch; 1 sends nozen.wheel amounts as a single report
+    pub wheel_step: u8,
+    /// Truncate over-long recoil names with a warning instead of rejecting them
+    pub lenient: bool,
+    /// Refuse injection unless the active target's descriptor is cached
+    pub strict: bool,
+    /// Parse and track commands but send nothing to the FPGA
+    pub dry_run: bool,
+    /// Relative movement scale in percent (100 = unchanged)
+    pub sensitivity: u16,
+    /// Negate relative movement on each axis (x, y)
+    pub invert: (bool, bool),
+    /// nozen.move is dropped when both axes are below this (0 = off)
+    pub deadzone: u8,
+    /// Largest per-axis movement in one relative moveto report
+    pub max_speed: u8,
+    /// moveto updates the tracked position; off when the host tracks it itself
+    pub track: bool,
+    /// FPGA lines collected by nozen.uart.capture
+    pub capture: UartCapture,
+    /// Recent FPGA lines for nozen.uart.history
+    pub uart_history: UartHistory,
+    /// Echo each FPGA command back in canonical text form
+    pub canon: bool,
+    /// Button mask nozen.click uses when no button is given
+    pub default_button: u8,
+    /// Lines that were not recognized or failed to parse
+    pub bad_lines: u32,
+    /// Commands handled, by kind, for nozen.cmdstats
+    pub cmd_stats: CommandStats,
+    /// Start of the most recent bad line
+    pub last_bad_line: heapless::Vec<u8, BAD_LINE_KEEP>,
+    /// USB identity and banner, saved to flash and applied at the next boot
+    pub config: DeviceConfig,
+    /// Set when the config image needs rewriting; cleared by the main loop
+    pub config_dirty: bool,
+    /// nozen.moveto.verify in progress, corrected by "[DELTA:dx,dy]" FPGA lines
+    pub settle: Option<MoveSettle>,
+    /// Mode settings kept by nozen.mode.save until nozen.mode.restore
+    pub saved_mode: Option<ModeSnapshot>,
+    /// Report ID prefixed to injected mouse and keyboard reports, 0 for none
+    pub report_id: u8,
+    /// nozen.antiidle interval in ms, 0 when off
+    pub anti_idle_ms: u16,
+    /// When the next anti-idle jiggle is queued
+    anti_idle_due_ms: u32,
+    /// nozen.wander drift, None when off
+    pub wander: Option<Wander>,
+    /// Time between nozen.wander moves
+    pub wander_ms: u16,
+    /// When the next wander move is queued
+    wander_due_ms: u32,
+    /// nozen.deadman timeout in ms, 0 when off
+    pub deadman_ms: u16,
+    /// When the last host command arrived
+    last_command_ms: u32,
+    /// Deadman has released everything since the last command
+    deadman_tripped: bool,
+    /// Trip not yet reported by the main loop
+    deadman_notice: bool,
+    /// Startup banner handled for the current USB configuration
+    banner_sent: bool,
+    /// Last error response sent, for coalescing repeats
+    last_error: heapless::Vec<u8, ERROR_KEEP>,
+    /// Times last_error occurred in the current run
+    error_run: u16,
+    last_error_ms: u32,
 }
 
+/// Largest payload a Command can carry
+pub const MAX_PAYLOAD: usize = 128;
+
+/// Payload bytes the FPGA takes from an INJECT_MOUSE frame; anything longer
+/// is not forwarded
+pub const INJECT_MOUSE_LEN: usize = 5;
+
+/// Size of the buffer returned by Command::to_uart_frame
+pub const UART_FRAME_SIZE: usize = 256;
+
+/// Frame bytes around the payload: "[CMD:XX] [LEN:YYYY] " and " [CKSUM:ZZ]\n"
+const FRAME_HEADER_LEN: usize = 20;
+const FRAME_TRAILER_LEN: usize = 12;
+
+// A full-length payload must still fit the frame buffer
+const _: () = assert!(FRAME_HEADER_LEN + MAX_PAYLOAD + FRAME_TRAILER_LEN <= UART_FRAME_SIZE);
+
 #[derive(Debug, PartialEq)]
 pub struct Command {
     pub code: u8,
-    pub payload: [u8; 128],
+    pub payload: [u8; MAX_PAYLOAD],
+    /// Payload bytes in use; anything above MAX_PAYLOAD is treated as MAX_PAYLOAD
     pub length: usize,
 }
 
@@ -25,15 +192,53 @@ pub struct Command {
 pub enum CommandType {
     FpgaCommand(Command),  // Send to FPGA
     Response,              // Response ready in buffer
-    Restart,               // Restart device
+    Restart,               // Save pending config, then system reset
+    UartFlush,             // Discard buffered UART RX data
+    UsbReattach,           // Drop off the bus and re-enumerate, keeping state
+    Bench(u16),            // Send this many empty mouse reports and time them
+    UartRegs,              // Read the UART's SERCOM registers into the response
     NoOp,                  // No action needed
 }
 
 impl Command {
+    /// Build a command from `data`, truncated to MAX_PAYLOAD bytes
+    pub fn new(code: u8, data: &[u8]) -> Self {
+        debug_assert!(data.len() <= MAX_PAYLOAD, "payload exceeds MAX_PAYLOAD");
+        let length = data.len().min(MAX_PAYLOAD);
+        let mut payload = [0u8; MAX_PAYLOAD];
+        payload[..length].copy_from_slice(&data[..length]);
+        Command { code, payload, length }
+    }
+
+    /// Same command with `id` prefixed to the payload as its report ID byte
+    /// A full payload loses its last byte to make room.
+    pub fn with_report_id(&self, id: u8) -> Command {
+        let length = (self.payload_len() + 1).min(MAX_PAYLOAD);
+        let mut payload = [0u8; MAX_PAYLOAD];
+        payload[0] = id;
+        payload[1..length].copy_from_slice(&self.payload[..length - 1]);
+        Command { code: self.code, payload, length }
+    }
+
+    /// Payload length clamped to MAX_PAYLOAD, so the fields can't index
+    /// past the payload array or the frame buffer
+    pub fn payload_len(&self) -> usize {
+        self.length.min(MAX_PAYLOAD)
+    }
+
+    /// Number of meaningful bytes at the start of to_uart_frame()
+    pub fn uart_frame_len(&self) -> usize {
+        FRAME_HEADER_LEN + self.payload_len() + FRAME_TRAILER_LEN
+    }
+
     /// Convert command to UART frame for FPGA
-    pub fn to_uart_frame(&self) -> [u8; 256] {
-        let mut frame = [0u8; 256];
+    /// Cannot index past the buffer: the payload is clamped to MAX_PAYLOAD and
+    /// the header, payload and trailer together fit UART_FRAME_SIZE (checked
+    /// at compile time above).
+    pub fn to_uart_frame(&self) -> [u8; UART_FRAME_SIZE] {
+        let mut frame = [0u8; UART_FRAME_SIZE];
         let mut idx = 0;
+        let length = self.payload_len();
         
         // Frame format: [CMD:XX] [LEN:YYYY] [PAYLOAD...] [CKSUM:ZZ]\n
         
@@ -49,36 +254,369 @@ impl Command {
         // Length
         frame[idx..idx+5].copy_from_slice(b"[LEN:");
         idx += 5;
-        frame[idx] = hex_digit((self.length >> 12) as u8);
-        frame[idx+1] = hex_digit(((self.length >> 8) & 0x0F) as u8);
-        frame[idx+2] = hex_digit(((self.length >> 4) & 0x0F) as u8);
-        frame[idx+3] = hex_digit((self.length & 0x0F) as u8);
+        frame[idx] = hex_digit((length >> 12) as u8);
+        frame[idx+1] = hex_digit(((length >> 8) & 0x0F) as u8);
+        frame[idx+2] = hex_digit(((length >> 4) & 0x0F) as u8);
+        frame[idx+3] = hex_digit((length & 0x0F) as u8);
         idx += 4;
         frame[idx..idx+2].copy_from_slice(b"] ");
         idx += 2;
+        debug_assert_eq!(idx, FRAME_HEADER_LEN);
         
         // Payload (raw binary)
-        for i in 0..self.length {
-            frame[idx] = self.payload[i];
-            idx += 1;
-        }
+        frame[idx..idx + length].copy_from_slice(&self.payload[..length]);
+        idx += length;
         frame[idx] = b' ';
         idx += 1;
         
-        // Checksum (simple sum of all bytes)
-        let mut cksum = self.code;
-        for i in 0..self.length {
-            cksum = cksum.wrapping_add(self.payload[i]);
-        }
+        let cksum = self.checksum();
         frame[idx..idx+7].copy_from_slice(b"[CKSUM:");
         idx += 7;
         frame[idx] = hex_digit(cksum >> 4);
         frame[idx+1] = hex_digit(cksum & 0x0F);
-        let _idx = idx + 2;
-        frame[_idx.._idx+2].copy_from_slice(b"]\n");
+        idx += 2;
+        frame[idx..idx+2].copy_from_slice(b"]\n");
+        debug_assert_eq!(idx + 2, self.uart_frame_len());
         
         frame
     }
+
+    /// Frame checksum: 8-bit wrapping sum of the code, both length bytes
+    /// and the payload. Covering the length (as the gateware does) means a
+    /// corrupted LEN field no longer passes with a matching payload sum.
+    pub fn checksum(&self) -> u8 {
+        let length = self.payload_len();
+        let mut cksum = self.code
+            .wrapping_add(length as u8)
+            .wrapping_add((length >> 8) as u8);
+        for &b in &self.payload[..length] {
+            cksum = cksum.wrapping_add(b);
+        }
+        cksum
+    }
+
+    /// Normalized text form of what the command will inject
+    /// Written as the nozen commands that, sent in order from a released state,
+    /// produce the same report. Pan has no command and isn't shown; codes no
+    /// command produces fall back to their frame header.
+    pub fn canonical(&self) -> heapless::String<192> {
+        use core::fmt::Write;
+
+        let mut out = heapless::String::new();
+        let payload = &self.payload[..self.payload_len()];
+        match (FpgaCode::from_code(self.code), payload) {
+            (Some(FpgaCode::InjectMouse), &[buttons, dx, dy, wheel, ..]) => {
+                let (dx, dy, wheel) = (dx as i8, dy as i8, wheel as i8);
+                if buttons != 0 {
+                    let _ = write!(out, "nozen.mouse.buttons(0x{:02X}) ", buttons);
+                }
+                if dx != 0 || dy != 0 || (wheel == 0 && buttons == 0) {
+                    let _ = write!(out, "nozen.move({},{}) ", dx, dy);
+                }
+                if wheel != 0 {
+                    let _ = write!(out, "nozen.wheel({}) ", wheel);
+                }
+            }
+            (Some(FpgaCode::InjectKbd), &[modifiers, _, ref keys @ ..]) => {
+                for name in ["ctrl", "shift", "alt", "gui", "rctrl", "rshift", "ralt", "rgui"] {
+                    if modifier_from_name(name.as_bytes()).is_some_and(|bit| modifiers & bit != 0) {
+                        let _ = write!(out, "nozen.mod({},1) ", name);
+                    }
+                }
+                for &key in keys.iter().filter(|&&k| k != 0) {
+                    let _ = write!(out, "nozen.key({},1) ", key);
+                }
+                if out.is_empty() {
+                    let _ = write!(out, "nozen.releaseall ");
+                }
+            }
+            _ => {
+                let _ = write!(out, "[CMD:{:02X}] [LEN:{}] ", self.code, payload.len());
+            }
+        }
+        out.pop();
+        out
+    }
+}
+
+/// Check a frame produced by to_uart_frame: header, length, payload and
+/// checksum must all agree
+pub fn verify_uart_frame(frame: &[u8]) -> bool {
+    if frame.len() < FRAME_HEADER_LEN || &frame[0..5] != b"[CMD:" || &frame[7..14] != b"] [LEN:" || &frame[18..20] != b"] " {
+        return false;
+    }
+    let code = match parse_hex_byte(frame[5], frame[6]) {
+        Some(v) => v,
+        None => return false,
+    };
+    let length = match (parse_hex_byte(frame[14], frame[15]), parse_hex_byte(frame[16], frame[17])) {
+        (Some(hi), Some(lo)) => ((hi as usize) << 8) | lo as usize,
+        _ => return false,
+    };
+    if length > MAX_PAYLOAD {
+        return false;
+    }
+
+    // Payload, then " [CKSUM:ZZ]\n"
+    let tail = FRAME_HEADER_LEN + length;
+    if frame.len() < tail + FRAME_TRAILER_LEN || &frame[tail..tail + 8] != b" [CKSUM:" || &frame[tail + 10..tail + 12] != b"]\n" {
+        return false;
+    }
+    let received = match parse_hex_byte(frame[tail + 8], frame[tail + 9]) {
+        Some(v) => v,
+        None => return false,
+    };
+
+    Command::new(code, &frame[FRAME_HEADER_LEN..tail]).checksum() == received
+}
+
+/// Handler signature shared by every entry in the command table
+type CommandHandler = fn(&mut CommandProcessor, &[u8], &mut DescriptorCache) -> CommandType;
+
+/// Dispatch table entry: command prefix and the handler it routes to
+struct CommandEntry {
+    prefix: &'static [u8],
+    handler: CommandHandler,
+}
+
+/// All supported "nozen." commands
+/// Used by parse_line for dispatch and by nozen.help for listing.
+/// Order does not matter: dispatch picks the longest matching prefix.
+const COMMAND_TABLE: &[CommandEntry] = &[
+    CommandEntry { prefix: b"nozen.move(", handler: |p, line, cache| p.parse_mouse_move(line, cache) },
+    CommandEntry { prefix: b"nozen.moveto(", handler: |p, line, cache| p.parse_mouse_moveto(line, cache) },
+    CommandEntry { prefix: b"nozen.moveto.verify(", handler: |p, line, cache| p.handle_moveto_verify(line, cache) },
+    CommandEntry { prefix: b"nozen.left(", handler: |p, line, cache| p.parse_button_command(line, cache, 0x01, b"nozen.left(") },
+    CommandEntry { prefix: b"nozen.right(", handler: |p, line, cache| p.parse_button_command(line, cache, 0x02, b"nozen.right(") },
+    CommandEntry { prefix: b"nozen.middle(", handler: |p, line, cache| p.parse_button_command(line, cache, 0x04, b"nozen.middle(") },
+    CommandEntry { prefix: b"nozen.side1(", handler: |p, line, cache| p.parse_button_command(line, cache, 0x08, b"nozen.side1(") },
+    CommandEntry { prefix: b"nozen.side2(", handler: |p, line, cache| p.parse_button_command(line, cache, 0x10, b"nozen.side2(") },
+    CommandEntry { prefix: b"nozen.click(", handler: |p, line, _| p.handle_click(line) },
+    CommandEntry { prefix: b"nozen.tap(", handler: |p, line, _| p.handle_tap(line) },
+    CommandEntry { prefix: b"nozen.mouse.buttons(", handler: |p, line, cache| p.handle_mouse_buttons(line, cache) },
+    CommandEntry { prefix: b"nozen.button.default(", handler: |p, line, _| p.handle_button_default(line) },
+    CommandEntry { prefix: b"nozen.wheel(", handler: |p, line, _| p.parse_wheel_command(line) },
+    CommandEntry { prefix: b"nozen.wheel.step(", handler: |p, line, _| p.handle_wheel_step(line) },
+    CommandEntry { prefix: b"nozen.key(", handler: |p, line, _| p.handle_key(line) },
+    CommandEntry { prefix: b"nozen.mod(", handler: |p, line, _| p.handle_mod(line) },
+    CommandEntry { prefix: b"nozen.held", handler: |p, _, _| p.handle_held() },
+    CommandEntry { prefix: b"nozen.key.isheld(", handler: |p, line, _| p.handle_key_isheld(line) },
+    CommandEntry { prefix: b"nozen.keys.table", handler: |p, line, _| p.handle_keys_table(line) },
+    CommandEntry { prefix: b"nozen.key.hold(", handler: |p, line, _| p.handle_key_hold(line) },
+    CommandEntry { prefix: b"nozen.typeseq(", handler: |p, line, _| p.handle_typeseq(line) },
+    CommandEntry { prefix: b"nozen.keyseq(", handler: |p, line, _| p.handle_keyseq(line) },
+    CommandEntry { prefix: b"nozen.calibrate(", handler: |p, line, _| p.handle_calibrate(line) },
+    CommandEntry { prefix: b"nozen.getpos", handler: |p, _, _| p.handle_getpos() },
+    CommandEntry { prefix: b"nozen.recoil.add(", handler: |p, line, _| p.handle_recoil_add(line) },
+    CommandEntry { prefix: b"nozen.recoil.batch{", handler: |p, line, _| p.handle_recoil_batch(line) },
+    CommandEntry { prefix: b"nozen.recoil.delete(", handler: |p, line, _| p.handle_recoil_delete(line) },
+    CommandEntry { prefix: b"nozen.recoil.list", handler: |p, _, _| p.handle_recoil_list() },
+    CommandEntry { prefix: b"nozen.recoil.get(", handler: |p, line, _| p.handle_recoil_get(line) },
+    CommandEntry { prefix: b"nozen.recoil.step(", handler: |p, line, _| p.handle_recoil_step(line) },
+    CommandEntry { prefix: b"nozen.recoil.setstep(", handler: |p, line, _| p.handle_recoil_setstep(line) },
+    CommandEntry { prefix: b"nozen.recoil.fire(", handler: |p, line, _| p.handle_recoil_fire(line) },
+    CommandEntry { prefix: b"nozen.recoil.run(", handler: |p, line, _| p.handle_recoil_run(line) },
+    CommandEntry { prefix: b"nozen.recoil.chain(", handler: |p, line, _| p.handle_recoil_chain(line) },
+    CommandEntry { prefix: b"nozen.recoil.mirror(", handler: |p, line, _| p.handle_recoil_mirror(line) },
+    CommandEntry { prefix: b"nozen.recoil.rescale(", handler: |p, line, _| p.handle_recoil_rescale(line) },
+    CommandEntry { prefix: b"nozen.recoil.smooth(", handler: |p, line, _| p.handle_recoil_smooth(line) },
+    CommandEntry { prefix: b"nozen.recoil.resample(", handler: |p, line, _| p.handle_recoil_resample(line) },
+    CommandEntry { prefix: b"nozen.recoil.names", handler: |p, _, _| p.handle_recoil_names() },
+    CommandEntry { prefix: b"nozen.recoil.timeunit", handler: |p, line, _| p.handle_recoil_timeunit(line) },
+    CommandEntry { prefix: b"nozen.print(", handler: |p, line, _| p.handle_print(line) },
+    CommandEntry { prefix: b"nozen.descriptor.get(", handler: |p, line, cache| p.handle_descriptor_get(line, cache) },
+    CommandEntry { prefix: b"nozen.descriptor.export", handler: |p, _, cache| p.handle_descriptor_export(cache) },
+    CommandEntry { prefix: b"nozen.descriptor.import{", handler: |p, line, cache| p.handle_descriptor_import(line, cache) },
+    CommandEntry { prefix: b"nozen.descriptor.expect(", handler: |p, line, cache| p.handle_descriptor_expect(line, cache) },
+    CommandEntry { prefix: b"nozen.descriptor.validate(", handler: |p, line, cache| p.handle_descriptor_validate(line, cache) },
+    CommandEntry { prefix: b"nozen.descriptor.pin(", handler: |p, line, cache| p.handle_descriptor_pin(line, cache, true) },
+    CommandEntry { prefix: b"nozen.descriptor.unpin(", handler: |p, line, cache| p.handle_descriptor_pin(line, cache, false) },
+    CommandEntry { prefix: b"nozen.descriptor.csv(", handler: |p, line, cache| p.handle_descriptor_csv(line, cache) },
+    CommandEntry { prefix: b"nozen.descriptor.sens(", handler: |p, line, cache| p.handle_descriptor_sens(line, cache) },
+    CommandEntry { prefix: b"nozen.descriptor.bits(", handler: |p, line, cache| p.handle_descriptor_bits(line, cache) },
+    CommandEntry { prefix: b"nozen.descriptor.axes(", handler: |p, line, cache| p.handle_descriptor_axes(line, cache) },
+    CommandEntry { prefix: b"nozen.descriptor.info(", handler: |p, line, cache| p.handle_descriptor_info(line, cache) },
+    CommandEntry { prefix: b"nozen.descriptor.stats", handler: |p, _, cache| p.handle_descriptor_stats(cache) },
+    CommandEntry { prefix: b"nozen.reportid(", handler: |p, line, _| p.handle_reportid(line) },
+    CommandEntry { prefix: b"nozen.reportids", handler: |p, line, cache| p.handle_reportids(line, cache) },
+    CommandEntry { prefix: b"nozen.target(", handler: |p, line, cache| p.handle_target(line, cache) },
+    CommandEntry { prefix: b"nozen.target.clear", handler: |p, _, _| p.handle_target_clear() },
+    CommandEntry { prefix: b"nozen.screen(", handler: |p, line, _| p.handle_screen(line) },
+    CommandEntry { prefix: b"nozen.lenient(", handler: |p, line, _| p.handle_lenient(line) },
+    CommandEntry { prefix: b"nozen.strict(", handler: |p, line, _| p.handle_strict(line) },
+    CommandEntry { prefix: b"nozen.canon(", handler: |p, line, _| p.handle_canon(line) },
+    CommandEntry { prefix: b"nozen.track(", handler: |p, line, _| p.handle_track(line) },
+    CommandEntry { prefix: b"nozen.dryrun(", handler: |p, line, _| p.handle_dryrun(line) },
+    CommandEntry { prefix: b"nozen.deadzone(", handler: |p, line, _| p.handle_deadzone(line) },
+    CommandEntry { prefix: b"nozen.sensitivity(", handler: |p, line, _| p.handle_sensitivity(line) },
+    CommandEntry { prefix: b"nozen.maxspeed(", handler: |p, line, _| p.handle_maxspeed(line) },
+    CommandEntry { prefix: b"nozen.invert(", handler: |p, line, _| p.handle_invert(line) },
+    CommandEntry { prefix: b"nozen.mode", handler: |p, _, cache| p.handle_mode(cache) },
+    CommandEntry { prefix: b"nozen.mode.save", handler: |p, _, _| p.handle_mode_save() },
+    CommandEntry { prefix: b"nozen.mode.restore", handler: |p, _, _| p.handle_mode_restore() },
+    CommandEntry { prefix: b"nozen.deadman(", handler: |p, line, _| p.handle_deadman(line) },
+    CommandEntry { prefix: b"nozen.antiidle(", handler: |p, line, _| p.handle_antiidle(line) },
+    CommandEntry { prefix: b"nozen.wander(", handler: |p, line, _| p.handle_wander(line) },
+    CommandEntry { prefix: b"nozen.human(", handler: |p, line, _| p.handle_human(line) },
+    CommandEntry { prefix: b"nozen.errors", handler: |p, _, _| p.handle_errors() },
+    CommandEntry { prefix: b"nozen.errors.clear", handler: |p, _, _| p.handle_errors_clear() },
+    CommandEntry { prefix: b"nozen.banner", handler: |p, line, _| p.handle_banner(line) },
+    CommandEntry { prefix: b"nozen.serial", handler: |p, line, _| p.handle_serial(line) },
+    CommandEntry { prefix: b"nozen.uptime", handler: |p, _, _| p.handle_uptime() },
+    CommandEntry { prefix: b"nozen.rtt", handler: |p, line, _| p.handle_rtt(line) },
+    CommandEntry { prefix: b"nozen.loopfreq", handler: |p, _, _| p.handle_loopfreq() },
+    CommandEntry { prefix: b"nozen.telemetry", handler: |p, line, cache| p.handle_telemetry(line, cache) },
+    CommandEntry { prefix: b"nozen.bench(", handler: |p, line, _| p.handle_bench(line) },
+    CommandEntry { prefix: b"nozen.cmdstats", handler: |p, _, _| p.handle_cmdstats() },
+    CommandEntry { prefix: b"nozen.cmdstats.reset", handler: |p, _, _| p.handle_cmdstats_reset() },
+    CommandEntry { prefix: b"nozen.codes", handler: |p, _, _| p.handle_codes() },
+    CommandEntry { prefix: BUFFER_COMMAND, handler: |p, _, _| p.handle_buffer() },
+    CommandEntry { prefix: b"nozen.queue.depth", handler: |p, _, _| p.handle_queue_depth() },
+    CommandEntry { prefix: b"nozen.queue.list", handler: |p, _, _| p.handle_queue_list() },
+    CommandEntry { prefix: b"nozen.restart", handler: |_, _, _| CommandType::Restart },
+    CommandEntry { prefix: b"nozen.uart.capture(", handler: |p, line, _| p.handle_uart_capture(line) },
+    CommandEntry { prefix: b"nozen.uart.history", handler: |p, _, _| p.handle_uart_history() },
+    CommandEntry { prefix: b"nozen.uart.flush", handler: |_, _, _| CommandType::UartFlush },
+    CommandEntry { prefix: b"nozen.profile(", handler: |p, line, cache| p.handle_profile(line, cache) },
+    CommandEntry { prefix: b"nozen.uart.regs", handler: |_, _, _| CommandType::UartRegs },
+    CommandEntry { prefix: b"nozen.usb.ids", handler: |p, line, _| p.handle_usb_ids(line) },
+    CommandEntry { prefix: b"nozen.usb.strings", handler: |p, line, _| p.handle_usb_strings(line) },
+    CommandEntry { prefix: b"nozen.usb.reattach", handler: |_, _, _| CommandType::UsbReattach },
+    CommandEntry { prefix: b"nozen.releaseall", handler: |p, _, _| p.handle_releaseall() },
+    CommandEntry { prefix: b"nozen.reset", handler: |p, _, _| p.handle_reset() },
+    CommandEntry { prefix: b"nozen.frame(", handler: |p, line, cache| p.handle_frame(line, cache) },
+    CommandEntry { prefix: b"nozen.help", handler: |p, line, _| p.handle_help(line) },
+];
+
+/// Commands handled since boot (or nozen.cmdstats.reset), grouped by kind
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CommandStats {
+    pub moves: u32,
+    /// Clicks and button presses/releases
+    pub clicks: u32,
+    pub wheels: u32,
+    /// Key presses, holds, modifiers and typed sequences
+    pub keys: u32,
+    /// Recoil patterns played, singly or chained, and single steps fired
+    pub recoil_runs: u32,
+    pub other: u32,
+}
+
+impl CommandStats {
+    /// Count a command dispatched through the given table prefix
+    /// (the cmdstats commands themselves are left out)
+    fn record(&mut self, prefix: &[u8]) {
+        let counter = match prefix {
+            b"nozen.cmdstats" | b"nozen.cmdstats.reset" => return,
+            b"nozen.move(" | b"nozen.moveto(" | b"nozen.moveto.verify(" | b"nozen.calibrate(" => &mut self.moves,
+            b"nozen.click(" | b"nozen.tap(" | b"nozen.left(" | b"nozen.right(" | b"nozen.middle("
+            | b"nozen.side1(" | b"nozen.side2(" | b"nozen.mouse.buttons(" => &mut self.clicks,
+            b"nozen.wheel(" => &mut self.wheels,
+            b"nozen.key(" | b"nozen.key.hold(" | b"nozen.mod(" | b"nozen.typeseq("
+            | b"nozen.keyseq(" => &mut self.keys,
+            b"nozen.recoil.run(" | b"nozen.recoil.chain(" | b"nozen.recoil.fire(" => &mut self.recoil_runs,
+            _ => &mut self.other,
+        };
+        *counter = counter.saturating_add(1);
+    }
+
+    /// Format as string for display
+    pub fn format(&self) -> heapless::String<96> {
+        use core::fmt::Write;
+        let mut s = heapless::String::new();
+        let _ = write!(s, "Moves:{} Clicks:{} Wheel:{} Keys:{} Recoil:{} Other:{}",
+            self.moves,
+            self.clicks,
+            self.wheels,
+            self.keys,
+            self.recoil_runs,
+            self.other
+        );
+        s
+    }
+}
+
+/// True for table prefixes whose commands inject reports, now or later, as
+/// refused by nozen.strict. Only for strict mode: nozen.frame uses the
+/// narrower builds_frame list.
+fn injects(prefix: &[u8]) -> bool {
+    matches!(prefix,
+        b"nozen.move(" | b"nozen.moveto(" | b"nozen.moveto.verify(" | b"nozen.calibrate("
+        | b"nozen.click(" | b"nozen.tap(" | b"nozen.left(" | b"nozen.right(" | b"nozen.middle("
+        | b"nozen.side1(" | b"nozen.side2(" | b"nozen.mouse.buttons(" | b"nozen.wheel("
+        | b"nozen.key(" | b"nozen.key.hold(" | b"nozen.mod(" | b"nozen.typeseq(" | b"nozen.keyseq("
+        | b"nozen.recoil.run(" | b"nozen.recoil.chain(" | b"nozen.recoil.fire("
+        | b"nozen.bench(" | b"nozen.frame(" | b"nozen.wander(" | b"nozen.antiidle(")
+}
+
+/// True for table prefixes whose handler builds one FPGA frame and touches
+/// nothing but position, held inputs and pending steps, so nozen.frame can
+/// roll it back
+fn builds_frame(prefix: &[u8]) -> bool {
+    matches!(prefix,
+        b"nozen.move(" | b"nozen.moveto(" | b"nozen.click(" | b"nozen.tap(" | b"nozen.left("
+        | b"nozen.right(" | b"nozen.middle(" | b"nozen.side1(" | b"nozen.side2("
+        | b"nozen.mouse.buttons(" | b"nozen.wheel(" | b"nozen.key(" | b"nozen.key.hold("
+        | b"nozen.mod(" | b"nozen.recoil.fire(")
+}
+
+/// The settings nozen.mode shows, saved by nozen.mode.save
+/// (coordinates follow the target, so they aren't part of it)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModeSnapshot {
+    pub dry_run: bool,
+    pub human: HumanProfile,
+    pub sensitivity: u16,
+    pub max_speed: u8,
+    pub invert: (bool, bool),
+    pub canon: bool,
+    pub deadzone: u8,
+    pub wheel_step: u8,
+    pub strict: bool,
+    pub track: bool,
+    pub report_id: u8,
+}
+
+/// Reports the parser's pending bytes; matched at the end of a line by parse()
+const BUFFER_COMMAND: &[u8] = b"nozen.buffer";
+
+/// Find the entry whose prefix is the longest match for the line
+/// so "nozen.recoil.listall" is never captured by "nozen.recoil.list"
+fn find_command<'a>(table: &'a [CommandEntry], line: &[u8]) -> Option<&'a CommandEntry> {
+    table.iter()
+        .filter(|entry| line.starts_with(entry.prefix))
+        .max_by_key(|entry| entry.prefix.len())
+}
+
+/// Bytes reserved on each help page for the header and "More:" footer
+const HELP_OVERHEAD: usize = 48;
+
+/// Response when a command's steps don't fit the pending-step queue
+const QUEUE_FULL: &[u8] = b"[ERROR] Queue full\n";
+
+/// Response when a recoil command names a pattern that isn't stored
+const PATTERN_NOT_FOUND: &[u8] = b"[ERROR] Pattern not found\n";
+
+/// Header row of nozen.descriptor.csv
+const CSV_HEADER: &[u8] = b"report_id,report_type,usage_page,usage_id,bit_offset,bit_size,relative,array\n";
+
+/// Longest "More:" footer of nozen.descriptor.csv
+const CSV_FOOTER_LEN: usize = "More: nozen.descriptor.csv(255,255,255)\n".len();
+
+/// Sections in the nozen.telemetry report
+const TELEMETRY_SECTIONS: usize = 6;
+
+/// Rows per nozen.reportids page; the longest row is 24 bytes, so eight
+/// fit the response buffer alongside the header and "More:" footer
+const REPORTIDS_PER_PAGE: usize = 8;
+
+/// Rows per nozen.keys.table page; the longest row is 13 bytes ("~ 0x35 shift")
+const KEYS_PER_PAGE: usize = 16;
+
+/// Command name as shown by nozen.help ("nozen.recoil.add(" -> "recoil.add")
+fn command_name(prefix: &'static [u8]) -> &'static [u8] {
+    let name = prefix.strip_prefix(b"nozen.").unwrap_or(prefix);
+    name.strip_suffix(b"(").unwrap_or(name)
 }
 
 fn parse_int(data: &[u8]) -> Option<i16> {
@@ -153,9 +691,129 @@ impl CommandProcessor {
             index: 0,
             recoil_manager: RecoilManager::new(),
             mouse_state: MouseState::new(),
+            held: HeldInput::new(),
             response_buffer: [0u8; 256],
             response_len: 0,
+            active_target: None,
+            screen_size: DEFAULT_SCREEN_SIZE,
+            now_ms: 0,
+            loop_rate: LoopRate::new(),
+            uptime: Uptime::new(),
+            latency: LatencyStats::new(),
+            cycle_timer: CycleTimer::stopped(),
+            link: LinkStats::new(),
+            pending: StepQueue::new(),
+            humanizer: Humanizer::new(0x2545_F491),
+            wheel_step: 1,
+            lenient: false,
+            strict: false,
+            dry_run: false,
+            sensitivity: 100,
+            invert: (false, false),
+            deadzone: 0,
+            max_speed: 127,
+            track: true,
+            capture: UartCapture::new(),
+            uart_history: UartHistory::new(),
+            canon: false,
+            default_button: 0x01,
+            bad_lines: 0,
+            cmd_stats: CommandStats::default(),
+            last_bad_line: heapless::Vec::new(),
+            config: DeviceConfig::new(),
+            config_dirty: false,
+            settle: None,
+            saved_mode: None,
+            report_id: 0,
+            anti_idle_ms: 0,
+            anti_idle_due_ms: 0,
+            wander: None,
+            wander_ms: 0,
+            wander_due_ms: 0,
+            deadman_ms: 0,
+            last_command_ms: 0,
+            deadman_tripped: false,
+            deadman_notice: false,
+            banner_sent: false,
+            last_error: heapless::Vec::new(),
+            error_run: 0,
+            last_error_ms: 0,
+        }
+    }
+    
+    /// Called once per main loop iteration with the current time
+    pub fn tick(&mut self, now_ms: u32, loop_counter: u32) {
+        self.now_ms = now_ms;
+        self.loop_rate.sample(loop_counter, now_ms);
+        self.uptime.sample(now_ms);
+        self.poll_anti_idle();
+        self.poll_wander();
+        self.poll_deadman();
+    }
+    
+    /// Release everything once no command has arrived for nozen.deadman ms
+    /// Trips once per silence; the next command arms it again.
+    fn poll_deadman(&mut self) {
+        if self.deadman_ms == 0 || self.deadman_tripped
+            || self.now_ms.wrapping_sub(self.last_command_ms) < self.deadman_ms as u32 {
+            return;
+        }
+        self.deadman_tripped = true;
+        self.deadman_notice = true;
+        // Queued behind the keyboard release that release_all leaves
+        let release = self.release_all();
+        let _ = self.pending.push(PendingStep::new(self.now_ms, release.code, &release.payload[..release.length]));
+    }
+    
+    /// True once after the deadman trips, for the main loop to report it
+    pub fn take_deadman_trip(&mut self) -> bool {
+        core::mem::take(&mut self.deadman_notice)
+    }
+    
+    /// True once each time USB becomes configured, for the main loop to send
+    /// the startup banner, unless nozen.banner(off) is set
+    pub fn take_startup_banner(&mut self, usb_configured: bool) -> bool {
+        if !usb_configured {
+            self.banner_sent = false;
+            return false;
+        }
+        !core::mem::replace(&mut self.banner_sent, true) && self.config.banner
+    }
+    
+    /// Queue the nozen.antiidle jiggle once its interval has elapsed
+    /// One px right then one px left, so the tracked position never moves.
+    /// A full queue skips this round rather than sending half the pair.
+    fn poll_anti_idle(&mut self) {
+        if self.anti_idle_ms == 0 || (self.now_ms.wrapping_sub(self.anti_idle_due_ms) as i32) < 0 {
+            return;
+        }
+        self.anti_idle_due_ms = self.now_ms.wrapping_add(self.anti_idle_ms as u32);
+        if self.pending.reserve(2).is_err() {
+            return;
         }
+        let _ = self.pending.push(PendingStep::new(self.now_ms, FpgaCode::InjectMouse as u8, &self.mouse_report(1, 0)));
+        let _ = self.pending.push(PendingStep::new(self.now_ms, FpgaCode::InjectMouse as u8, &self.mouse_report(-1, 0)));
+    }
+    
+    /// Queue the next nozen.wander move once its interval has elapsed
+    /// The tracked position follows the drift.
+    fn poll_wander(&mut self) {
+        if (self.now_ms.wrapping_sub(self.wander_due_ms) as i32) < 0 {
+            return;
+        }
+        let Some(wander) = self.wander.as_mut() else {
+            return;
+        };
+        self.wander_due_ms = self.now_ms.wrapping_add(self.wander_ms as u32);
+        if self.pending.reserve(1).is_err() {
+            return;
+        }
+        let (dx, dy) = wander.next_step();
+        if (dx, dy) == (0, 0) {
+            return;
+        }
+        self.mouse_state.update_relative(dx, dy);
+        let _ = self.pending.push(PendingStep::new(self.now_ms, FpgaCode::InjectMouse as u8, &self.mouse_report(dx, dy)));
     }
     
     /// Parse incoming data from USB and extract commands
@@ -164,13 +822,43 @@ impl CommandProcessor {
         
         for &byte in data {
             if byte == b'\n' || byte == b'\r' {
+                // Empty lines are skipped, which also collapses "\r\n" and
+                // "\n\r" into a single boundary
+                if self.index == 0 {
+                    continue;
+                }
+                
+                // "nozen.buffer" inspects whatever was stuck ahead of it,
+                // which stays buffered so the line can still be completed
+                if self.buffer[..self.index].ends_with(BUFFER_COMMAND) {
+                    self.index -= BUFFER_COMMAND.len();
+                    return self.handle_buffer();
+                }
+                
                 // Process line - copy to avoid borrow checker issues
                 let mut line_buf = [0u8; 256];
                 let line_len = self.index;
                 line_buf[..line_len].copy_from_slice(&self.buffer[..line_len]);
                 self.index = 0;
                 
-                return self.parse_line(&line_buf[..line_len], descriptor_cache);
+                let result = match self.parse_line(&line_buf[..line_len], descriptor_cache) {
+                    CommandType::NoOp => {
+                        self.record_bad_line(&line_buf[..line_len]);
+                        CommandType::NoOp
+                    }
+                    CommandType::FpgaCommand(cmd) if self.dry_run => {
+                        let cmd = self.tag_report(cmd);
+                        self.dry_run_response(&cmd)
+                    }
+                    CommandType::FpgaCommand(cmd) => {
+                        if self.canon {
+                            self.echo_canonical(&cmd);
+                        }
+                        CommandType::FpgaCommand(self.tag_report(cmd))
+                    }
+                    other => other,
+                };
+                return self.coalesce_errors(result);
             } else if self.index < self.buffer.len() {
                 self.buffer[self.index] = byte;
                 self.index += 1;
@@ -180,6 +868,193 @@ impl CommandProcessor {
         CommandType::NoOp
     }
     
+    /// Release every held input, e.g. when the host suspends
+    /// Returns the neutral mouse report; the neutral keyboard report is queued
+    /// for the main loop to send right after it.
+    pub fn on_suspend(&mut self) -> Command {
+        let cmd = self.release_all();
+        self.tag_report(cmd)
+    }
+    
+    /// Next queued report whose due time has been reached
+    /// The main loop drains this every iteration and sends each to the FPGA
+    /// In dry-run mode due steps are dropped instead
+    pub fn next_due(&mut self) -> Option<Command> {
+        let mut step = self.pending.pop_due(self.now_ms)?;
+        while self.dry_run {
+            step = self.pending.pop_due(self.now_ms)?;
+        }
+        Some(self.tag_report(Command::new(step.code, &step.data[..step.length as usize])))
+    }
+    
+    /// Prefix the report ID forced with nozen.reportid to mouse and keyboard reports
+    fn tag_report(&self, cmd: Command) -> Command {
+        match (self.report_id, FpgaCode::from_code(cmd.code)) {
+            (0, _) => cmd,
+            (id, Some(FpgaCode::InjectKbd | FpgaCode::InjectMouse)) => cmd.with_report_id(id),
+            _ => cmd,
+        }
+    }
+    
+    /// Feed a complete line received from the FPGA
+    /// A "[DELTA:dx,dy]" line feeds a nozen.moveto.verify in progress: the
+    /// correction is queued, or the outcome is left in the response buffer
+    pub fn on_uart_line(&mut self, line: &[u8]) {
+        self.uart_history.push(line);
+        self.capture.record(line, self.now_ms);
+        self.link.record_rx(line);
+        
+        if let (Some(settle), Some((dx, dy))) = (self.settle.as_mut(), parse_delta_line(line)) {
+            settle.observe(dx, dy);
+            match settle.next_move() {
+                Some((x, y)) => {
+                    let report = self.mouse_report(x, y);
+                    if self.pending.push(PendingStep::new(self.now_ms, FpgaCode::InjectMouse as u8, &report)).is_err() {
+                        self.settle = None;
+                        self.response_len = 0;
+                        write_str(&mut self.response_buffer[..], b"[ERROR] Queue full, moveto.verify stopped\n", &mut self.response_len);
+                    }
+                }
+                None => self.finish_settle(),
+            }
+        }
+    }
+    
+    /// Report how a nozen.moveto.verify ended and stop tracking it
+    fn finish_settle(&mut self) {
+        use core::fmt::Write;
+        
+        let settle = match self.settle.take() {
+            Some(settle) => settle,
+            None => return,
+        };
+        let mut msg = heapless::String::<64>::new();
+        if settle.is_settled() {
+            let _ = writeln!(msg, "[OK] Settled after {} moves", settle.moves());
+        } else {
+            let (x, y) = settle.remaining();
+            let _ = writeln!(msg, "[WARN] Not settled after {} moves, off by {},{}", settle.moves(), x, y);
+        }
+        self.response_len = 0;
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+    }
+    
+    /// Report sent repeatedly by nozen.bench: no movement, held buttons kept
+    pub fn bench_report(&self) -> Command {
+        self.tag_report(Command::new(FpgaCode::InjectMouse as u8, &self.mouse_report(0, 0)))
+    }
+    
+    /// Leave the outcome of a nozen.bench in the response buffer
+    pub fn finish_bench(&mut self, count: u16, elapsed_ms: u32) {
+        use core::fmt::Write;
+        
+        let mut msg = heapless::String::<80>::new();
+        let _ = writeln!(msg, "[Bench] {} reports in {} ms ({} reports/s)",
+                       count, elapsed_ms, reports_per_second(count as u32, elapsed_ms));
+        self.response_len = 0;
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+    }
+    
+    /// Leave the SERCOM registers read for nozen.uart.regs in the response buffer
+    pub fn finish_uart_regs(&mut self, regs: &SercomRegs) {
+        use core::fmt::Write;
+        
+        let mut msg = heapless::String::<96>::new();
+        let _ = writeln!(msg, "[UART] {}", regs.format());
+        self.response_len = 0;
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+    }
+    
+    /// (lines kept, lines dropped) once a nozen.uart.capture window has elapsed
+    /// The trace is then in capture.trace(). Returns Some only once per capture.
+    pub fn finish_capture(&mut self) -> Option<(u16, u16)> {
+        self.capture.finish(self.now_ms)
+    }
+    
+    /// Response standing in for a frame that dry-run mode kept back
+    fn dry_run_response(&mut self, cmd: &Command) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let mut msg = heapless::String::<64>::new();
+        let _ = writeln!(msg, "[DRY] Not sent: CMD:{:02X} LEN:{}", cmd.code, cmd.length);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        CommandType::Response
+    }
+    
+    /// Swallow an error response identical to the previous one within
+    /// ERROR_COALESCE_MS. When the run is broken, "[ERR] xN" (N = times the
+    /// error occurred) goes out ahead of the next response.
+    fn coalesce_errors(&mut self, result: CommandType) -> CommandType {
+        if matches!(result, CommandType::NoOp) {
+            return result;
+        }
+        
+        let response = &self.response_buffer[..self.response_len];
+        let is_error = matches!(result, CommandType::Response) && response.starts_with(b"[ERROR]");
+        let repeat = is_error && self.error_run > 0 && response == &self.last_error[..]
+            && self.now_ms.wrapping_sub(self.last_error_ms) < ERROR_COALESCE_MS;
+        if repeat {
+            self.error_run = self.error_run.saturating_add(1);
+            self.last_error_ms = self.now_ms;
+            self.response_len = 0;
+            return CommandType::NoOp;
+        }
+        
+        let ended = self.end_error_run();
+        if is_error && self.response_len <= ERROR_KEEP {
+            let _ = self.last_error.extend_from_slice(&self.response_buffer[..self.response_len]);
+            self.error_run = 1;
+            self.last_error_ms = self.now_ms;
+        }
+        
+        if let Some(count) = ended {
+            use core::fmt::Write;
+            
+            let mut held = [0u8; 256];
+            let len = self.response_len;
+            held[..len].copy_from_slice(&self.response_buffer[..len]);
+            
+            let mut msg = heapless::String::<16>::new();
+            let _ = writeln!(msg, "[ERR] x{}", count);
+            self.response_len = 0;
+            write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            write_str(&mut self.response_buffer[..], &held[..len], &mut self.response_len);
+        }
+        result
+    }
+    
+    /// Close the current error run; returns its length if any repeats were swallowed
+    fn end_error_run(&mut self) -> Option<u16> {
+        let count = self.error_run;
+        self.error_run = 0;
+        self.last_error.clear();
+        (count > 1).then_some(count)
+    }
+    
+    /// Count of a swallowed error run once ERROR_COALESCE_MS has passed
+    /// without a repeat, for the main loop to report as "[ERR] xN"
+    pub fn take_error_run(&mut self) -> Option<u16> {
+        if self.error_run > 1 && self.now_ms.wrapping_sub(self.last_error_ms) >= ERROR_COALESCE_MS {
+            return self.end_error_run();
+        }
+        None
+    }
+    
+    /// Count a line that produced no action and keep its start for nozen.errors
+    fn record_bad_line(&mut self, line: &[u8]) {
+        self.bad_lines = self.bad_lines.saturating_add(1);
+        self.last_bad_line.clear();
+        let _ = self.last_bad_line.extend_from_slice(&line[..line.len().min(BAD_LINE_KEEP)]);
+    }
+    
+    /// Append "[CANON] <form>" after any message the handler left
+    fn echo_canonical(&mut self, cmd: &Command) {
+        write_str(&mut self.response_buffer[..], b"[CANON] ", &mut self.response_len);
+        write_str(&mut self.response_buffer[..], cmd.canonical().as_bytes(), &mut self.response_len);
+        write_str(&mut self.response_buffer[..], b"\n", &mut self.response_len);
+    }
+    
     /// Get response data if available
     pub fn get_response(&mut self) -> Option<&[u8]> {
         if self.response_len > 0 {
@@ -205,76 +1080,42 @@ impl CommandProcessor {
         //
         // FPGA auto-forwarding (no "nozen." prefix):
         //   "[DESC:addr:iface]{hex_data}" - Auto-forwarded HID descriptor
+        //   "[RPT:addr:iface]{hex_data}"  - Forwarded input report
         //
-        // Debug commands:
-        //   "nozen.descriptor.get(addr,iface)"
-        //   "nozen.descriptor.stats"
+        // All "nozen." commands are dispatched through COMMAND_TABLE.
         
         // Check for FPGA-forwarded descriptor (starts with [DESC:)
         if line.starts_with(b"[DESC:") {
             return self.handle_fpga_descriptor(line, descriptor_cache);
         }
+        if line.starts_with(b"[RPT:") {
+            return self.handle_fpga_report(line, descriptor_cache);
+        }
         
-        if line.starts_with(b"nozen.move(") {
-            // Parse: nozen.move(x,y)
-            self.parse_mouse_move(line)
-        } else if line.starts_with(b"nozen.moveto(") {
-            // Parse: nozen.moveto(x,y)
-            self.parse_mouse_moveto(line)
-        } else if line.starts_with(b"nozen.left(") {
-            // Parse: nozen.left(0) or nozen.left(1)
-            self.parse_button_command(line, 0x01, b"nozen.left(")
-        } else if line.starts_with(b"nozen.right(") {
-            // Parse: nozen.right(0) or nozen.right(1)
-            self.parse_button_command(line, 0x02, b"nozen.right(")
-        } else if line.starts_with(b"nozen.middle(") {
-            // Parse: nozen.middle(0) or nozen.middle(1)
-            self.parse_button_command(line, 0x04, b"nozen.middle(")
-        } else if line.starts_with(b"nozen.side1(") {
-            // Parse: nozen.side1(0) or nozen.side1(1)
-            self.parse_button_command(line, 0x08, b"nozen.side1(")
-        } else if line.starts_with(b"nozen.side2(") {
-            // Parse: nozen.side2(0) or nozen.side2(1)
-            self.parse_button_command(line, 0x10, b"nozen.side2(")
-        } else if line.starts_with(b"nozen.wheel(") {
-            // Parse: nozen.wheel(amount)
-            self.parse_wheel_command(line)
-        } else if line.starts_with(b"nozen.getpos") {
-            // Get current mouse position
-            self.handle_getpos()
-        } else if line.starts_with(b"nozen.recoil.add(") {
-            // Add recoil pattern
-            self.handle_recoil_add(line)
-        } else if line.starts_with(b"nozen.recoil.delete(") {
-            // Delete recoil pattern
-            self.handle_recoil_delete(line)
-        } else if line.starts_with(b"nozen.recoil.list") {
-            // List all recoil patterns
-            self.handle_recoil_list()
-        } else if line.starts_with(b"nozen.recoil.get(") {
-            // Get specific recoil pattern
-            self.handle_recoil_get(line)
-        } else if line.starts_with(b"nozen.recoil.names") {
-            // List recoil pattern names
-            self.handle_recoil_names()
-        } else if line.starts_with(b"nozen.print(") {
-            // Print message
-            self.handle_print(line)
-        } else if line.starts_with(b"nozen.descriptor.get(") {
-            // Get descriptor from cache (debug only)
-            self.handle_descriptor_get(line, descriptor_cache)
-        } else if line.starts_with(b"nozen.descriptor.stats") {
-            // Get descriptor cache statistics (debug only)
-            self.handle_descriptor_stats(descriptor_cache)
-        } else if line.starts_with(b"nozen.restart") {
-            // Restart device
-            CommandType::Restart
-        } else {
-            CommandType::NoOp
+        // Any host line, even one that fails to parse, shows the host is alive
+        self.last_command_ms = self.now_ms;
+        self.deadman_tripped = false;
+        
+        match find_command(COMMAND_TABLE, line) {
+            // Checked before dispatch so a refused command leaves no tracked state behind
+            Some(entry) if self.strict && injects(entry.prefix) && !self.target_cached(descriptor_cache) => {
+                self.response_len = 0;
+                write_str(&mut self.response_buffer[..], b"[ERROR] Strict: target descriptor not cached, set one with nozen.target\n", &mut self.response_len);
+                CommandType::Response
+            }
+            Some(entry) => {
+                let result = (entry.handler)(self, line, descriptor_cache);
+                // Lines that fail to parse are counted by nozen.errors instead
+                if result != CommandType::NoOp {
+                    self.cmd_stats.record(entry.prefix);
+                }
+                result
+            }
+            None => CommandType::NoOp,
         }
     }
     
-    fn parse_mouse_move(&mut self, line: &[u8]) -> CommandType {
+    fn parse_mouse_move(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
         // Parse "nozen.move(x,y)"
         let args_start = b"nozen.move(".len();
         let args = &line[args_start..];
@@ -303,6 +1144,17 @@ impl CommandProcessor {
             None => return CommandType::NoOp,
         };
         
+        let (x, y) = self.apply_motion_mode(x, y, self.target_sensitivity(descriptor_cache));
+        let (x, y) = self.humanizer.jitter(x, y);
+        
+        // Moves inside the deadzone send nothing and leave the position alone
+        let deadzone = self.deadzone as i16;
+        if x.abs() < deadzone && y.abs() < deadzone {
+            self.response_len = 0;
+            write_str(&mut self.response_buffer[..], b"[OK] Inside deadzone, not sent\n", &mut self.response_len);
+            return CommandType::Response;
+        }
+        
         // Update mouse state
         self.mouse_state.update_relative(x, y);
         
@@ -315,13 +1167,13 @@ impl CommandProcessor {
         payload[4] = 0x00;  // pan
         
         CommandType::FpgaCommand(Command {
-            code: 0x11,  // INJECT_MOUSE
+            code: FpgaCode::InjectMouse as u8,
             payload,
             length: 5,
         })
     }
     
-    fn parse_mouse_moveto(&mut self, line: &[u8]) -> CommandType {
+    fn parse_mouse_moveto(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
         // Parse "nozen.moveto(x,y)"
         let args_start = b"nozen.moveto(".len();
         let args = &line[args_start..];
@@ -348,11 +1200,67 @@ impl CommandProcessor {
             None => return CommandType::NoOp,
         };
         
+        // Absolute-coordinate targets take the position scaled into their logical
+        // range, sent as an INJECT_MOUSE report in the target's own layout (the
+        // FPGA forwards the payload as-is)
+        if let Some((x_field, y_field)) = self.absolute_axes(descriptor_cache) {
+            let abs_x = scale_to_logical(target_x, self.screen_size.0, x_field.logical_min, x_field.logical_max);
+            let abs_y = scale_to_logical(target_y, self.screen_size.1, y_field.logical_min, y_field.logical_max);
+            
+            let mut report = [0u8; INJECT_MOUSE_LEN];
+            let buttons = self.held.buttons;
+            let len = self.active_target
+                .and_then(|(addr, iface)| descriptor_cache.get(addr, iface))
+                .and_then(|desc| desc.absolute_report(buttons, abs_x, abs_y, &mut report));
+            let Some(len) = len else {
+                self.response_len = 0;
+                write_str(&mut self.response_buffer[..], b"[ERROR] Target's absolute report is longer than INJECT_MOUSE's 5 bytes\n", &mut self.response_len);
+                return CommandType::Response;
+            };
+            
+            if self.track {
+                self.mouse_state.set_position(target_x, target_y);
+            }
+            return CommandType::FpgaCommand(Command::new(FpgaCode::InjectMouse as u8, &report[..len]));
+        }
+        
         // Calculate delta from current position
         let (dx, dy) = self.mouse_state.delta_to(target_x, target_y);
         
-        // Update state to new position
-        self.mouse_state.set_position(target_x, target_y);
+        // Humanizer easing, then each segment split so no report exceeds
+        // max_speed: first report now, the rest queued
+        let segments = self.humanizer.ease(dx, dy);
+        let max = self.max_speed as i16;
+        let reports: usize = segments.iter().map(|&(x, y)| speed_parts(x, y, max)).sum();
+        if self.pending.reserve(reports - 1).is_err() {
+            self.response_len = 0;
+            write_str(&mut self.response_buffer[..], QUEUE_FULL, &mut self.response_len);
+            return CommandType::Response;
+        }
+        let interval = if segments.len() > 1 { EASING_INTERVAL_MS } else { SPLIT_INTERVAL_MS };
+        
+        // Update state to new position, short of the target if the delta was clamped
+        if self.track {
+            self.mouse_state.update_relative(dx, dy);
+        }
+        
+        // Timing variance spreads the reports, but no jitter: the moves
+        // must still add up to the target
+        let mut first = None;
+        let mut due = self.now_ms;
+        for &(seg_x, seg_y) in segments.iter() {
+            let parts = speed_parts(seg_x, seg_y, max);
+            for part in 0..parts {
+                let (x, y) = (split_part(seg_x, part, parts), split_part(seg_y, part, parts));
+                if first.is_none() {
+                    first = Some((x, y));
+                } else {
+                    due = due.wrapping_add(interval + self.humanizer.timing_offset());
+                    let _ = self.pending.push(PendingStep::new(due, FpgaCode::InjectMouse as u8, &self.mouse_report(x, y)));
+                }
+            }
+        }
+        let (dx, dy) = first.unwrap_or((0, 0));
         
         // Send relative movement to FPGA
         let mut payload = [0u8; 128];
@@ -363,13 +1271,99 @@ impl CommandProcessor {
         payload[4] = 0x00;
         
         CommandType::FpgaCommand(Command {
-            code: 0x11,  // INJECT_MOUSE
+            code: FpgaCode::InjectMouse as u8,
+            payload,
+            length: 5,
+        })
+    }
+    
+    /// Handle moveto.verify command
+    /// Format: nozen.moveto.verify(x,y)
+    /// Sends the relative move, then corrects it from the "[DELTA:dx,dy]"
+    /// lines the FPGA reports until within tolerance or out of moves.
+    /// Current gateware sends no such lines, so only the first move goes out.
+    fn handle_moveto_verify(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        self.response_len = 0;
+        let args = &line[b"nozen.moveto.verify(".len()..];
+        let args = match args.iter().position(|&c| c == b')') {
+            Some(p) => &args[..p],
+            None => return CommandType::NoOp,
+        };
+        let mut parts = args.split(|&c| c == b',');
+        let target = match (parts.next().and_then(parse_int), parts.next().and_then(parse_int), parts.next()) {
+            (Some(x), Some(y), None) => (x, y),
+            _ => return CommandType::NoOp,
+        };
+        
+        if self.absolute_axes(descriptor_cache).is_some() {
+            write_str(&mut self.response_buffer[..], b"[ERROR] Target is absolute, use nozen.moveto\n", &mut self.response_len);
+            return CommandType::Response;
+        }
+        
+        let (dx, dy) = self.mouse_state.delta_to(target.0, target.1);
+        self.mouse_state.update_relative(dx, dy);
+        
+        let mut settle = MoveSettle::new(dx, dy);
+        let (x, y) = settle.next_move().unwrap_or((0, 0));
+        self.settle = Some(settle);
+        
+        let mut payload = [0u8; 128];
+        payload[..5].copy_from_slice(&self.mouse_report(x, y));
+        CommandType::FpgaCommand(Command {
+            code: FpgaCode::InjectMouse as u8,
             payload,
             length: 5,
         })
     }
     
-    fn parse_button_command(&self, line: &[u8], button_mask: u8, prefix: &[u8]) -> CommandType {
+    /// Relative movement after sensitivity scaling and axis inversion
+    /// `device_percent` is the active target's own sensitivity, on top of the global one
+    fn apply_motion_mode(&self, dx: i16, dy: i16, device_percent: u16) -> (i16, i16) {
+        let scale = |v: i16, invert: bool| {
+            let v = (v as i32 * self.sensitivity as i32 * device_percent as i32 / 10_000)
+                .clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+            if invert { v.saturating_neg() } else { v }
+        };
+        (scale(dx, self.invert.0), scale(dy, self.invert.1))
+    }
+    
+    /// INJECT_MOUSE payload for a queued movement, keeping held buttons pressed
+    fn mouse_report(&self, dx: i16, dy: i16) -> [u8; 5] {
+        [self.held.buttons, dx as u8, dy as u8, 0, 0]
+    }
+    
+    /// True if there is an active target and its descriptor is cached
+    fn target_cached(&self, descriptor_cache: &DescriptorCache) -> bool {
+        self.active_target
+            .is_some_and(|(addr, iface)| descriptor_cache.raw(addr, iface).is_some())
+    }
+    
+    /// Sensitivity set for the active target with nozen.descriptor.sens, 100 if none
+    fn target_sensitivity(&self, descriptor_cache: &DescriptorCache) -> u16 {
+        self.active_target
+            .and_then(|(addr, iface)| descriptor_cache.sensitivity(addr, iface))
+            .unwrap_or(100)
+    }
+    
+    /// Buttons declared by the active target's cached descriptor
+    fn target_button_count(&self, descriptor_cache: &mut DescriptorCache) -> Option<u8> {
+        let (addr, iface) = self.active_target?;
+        Some(descriptor_cache.get(addr, iface)?.button_count())
+    }
+    
+    /// X/Y input fields of the active target, if it reports absolute coordinates
+    fn absolute_axes(&self, descriptor_cache: &mut DescriptorCache) -> Option<(ReportField, ReportField)> {
+        let (addr, iface) = self.active_target?;
+        let desc = descriptor_cache.get(addr, iface)?;
+        if !desc.is_absolute_pointer() {
+            return None;
+        }
+        let x = *desc.find_input(UsagePage::GenericDesktop, 0x30)?;
+        let y = *desc.find_input(UsagePage::GenericDesktop, 0x31)?;
+        Some((x, y))
+    }
+    
+    fn parse_button_command(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache, button_mask: u8, prefix: &[u8]) -> CommandType {
         // Parse "nozen.left(0)" or "nozen.left(1)"
         let args_start = prefix.len();
         let args = &line[args_start..];
@@ -380,7 +1374,23 @@ impl CommandProcessor {
         };
         let state = args[0];
         
-        let buttons = if state == b'1' { button_mask } else { 0x00 };
+        // Presses beyond what the active target declares would be ignored by the
+        // host; releases always go through so nothing can be left held
+        let button = button_mask.trailing_zeros() as u8 + 1;
+        if let Some(count) = self.target_button_count(descriptor_cache) {
+            if state == b'1' && button > count {
+                use core::fmt::Write;
+                
+                self.response_len = 0;
+                let mut msg = heapless::String::<64>::new();
+                let _ = writeln!(msg, "[ERROR] Button {} not supported, target has {}", button, count);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+                return CommandType::Response;
+            }
+        }
+        
+        // Other buttons still held stay pressed in the report
+        let buttons = self.held.set_buttons(button_mask, state == b'1');
         
         // Create INJECT_MOUSE command
         let mut payload = [0u8; 128];
@@ -391,16 +1401,194 @@ impl CommandProcessor {
         payload[4] = 0x00;
         
         CommandType::FpgaCommand(Command {
-            code: 0x11,  // INJECT_MOUSE
+            code: FpgaCode::InjectMouse as u8,
             payload,
             length: 5,
         })
     }
     
-    fn parse_wheel_command(&self, line: &[u8]) -> CommandType {
-        // Parse "nozen.wheel(amount)"
-        let args_start = b"nozen.wheel(".len();
-        let args = &line[args_start..];
+    /// Handle mouse.buttons command
+    /// Format: nozen.mouse.buttons(mask), mask as 0xNN hex or decimal
+    /// Sets every button at once in a single report: set bits are held, clear bits released
+    fn handle_mouse_buttons(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.mouse.buttons(".len()..];
+        let arg = &args[..args.iter().position(|&c| c == b')').unwrap_or(args.len())];
+        let mask = match arg.strip_prefix(b"0x").or_else(|| arg.strip_prefix(b"0X")) {
+            Some(&[high, low]) => parse_hex_byte(high, low),
+            Some(_) => None,
+            None => parse_int(arg).and_then(|v| u8::try_from(v).ok()),
+        };
+        let mask = match mask {
+            Some(mask) => mask,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.mouse.buttons(0xNN)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        // Highest button in the mask, counting from 1
+        let highest = 8 - mask.leading_zeros() as u8;
+        if let Some(count) = self.target_button_count(descriptor_cache) {
+            if highest > count {
+                let mut msg = heapless::String::<64>::new();
+                let _ = writeln!(msg, "[ERROR] Button {} not supported, target has {}", highest, count);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+                return CommandType::Response;
+            }
+        }
+        
+        self.held.buttons = mask;
+        let mut payload = [0u8; 128];
+        payload[..5].copy_from_slice(&self.mouse_report(0, 0));
+        CommandType::FpgaCommand(Command {
+            code: FpgaCode::InjectMouse as u8,
+            payload,
+            length: 5,
+        })
+    }
+    
+    /// Handle click command
+    /// Format: nozen.click() for the default button, or nozen.click(left|right|middle)
+    /// Presses now and queues the release CLICK_HOLD_MS later
+    fn handle_click(&mut self, line: &[u8]) -> CommandType {
+        self.response_len = 0;
+        let args = &line[b"nozen.click(".len()..];
+        let arg = match args.iter().position(|&c| c == b')') {
+            Some(p) => &args[..p],
+            None => return CommandType::NoOp,
+        };
+        
+        let mask = if arg.is_empty() {
+            self.default_button
+        } else {
+            match button_from_name(arg) {
+                Some(mask) => mask,
+                None => {
+                    write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.click(left|right|middle)\n", &mut self.response_len);
+                    return CommandType::Response;
+                }
+            }
+        };
+        self.press_and_release(mask, CLICK_HOLD_MS)
+    }
+    
+    /// Handle calibrate command
+    /// Format: nozen.calibrate(size), size 1-1000 px
+    /// Queues a square right, down, left and up back to the start, pausing
+    /// CALIBRATE_PAUSE_MS at each corner. Edges are split to max_speed; the
+    /// tracked position is unchanged since the box closes.
+    fn handle_calibrate(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.calibrate(".len()..];
+        let size = args.iter().position(|&c| c == b')')
+            .and_then(|end| parse_int(&args[..end]));
+        let size = match size {
+            Some(size) if (1..=1000).contains(&size) => size,
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.calibrate(1-1000)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let edges = [(size, 0), (0, size), (-size, 0), (0, -size)];
+        let max = self.max_speed as i16;
+        let moves: usize = edges.iter().map(|&(x, y)| speed_parts(x, y, max)).sum();
+        if self.pending.reserve(moves).is_err() {
+            write_str(&mut self.response_buffer[..], QUEUE_FULL, &mut self.response_len);
+            return CommandType::Response;
+        }
+        
+        let mut due = self.now_ms;
+        for &(edge_x, edge_y) in edges.iter() {
+            let parts = speed_parts(edge_x, edge_y, max);
+            for part in 0..parts {
+                let (x, y) = (split_part(edge_x, part, parts), split_part(edge_y, part, parts));
+                let _ = self.pending.push(PendingStep::new(due, FpgaCode::InjectMouse as u8, &self.mouse_report(x, y)));
+                due = due.wrapping_add(SPLIT_INTERVAL_MS);
+            }
+            due = due.wrapping_add(CALIBRATE_PAUSE_MS);
+        }
+        
+        let mut msg = heapless::String::<64>::new();
+        let _ = writeln!(msg, "[OK] Calibrating: {} px box, {} moves", size, moves);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle tap command
+    /// Format: nozen.tap(left|right|middle,press_ms), press_ms 1-32767
+    /// Like click, but the release is queued press_ms after the press
+    fn handle_tap(&mut self, line: &[u8]) -> CommandType {
+        self.response_len = 0;
+        let mut values = [0i16; 1];
+        let mask = parse_name_and_ints(line, b"nozen.tap(".len(), &mut values)
+            .and_then(button_from_name);
+        match mask {
+            Some(mask) if values[0] >= 1 => self.press_and_release(mask, values[0] as u32),
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.tap(left|right|middle,1-32767)\n", &mut self.response_len);
+                CommandType::Response
+            }
+        }
+    }
+    
+    /// Press `mask` now and queue the release `hold_ms` later
+    fn press_and_release(&mut self, mask: u8, hold_ms: u32) -> CommandType {
+        if self.pending.reserve(1).is_err() {
+            write_str(&mut self.response_buffer[..], QUEUE_FULL, &mut self.response_len);
+            return CommandType::Response;
+        }
+        
+        // Buttons held with nozen.left etc. stay down through the click
+        let release = self.mouse_report(0, 0);
+        let due = self.now_ms.wrapping_add(hold_ms);
+        let _ = self.pending.push(PendingStep::new(due, FpgaCode::InjectMouse as u8, &release));
+        
+        let mut payload = [0u8; 128];
+        payload[..5].copy_from_slice(&release);
+        payload[0] |= mask;
+        CommandType::FpgaCommand(Command {
+            code: FpgaCode::InjectMouse as u8,
+            payload,
+            length: 5,
+        })
+    }
+    
+    /// Handle button.default command
+    /// Format: nozen.button.default(left|right|middle)
+    fn handle_button_default(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.button.default(".len()..];
+        let mask = args.iter().position(|&c| c == b')')
+            .and_then(|end| button_from_name(&args[..end]));
+        
+        match mask {
+            Some(mask) => {
+                self.default_button = mask;
+                let mut msg = heapless::String::<48>::new();
+                let _ = writeln!(msg, "[OK] Default button: {}", button_name(mask));
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.button.default(left|right|middle)\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    fn parse_wheel_command(&mut self, line: &[u8]) -> CommandType {
+        // Parse "nozen.wheel(amount)"
+        let args_start = b"nozen.wheel(".len();
+        let args = &line[args_start..];
         
         let paren_pos = match args.iter().position(|&c| c == b')') {
             Some(p) => p,
@@ -413,16 +1601,35 @@ impl CommandProcessor {
             None => return CommandType::NoOp,
         };
         
+        // With a notch size set, send |amount| reports of +/-step: the
+        // first now, the rest through the pending queue
+        let (wheel, notches) = if self.wheel_step > 1 && amount != 0 {
+            (amount.signum() * self.wheel_step as i16, amount.unsigned_abs() as usize)
+        } else {
+            (amount, 1)
+        };
+        if self.pending.reserve(notches - 1).is_err() {
+            self.response_len = 0;
+            write_str(&mut self.response_buffer[..], QUEUE_FULL, &mut self.response_len);
+            return CommandType::Response;
+        }
+        
+        let notch = [0x00, 0x00, 0x00, (wheel & 0xFF) as u8, 0x00];
+        for i in 1..notches {
+            let due = self.now_ms.wrapping_add(i as u32 * WHEEL_NOTCH_INTERVAL_MS);
+            let _ = self.pending.push(PendingStep::new(due, FpgaCode::InjectMouse as u8, &notch));
+        }
+        
         // Create INJECT_MOUSE command with wheel movement
         let mut payload = [0u8; 128];
         payload[0] = 0x00;  // No buttons
         payload[1] = 0x00;  // No x movement
         payload[2] = 0x00;  // No y movement
-        payload[3] = (amount & 0xFF) as u8;  // Wheel
+        payload[3] = notch[3];  // Wheel
         payload[4] = 0x00;  // Pan
         
         CommandType::FpgaCommand(Command {
-            code: 0x11,  // INJECT_MOUSE
+            code: FpgaCode::InjectMouse as u8,
             payload,
             length: 5,
         })
@@ -458,42 +1665,402 @@ impl CommandProcessor {
     }
     
     fn handle_recoil_add(&mut self, line: &[u8]) -> CommandType {
-        match parse_recoil_add(line) {
-            Some((name, steps)) => {
-                let name_str = core::str::from_utf8(name).unwrap_or("???");
+        use core::fmt::Write;
+        
+        let parsed = parse_recoil_add(line).and_then(|(arg, steps)| {
+            Ok((split_pattern_flags(arg).ok_or(RECOIL_ADD_FORMAT_ERROR)?, steps))
+        });
+        match parsed {
+            Ok(((name, absolute), steps)) => {
+                let mut name_str = core::str::from_utf8(name).unwrap_or("???");
                 let steps_slice: &[i16] = &steps;
                 
+                let truncated = name_str.len() > MAX_PATTERN_NAME_LEN;
+                if truncated {
+                    let mut msg = heapless::String::<64>::new();
+                    if !self.lenient {
+                        self.response_len = 0;
+                        let _ = writeln!(msg, "[ERROR] Name too long (max {} chars)", MAX_PATTERN_NAME_LEN);
+                        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+                        return CommandType::Response;
+                    }
+                    let mut end = MAX_PATTERN_NAME_LEN;
+                    while !name_str.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    name_str = &name_str[..end];
+                }
+                
                 match self.recoil_manager.add_pattern(name_str, steps_slice) {
                     Ok(_) => {
-                        let msg = b"Recoil pattern added\n";
-                        self.response_buffer[..msg.len()].copy_from_slice(msg);
-                        self.response_len = msg.len();
+                        if let Some(pattern) = self.recoil_manager.get_pattern_mut(name_str) {
+                            pattern.absolute = absolute;
+                        }
+                        self.response_len = 0;
+                        if truncated {
+                            let mut msg = heapless::String::<96>::new();
+                            let _ = writeln!(msg, "[WARN] Name truncated to {} chars: {}", MAX_PATTERN_NAME_LEN, name_str);
+                            write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+                        }
+                        write_str(&mut self.response_buffer[..], b"Recoil pattern added\n", &mut self.response_len);
                         CommandType::Response
                     }
                     Err(e) => {
-                        let mut resp = [0u8; 256];
-                        let err_msg = b"Error: ";
-                        resp[..err_msg.len()].copy_from_slice(err_msg);
-                        let e_bytes = e.as_bytes();
-                        let e_len = e_bytes.len().min(240);
-                        resp[err_msg.len()..err_msg.len()+e_len].copy_from_slice(&e_bytes[..e_len]);
-                        resp[err_msg.len()+e_len] = b'\n';
-                        let total_len = err_msg.len()+e_len+1;
-                        self.response_buffer[..total_len].copy_from_slice(&resp[..total_len]);
-                        self.response_len = total_len;
+                        let mut msg = heapless::String::<80>::new();
+                        let _ = writeln!(msg, "[ERROR] {}", e.message());
+                        self.response_len = 0;
+                        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
                         CommandType::Response
                     }
                 }
             }
-            None => {
-                let msg = b"Invalid recoil.add format\n";
-                self.response_buffer[..msg.len()].copy_from_slice(msg);
-                self.response_len = msg.len();
+            Err(e) => {
+                let mut msg = heapless::String::<80>::new();
+                let _ = writeln!(msg, "[ERROR] {}", e);
+                self.response_len = 0;
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
                 CommandType::Response
             }
         }
     }
     
+    /// Handle recoil.batch command
+    /// Format: nozen.recoil.batch{name1:x,y,d,...;name2:x,y,d,...}
+    /// Stops at the first segment that doesn't fit in pattern storage
+    fn handle_recoil_batch(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let body = &line[b"nozen.recoil.batch{".len()..];
+        let body = match body.iter().position(|&c| c == b'}') {
+            Some(end) => &body[..end],
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Invalid recoil.batch format\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let mut added = 0;
+        let mut failed = 0;
+        let mut storage_full = false;
+        for segment in body.split(|&c| c == b';').filter(|s| !s.is_empty()) {
+            if storage_full {
+                failed += 1;
+                continue;
+            }
+            // None: the segment itself was malformed
+            let result = match parse_batch_segment(segment) {
+                Some((name, steps)) => match core::str::from_utf8(name) {
+                    Ok(name_str) => self.recoil_manager.add_pattern(name_str, &steps).map_err(Some),
+                    Err(_) => Err(None),
+                },
+                None => Err(None),
+            };
+            match result {
+                Ok(()) => added += 1,
+                Err(e) => {
+                    failed += 1;
+                    storage_full = e == Some(RecoilError::StorageFull);
+                }
+            }
+        }
+        
+        let mut msg = heapless::String::<96>::new();
+        let tag = if failed == 0 { "[OK]" } else { "[WARN]" };
+        let _ = writeln!(msg, "{} Batch: {} added, {} failed", tag, added, failed);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        if storage_full {
+            write_str(&mut self.response_buffer[..], b"[ERROR] Pattern storage full\n", &mut self.response_len);
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Queue every step of a stored pattern, the first one due at `due`
+    /// Returns the steps queued and when a following step would be due,
+    /// or the error response; nothing is queued on error
+    fn queue_recoil_pattern(&mut self, name: &str, due: u32) -> Result<(usize, u32), &'static [u8]> {
+        // Copied out so the humanizer can be borrowed while queueing
+        let mut steps = [(0i16, 0i16, 0i16); MAX_PATTERN_STEPS / 3];
+        let pattern = self.recoil_manager.get_pattern(name).ok_or(PATTERN_NOT_FOUND)?;
+        let absolute = pattern.absolute;
+        let count = pattern.step_count().min(steps.len());
+        for (i, step) in steps.iter_mut().enumerate().take(count) {
+            *step = pattern.step(i).unwrap_or_default();
+        }
+        
+        // Absolute steps are split like moveto, so one can take several reports
+        let max = self.max_speed as i16;
+        let mut at = self.mouse_state.position();
+        let mut reports = 0;
+        for &(x, y, _) in &steps[..count] {
+            if absolute {
+                let (dx, dy) = (x.saturating_sub(at.0), y.saturating_sub(at.1));
+                reports += speed_parts(dx, dy, max);
+                at = (at.0 + dx, at.1 + dy);
+            } else {
+                reports += 1;
+            }
+        }
+        if self.pending.reserve(reports).is_err() {
+            return Err(QUEUE_FULL);
+        }
+        
+        let mut due = due;
+        for &(x, y, delay) in &steps[..count] {
+            if absolute {
+                // Moved to their target like moveto, unjittered so the
+                // tracked position stays exact
+                let (dx, dy) = self.mouse_state.delta_to(x, y);
+                self.mouse_state.update_relative(dx, dy);
+                let parts = speed_parts(dx, dy, max);
+                for part in 0..parts {
+                    let report = self.mouse_report(split_part(dx, part, parts), split_part(dy, part, parts));
+                    let part_due = due.wrapping_add(part as u32 * SPLIT_INTERVAL_MS);
+                    let _ = self.pending.push(PendingStep::new(part_due, FpgaCode::InjectMouse as u8, &report));
+                }
+            } else {
+                let (x, y) = self.humanizer.jitter(x, y);
+                let _ = self.pending.push(PendingStep::new(due, FpgaCode::InjectMouse as u8, &self.mouse_report(x, y)));
+            }
+            due = due.wrapping_add(self.recoil_manager.time_unit.to_ms(delay) + self.humanizer.timing_offset());
+        }
+        Ok((count, due))
+    }
+    
+    /// Handle recoil.run command
+    /// Format: nozen.recoil.run(name)
+    /// Queues each (x, y, delay) step; the move is sent, then `delay` ms pass
+    fn handle_recoil_run(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let name = match parse_recoil_name(line, b"nozen.recoil.run") {
+            Some(name) => core::str::from_utf8(name).unwrap_or("???"),
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.recoil.run(name)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let count = match self.queue_recoil_pattern(name, self.now_ms) {
+            Ok((count, _)) => count,
+            Err(error) => {
+                write_str(&mut self.response_buffer[..], error, &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let mut msg = heapless::String::<64>::new();
+        let _ = writeln!(msg, "[OK] Running {} ({} steps)", name, count);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle recoil.mirror command
+    /// Format: nozen.recoil.mirror(name,x|y|both)
+    /// Negates the chosen axes of every stored step in place; delays are kept
+    fn handle_recoil_mirror(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = parse_recoil_name(line, b"nozen.recoil.mirror").unwrap_or(b"");
+        let parsed = args.iter().rposition(|&c| c == b',').and_then(|comma| {
+            let axes = match &args[comma + 1..] {
+                b"x" => (true, false),
+                b"y" => (false, true),
+                b"both" => (true, true),
+                _ => return None,
+            };
+            Some((&args[..comma], axes))
+        });
+        let (name, (x, y)) = match parsed {
+            Some(parsed) => parsed,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.recoil.mirror(name,x|y|both)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let name_str = core::str::from_utf8(name).unwrap_or("???");
+        match self.recoil_manager.get_pattern_mut(name_str) {
+            Some(pattern) => {
+                pattern.mirror(x, y);
+                let mut msg = heapless::String::<96>::new();
+                let axis = match (x, y) {
+                    (true, true) => "x and y",
+                    (true, false) => "x",
+                    _ => "y",
+                };
+                let _ = writeln!(msg, "[OK] Mirrored {} in {} ({} steps)", axis, name_str, pattern.step_count());
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            None => {
+                write_str(&mut self.response_buffer[..], PATTERN_NOT_FOUND, &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle recoil.rescale command
+    /// Format: nozen.recoil.rescale(name,percent), percent 1-1000 as for nozen.sensitivity
+    /// Scales the stored x/y of every step for good; delays are kept
+    fn handle_recoil_rescale(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let mut values = [0i16; 1];
+        let name = match parse_name_and_ints(line, b"nozen.recoil.rescale(".len(), &mut values) {
+            Some(name) if (1..=1000).contains(&values[0]) => name,
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.recoil.rescale(name,1-1000)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let name_str = core::str::from_utf8(name).unwrap_or("???");
+        let percent = values[0] as u16;
+        match self.recoil_manager.get_pattern_mut(name_str) {
+            Some(pattern) => {
+                let clamped = pattern.rescale(percent);
+                let mut msg = heapless::String::<96>::new();
+                let _ = write!(msg, "[OK] Rescaled {} to {}%", name_str, percent);
+                if clamped > 0 {
+                    let _ = write!(msg, " ({} values clamped)", clamped);
+                }
+                let _ = msg.push('\n');
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            None => {
+                write_str(&mut self.response_buffer[..], PATTERN_NOT_FOUND, &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle recoil.smooth command
+    /// Format: nozen.recoil.smooth(name,window), window 1-21 triplets
+    /// Averages each step's x/y with its neighbours in the stored pattern
+    fn handle_recoil_smooth(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let mut values = [0i16; 1];
+        let name = match parse_name_and_ints(line, b"nozen.recoil.smooth(".len(), &mut values) {
+            Some(name) if (1..=(MAX_PATTERN_STEPS / 3) as i16).contains(&values[0]) => name,
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.recoil.smooth(name,1-21)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let name_str = core::str::from_utf8(name).unwrap_or("???");
+        let window = values[0] as usize;
+        match self.recoil_manager.get_pattern_mut(name_str) {
+            Some(pattern) => {
+                let _ = pattern.smooth(window);
+                let mut msg = heapless::String::<96>::new();
+                let _ = writeln!(msg, "[OK] Smoothed {} over {} steps ({} steps)", name_str, window, pattern.step_count());
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            None => {
+                write_str(&mut self.response_buffer[..], PATTERN_NOT_FOUND, &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle recoil.resample command
+    /// Format: nozen.recoil.resample(name,factor)
+    /// Replaces each step with `factor` interpolated ones, up to the pattern size limit
+    fn handle_recoil_resample(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let mut values = [0i16; 1];
+        let name = match parse_name_and_ints(line, b"nozen.recoil.resample(".len(), &mut values) {
+            Some(name) => name,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.recoil.resample(name,factor)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let name_str = core::str::from_utf8(name).unwrap_or("???");
+        let mut msg = heapless::String::<96>::new();
+        match self.recoil_manager.get_pattern_mut(name_str) {
+            Some(pattern) => {
+                let before = pattern.step_count();
+                match pattern.resample(values[0].max(0) as usize) {
+                    Ok(()) => {
+                        let _ = writeln!(msg, "[OK] Resampled {}: {} -> {} steps", name_str, before, pattern.step_count());
+                    }
+                    Err(e) => {
+                        let _ = writeln!(msg, "[ERROR] {} ({} steps)", e, before);
+                    }
+                }
+            }
+            None => {
+                write_str(&mut self.response_buffer[..], PATTERN_NOT_FOUND, &mut self.response_len);
+                return CommandType::Response;
+            }
+        }
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle recoil.chain command
+    /// Format: nozen.recoil.chain(a,b,c)
+    /// Queues the named patterns back to back, each starting after the last
+    /// step delay of the one before; unknown names are skipped with a warning
+    fn handle_recoil_chain(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let names = match parse_recoil_name(line, b"nozen.recoil.chain") {
+            Some(names) if !names.is_empty() => names,
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.recoil.chain(a,b,...)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let mut msg = heapless::String::<64>::new();
+        let mut due = self.now_ms;
+        let (mut patterns, mut steps) = (0, 0);
+        for name in names.split(|&c| c == b',') {
+            let name = core::str::from_utf8(name).unwrap_or("???").trim();
+            match self.queue_recoil_pattern(name, due) {
+                Ok((count, next)) => {
+                    patterns += 1;
+                    steps += count;
+                    due = next;
+                }
+                Err(error) if error == PATTERN_NOT_FOUND => {
+                    msg.clear();
+                    let _ = writeln!(msg, "[WARN] Pattern not found: {}", name);
+                    write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+                }
+                Err(error) => {
+                    // Patterns already queued still run
+                    write_str(&mut self.response_buffer[..], error, &mut self.response_len);
+                    return CommandType::Response;
+                }
+            }
+        }
+        
+        msg.clear();
+        let _ = writeln!(msg, "[OK] Chained {} patterns ({} steps)", patterns, steps);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
     fn handle_recoil_delete(&mut self, line: &[u8]) -> CommandType {
         match parse_recoil_name(line, b"nozen.recoil.delete") {
             Some(name) => {
@@ -578,6 +2145,11 @@ impl CommandProcessor {
                         resp[idx..idx+name_len].copy_from_slice(&name_bytes[..name_len]);
                         idx += name_len;
                         
+                        if pattern.absolute {
+                            resp[idx..idx+4].copy_from_slice(b",abs");
+                            idx += 4;
+                        }
+                        
                         resp[idx..idx+3].copy_from_slice(b": {");
                         idx += 3;
                         
@@ -615,6 +2187,49 @@ impl CommandProcessor {
         }
     }
     
+    /// Handle recoil.timeunit command
+    /// Format: nozen.recoil.timeunit to show, nozen.recoil.timeunit(ms),
+    /// nozen.recoil.timeunit(tick) for RECOIL_TICK_MS ticks or
+    /// nozen.recoil.timeunit(tick,ms) with ms 1-1000
+    /// Applies to every pattern at playback; stored delays are unchanged
+    fn handle_recoil_timeunit(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.recoil.timeunit".len()..];
+        let set = match args.strip_prefix(b"(") {
+            None => false,
+            Some(args) => {
+                let args = &args[..args.iter().position(|&c| c == b')').unwrap_or(args.len())];
+                let mut parts = args.splitn(2, |&c| c == b',');
+                let unit = match (parts.next(), parts.next().map(parse_int)) {
+                    (Some(b"ms"), None) => Some(TimeUnit::Millis),
+                    (Some(b"tick"), None) => Some(TimeUnit::Ticks(RECOIL_TICK_MS)),
+                    (Some(b"tick"), Some(Some(ms))) if (1..=1000).contains(&ms) => Some(TimeUnit::Ticks(ms as u16)),
+                    _ => None,
+                };
+                match unit {
+                    Some(unit) => self.recoil_manager.time_unit = unit,
+                    None => {
+                        write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.recoil.timeunit(ms|tick[,1-1000])\n", &mut self.response_len);
+                        return CommandType::Response;
+                    }
+                }
+                true
+            }
+        };
+        
+        let mut msg = heapless::String::<48>::new();
+        let _ = msg.push_str(if set { "[OK] Recoil delays in " } else { "[Recoil] Delays in " });
+        match self.recoil_manager.time_unit {
+            TimeUnit::Millis => { let _ = writeln!(msg, "ms"); }
+            TimeUnit::Ticks(ms) => { let _ = writeln!(msg, "ticks of {} ms", ms); }
+        }
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
     fn handle_recoil_names(&mut self) -> CommandType {
         let mut resp = [0u8; 256];
         let mut idx = 0;
@@ -650,13 +2265,10 @@ impl CommandProcessor {
             return CommandType::NoOp;
         }
         
-        let args = &line[args_start..];
-        let paren_pos = match args.iter().position(|&c| c == b')') {
-            Some(p) => p,
+        let message = match parse_text_arg(&line[args_start..]) {
+            Some((message, _)) => message,
             None => return CommandType::NoOp,
         };
-        
-        let message = &args[..paren_pos];
         let msg_len = message.len().min(254);
         
         self.response_buffer[..msg_len].copy_from_slice(&message[..msg_len]);
@@ -666,669 +2278,5799 @@ impl CommandProcessor {
         CommandType::Response
     }
 
-    /// Handle FPGA-forwarded descriptor
-    /// Format: [DESC:addr:iface]{hex_data}
-    /// This is automatically sent by FPGA when it detects GET_DESCRIPTOR for HID Report
-    fn handle_fpga_descriptor(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
-        use core::fmt::Write;
-        
-        // Parse: [DESC:AA:II]{hex_data}
-        let mut idx = 6;  // Skip "[DESC:"
+    /// Handle key.hold command
+    /// Format: nozen.key.hold(scancode,ms,interval)
+    /// Presses now, re-sends the pressed report every `interval` ms and
+    /// releases once `ms` has elapsed
+    fn handle_key_hold(&mut self, line: &[u8]) -> CommandType {
+        self.response_len = 0;
+        let args = &line[b"nozen.key.hold(".len()..];
+        let args = match args.iter().position(|&c| c == b')') {
+            Some(p) => &args[..p],
+            None => return CommandType::NoOp,
+        };
         
-        // Parse address (hex)
-        if idx + 2 > line.len() {
-            return CommandType::NoOp;
+        let mut values = [0i16; 3];
+        let mut count = 0;
+        for part in args.split(|&c| c == b',') {
+            if count == values.len() {
+                count += 1;
+                break;
+            }
+            values[count] = match parse_int(part) {
+                Some(v) => v,
+                None => return CommandType::NoOp,
+            };
+            count += 1;
         }
-        let addr_high = hex_to_nibble(line[idx]).unwrap_or(0);
-        let addr_low = hex_to_nibble(line[idx + 1]).unwrap_or(0);
-        let addr = (addr_high << 4) | addr_low;
-        idx += 2;
         
-        // Skip ':'
-        if idx >= line.len() || line[idx] != b':' {
-            return CommandType::NoOp;
+        let [scancode, hold_ms, interval] = values;
+        if count != 3 || !(1..=255).contains(&scancode) || hold_ms <= 0 || interval <= 0 {
+            write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.key.hold(scancode,ms,interval)\n", &mut self.response_len);
+            return CommandType::Response;
         }
-        idx += 1;
         
-        // Parse interface (hex)
-        if idx >= line.len() {
-            return CommandType::NoOp;
+        // Keys and modifiers held with nozen.key and nozen.mod stay down for
+        // the whole hold and after it
+        let mut chord = self.held;
+        if !chord.press_key(scancode as u8) {
+            write_str(&mut self.response_buffer[..], b"[ERROR] All six key slots held\n", &mut self.response_len);
+            return CommandType::Response;
         }
-        let iface = hex_to_nibble(line[idx]).unwrap_or(0);
-        idx += 1;
         
-        // Find hex data in braces
-        while idx < line.len() && line[idx] != b'{' {
-            idx += 1;
+        // Repeats strictly inside the hold, then the release
+        let repeats = ((hold_ms - 1) / interval) as usize;
+        if self.pending.reserve(repeats + 1).is_err() {
+            write_str(&mut self.response_buffer[..], QUEUE_FULL, &mut self.response_len);
+            return CommandType::Response;
         }
-        idx += 1;
         
-        let start = idx;
-        while idx < line.len() && line[idx] != b'}' {
-            idx += 1;
+        let pressed = chord.keyboard_report();
+        for i in 1..=repeats {
+            let due = self.now_ms.wrapping_add((i as u32) * interval as u32);
+            let _ = self.pending.push(PendingStep::new(due, FpgaCode::InjectKbd as u8, &pressed));
         }
+        let release = self.held.keyboard_report();
+        let _ = self.pending.push(PendingStep::new(self.now_ms.wrapping_add(hold_ms as u32), FpgaCode::InjectKbd as u8, &release));
         
-        // Parse hex data
-        let hex_data = &line[start..idx];
-        let mut descriptor_bytes = [0u8; 1024];
-        let mut desc_len = 0;
-        
-        let mut i = 0;
-        while i < hex_data.len() && desc_len < 1024 {
-            // Skip whitespace/commas
-            while i < hex_data.len() && (hex_data[i] == b' ' || hex_data[i] == b',') {
-                i += 1;
+        let mut payload = [0u8; 128];
+        payload[..8].copy_from_slice(&pressed);
+        CommandType::FpgaCommand(Command {
+            code: FpgaCode::InjectKbd as u8,
+            payload,
+            length: 8,
+        })
+    }
+    
+    /// Handle key command
+    /// Format: nozen.key(scancode,state), state 1 = press, 0 = release
+    /// Sends every held key and modifier, so presses compose
+    fn handle_key(&mut self, line: &[u8]) -> CommandType {
+        self.response_len = 0;
+        let (scancode, state) = match parse_u8_pair(line, b"nozen.key(".len()) {
+            Some((scancode, state)) if scancode != 0 && state <= 1 => (scancode, state),
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.key(scancode,0|1)\n", &mut self.response_len);
+                return CommandType::Response;
             }
-            
-            if i + 1 < hex_data.len() {
-                let high = hex_to_nibble(hex_data[i]);
-                let low = hex_to_nibble(hex_data[i + 1]);
-                
-                if high.is_some() && low.is_some() {
-                    descriptor_bytes[desc_len] = (high.unwrap() << 4) | low.unwrap();
-                    desc_len += 1;
-                }
-                i += 2;
-            } else {
-                break;
+        };
+        
+        if state == 1 {
+            if !self.held.press_key(scancode) {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Six keys already held\n", &mut self.response_len);
+                return CommandType::Response;
             }
+        } else {
+            self.held.release_key(scancode);
         }
         
-        // Auto-parse and cache
-        match descriptor_cache.add(addr, iface, &descriptor_bytes[..desc_len]) {
-            Ok(()) => {
-                // Get the cached descriptor
-                let desc = descriptor_cache.get(addr, iface).unwrap();
-                
-                // Log successful auto-parse
-                self.response_len = 0;
-                let mut msg = heapless::String::<128>::new();
-                let _ = write!(msg, "[AUTO] HID descriptor: dev={} if={} ", addr, iface);
-                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
-                
-                if desc.is_keyboard {
-                    write_str(&mut self.response_buffer[..], b"[Keyboard] ", &mut self.response_len);
-                }
-                if desc.is_mouse {
-                    write_str(&mut self.response_buffer[..], b"[Mouse] ", &mut self.response_len);
-                }
-                if desc.is_gamepad {
-                    write_str(&mut self.response_buffer[..], b"[Gamepad] ", &mut self.response_len);
-                }
-                
-                let _ = write!(msg, "{}B\n", desc_len);
-                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
-                
-                CommandType::Response
-            }
-            Err(_) => {
-                // Parsing failed - still log it
-                self.response_len = 0;
-                let mut msg = heapless::String::<128>::new();
-                let _ = write!(msg, "[WARN] Failed to parse descriptor: dev={} if={}\n", addr, iface);
-                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
-                CommandType::Response
+        self.keyboard_command()
+    }
+    
+    /// Handle mod command
+    /// Format: nozen.mod(name,state), e.g. nozen.mod(shift,1); see modifier_from_name
+    /// Sends a report with only the held modifiers and keys, no new scancode
+    fn handle_mod(&mut self, line: &[u8]) -> CommandType {
+        self.response_len = 0;
+        let mut values = [0i16; 1];
+        let mask = parse_name_and_ints(line, b"nozen.mod(".len(), &mut values)
+            .and_then(modifier_from_name);
+        let mask = match mask {
+            Some(mask) if (0..=1).contains(&values[0]) => mask,
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.mod(ctrl|shift|alt|gui|rctrl|rshift|ralt|rgui,0|1)\n", &mut self.response_len);
+                return CommandType::Response;
             }
-        }
+        };
+        
+        self.held.set_modifiers(mask, values[0] == 1);
+        self.keyboard_command()
     }
     
-    /// Handle descriptor.add command - DEPRECATED, use FPGA auto-forward instead
-    /// Kept for manual testing only
-    #[allow(dead_code)]
-    fn handle_descriptor_add(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+    /// Handle key.isheld command
+    /// Format: nozen.key.isheld(scancode), answers held=1 or held=0
+    fn handle_key_isheld(&mut self, line: &[u8]) -> CommandType {
         use core::fmt::Write;
         
-        // Parse address and interface
-        let mut idx = b"nozen.descriptor.add(".len();
-        
-        // Parse address
-        let addr = match parse_u8_from_slice(&line[idx..]) {
-            Some(v) => v,
-            None => {
-                self.response_len = 0;
-                write_str(&mut self.response_buffer[..], b"[ERROR] Invalid address\n", &mut self.response_len);
+        self.response_len = 0;
+        let scancode = match parse_u8_from_slice(&line[b"nozen.key.isheld(".len()..]) {
+            Some(scancode) if scancode != 0 => scancode,
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.key.isheld(scancode)\n", &mut self.response_len);
                 return CommandType::Response;
             }
         };
         
-        // Skip to comma
-        while idx < line.len() && line[idx] != b',' {
-            idx += 1;
+        let mut msg = heapless::String::<32>::new();
+        let _ = writeln!(msg, "[Key] {} held={}", scancode, self.held.is_key_held(scancode) as u8);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        CommandType::Response
+    }
+    
+    /// Handle held command
+    /// Format: nozen.held; shows the buttons, modifiers and keys the firmware
+    /// considers pressed, i.e. what the next report will carry
+    fn handle_held(&mut self) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let mut msg = heapless::String::<80>::new();
+        let _ = writeln!(msg, "[Held] {}", self.held.format());
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        CommandType::Response
+    }
+    
+    /// Handle keys.table command
+    /// Format: nozen.keys.table or nozen.keys.table(page), pages numbered from 1
+    /// One row per printable ASCII character that ascii_to_scancode maps, with
+    /// its scancode and "shift" if it needs Shift; space is shown as "space"
+    fn handle_keys_table(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        let page = line.iter().position(|&c| c == b'(')
+            .and_then(|p| parse_u8_from_slice(&line[p + 1..]))
+            .unwrap_or(1)
+            .max(1) as usize;
+        
+        let rows = || (0x20u8..=0x7E).filter_map(|c| ascii_to_scancode(c).map(|(code, mods)| (c, code, mods)));
+        let total = rows().count();
+        let pages = total.div_ceil(KEYS_PER_PAGE).max(1);
+        
+        self.response_len = 0;
+        if page > pages {
+            write_str(&mut self.response_buffer[..], b"[ERROR] No such key table page\n", &mut self.response_len);
+            return CommandType::Response;
         }
-        idx += 1;
         
-        // Parse interface
-        let iface = match parse_u8_from_slice(&line[idx..]) {
-            Some(v) => v,
-            None => {
-                self.response_len = 0;
-                write_str(&mut self.response_buffer[..], b"[ERROR] Invalid interface\n", &mut self.response_len);
-                return CommandType::Response;
-            }
-        };
+        let mut msg = heapless::String::<48>::new();
+        let _ = writeln!(msg, "[Keys] ({}/{}, {} total)", page, pages, total);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
         
-        // Find hex data in braces
-        while idx < line.len() && line[idx] != b'{' {
-            idx += 1;
+        for (c, code, mods) in rows().skip((page - 1) * KEYS_PER_PAGE).take(KEYS_PER_PAGE) {
+            msg.clear();
+            match c {
+                b' ' => { let _ = write!(msg, "space"); }
+                _ => { let _ = msg.push(c as char); }
+            }
+            let _ = writeln!(msg, " 0x{:02X}{}", code, if mods != 0 { " shift" } else { "" });
+            write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
         }
-        idx += 1;
         
-        let start = idx;
-        while idx < line.len() && line[idx] != b'}' {
-            idx += 1;
+        if page < pages {
+            msg.clear();
+            let _ = writeln!(msg, "More: nozen.keys.table({})", page + 1);
+            write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
         }
         
-        // Parse hex data
-        let hex_data = &line[start..idx];
-        let mut descriptor_bytes = [0u8; 1024];
-        let mut desc_len = 0;
+        CommandType::Response
+    }
+    
+    /// INJECT_KBD command carrying the held modifiers and keys
+    fn keyboard_command(&self) -> CommandType {
+        let mut payload = [0u8; 128];
+        payload[..8].copy_from_slice(&self.held.keyboard_report());
+        CommandType::FpgaCommand(Command {
+            code: FpgaCode::InjectKbd as u8,
+            payload,
+            length: 8,
+        })
+    }
+    
+    /// Handle recoil.step command
+    /// Format: nozen.recoil.step(name,index), index counts triplets from 0
+    fn handle_recoil_step(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
         
-        let mut i = 0;
-        while i < hex_data.len() && desc_len < 1024 {
-            // Skip whitespace
-            while i < hex_data.len() && (hex_data[i] == b' ' || hex_data[i] == b',') {
-                i += 1;
-            }
-            
-            if i + 1 < hex_data.len() {
-                let high = hex_to_nibble(hex_data[i]);
-                let low = hex_to_nibble(hex_data[i + 1]);
-                
-                if high.is_none() || low.is_none() {
-                    self.response_len = 0;
-                    write_str(&mut self.response_buffer[..], b"[ERROR] Invalid hex data\n", &mut self.response_len);
-                    return CommandType::Response;
-                }
-                
-                descriptor_bytes[desc_len] = (high.unwrap() << 4) | low.unwrap();
-                desc_len += 1;
-                i += 2;
-            } else {
-                break;
+        self.response_len = 0;
+        let mut values = [0i16; 1];
+        let name = match parse_name_and_ints(line, b"nozen.recoil.step(".len(), &mut values) {
+            Some(name) => name,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.recoil.step(name,index)\n", &mut self.response_len);
+                return CommandType::Response;
             }
-        }
+        };
         
-        // Add to cache
-        match descriptor_cache.add(addr, iface, &descriptor_bytes[..desc_len]) {
-            Ok(()) => {
-                // Get the cached descriptor
-                let desc = descriptor_cache.get(addr, iface).unwrap();
-                
-                self.response_len = 0;
-                let mut msg = heapless::String::<128>::new();
-                let _ = write!(msg, "[OK] Descriptor cached: addr={} iface={} type=", addr, iface);
-                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
-                
-                if desc.is_keyboard {
-                    write_str(&mut self.response_buffer[..], b"Keyboard ", &mut self.response_len);
-                }
-                if desc.is_mouse {
-                    write_str(&mut self.response_buffer[..], b"Mouse ", &mut self.response_len);
+        let name_str = core::str::from_utf8(name).unwrap_or("???");
+        let mut msg = heapless::String::<96>::new();
+        match self.recoil_manager.get_pattern(name_str) {
+            Some(pattern) => match usize::try_from(values[0]).ok().and_then(|i| pattern.step(i)) {
+                Some((x, y, delay)) => {
+                    let _ = writeln!(msg, "{}[{}]: {},{},{}", name_str, values[0], x, y, delay);
                 }
-                if desc.is_gamepad {
-                    write_str(&mut self.response_buffer[..], b"Gamepad ", &mut self.response_len);
+                None => {
+                    let _ = writeln!(msg, "[ERROR] Step index out of range ({} steps)", pattern.step_count());
                 }
-                
-                write_str(&mut self.response_buffer[..], b"\n", &mut self.response_len);
-                CommandType::Response
-            }
-            Err(_) => {
-                self.response_len = 0;
-                write_str(&mut self.response_buffer[..], b"[ERROR] Failed to parse descriptor\n", &mut self.response_len);
-                CommandType::Response
+            },
+            None => {
+                let _ = writeln!(msg, "[ERROR] Pattern not found");
             }
         }
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
     }
     
-    /// Handle descriptor.get command
-    /// Format: nozen.descriptor.get(addr,iface)
-    fn handle_descriptor_get(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+    /// Handle recoil.fire command
+    /// Format: nozen.recoil.fire(name,index), index counts triplets from 0
+    /// Sends that one step's move now, unjittered; its delay is ignored
+    fn handle_recoil_fire(&mut self, line: &[u8]) -> CommandType {
         use core::fmt::Write;
         
-        // Parse address and interface
-        let mut idx = b"nozen.descriptor.get(".len();
+        self.response_len = 0;
+        let mut values = [0i16; 1];
+        let name = match parse_name_and_ints(line, b"nozen.recoil.fire(".len(), &mut values) {
+            Some(name) => name,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.recoil.fire(name,index)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
         
-        let addr = match parse_u8_from_slice(&line[idx..]) {
-            Some(v) => v,
+        let name_str = core::str::from_utf8(name).unwrap_or("???");
+        let pattern = match self.recoil_manager.get_pattern(name_str) {
+            Some(pattern) => pattern,
             None => {
-                self.response_len = 0;
-                write_str(&mut self.response_buffer[..], b"[ERROR] Invalid address\n", &mut self.response_len);
+                write_str(&mut self.response_buffer[..], PATTERN_NOT_FOUND, &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        let absolute = pattern.absolute;
+        let (x, y) = match usize::try_from(values[0]).ok().and_then(|i| pattern.step(i)) {
+            Some((x, y, _)) => (x, y),
+            None => {
+                let mut msg = heapless::String::<64>::new();
+                let _ = writeln!(msg, "[ERROR] Step index out of range ({} steps)", pattern.step_count());
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
                 return CommandType::Response;
             }
         };
         
-        while idx < line.len() && line[idx] != b',' {
-            idx += 1;
-        }
-        idx += 1;
+        // Absolute steps move to their target, as when the pattern runs
+        let (dx, dy) = if absolute {
+            let (dx, dy) = self.mouse_state.delta_to(x, y);
+            self.mouse_state.update_relative(dx, dy);
+            (dx, dy)
+        } else {
+            (x, y)
+        };
+        CommandType::FpgaCommand(Command::new(FpgaCode::InjectMouse as u8, &self.mouse_report(dx, dy)))
+    }
+    
+    /// Handle recoil.setstep command
+    /// Format: nozen.recoil.setstep(name,index,x,y,delay)
+    fn handle_recoil_setstep(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
         
-        let iface = match parse_u8_from_slice(&line[idx..]) {
-            Some(v) => v,
+        self.response_len = 0;
+        let mut values = [0i16; 4];
+        let name = match parse_name_and_ints(line, b"nozen.recoil.setstep(".len(), &mut values) {
+            Some(name) => name,
             None => {
-                self.response_len = 0;
-                write_str(&mut self.response_buffer[..], b"[ERROR] Invalid interface\n", &mut self.response_len);
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.recoil.setstep(name,index,x,y,delay)\n", &mut self.response_len);
                 return CommandType::Response;
             }
         };
         
-        // Get from cache
+        let name_str = core::str::from_utf8(name).unwrap_or("???");
+        let [index, x, y, delay] = values;
+        let mut msg = heapless::String::<96>::new();
+        match self.recoil_manager.get_pattern_mut(name_str) {
+            Some(pattern) => {
+                let result = usize::try_from(index)
+                    .map_err(|_| "Step index out of range")
+                    .and_then(|i| pattern.set_step(i, x, y, delay));
+                match result {
+                    Ok(()) => {
+                        let _ = writeln!(msg, "[OK] {}[{}]: {},{},{}", name_str, index, x, y, delay);
+                    }
+                    Err(e) => {
+                        let _ = writeln!(msg, "[ERROR] {} ({} steps)", e, pattern.step_count());
+                    }
+                }
+            }
+            None => {
+                let _ = writeln!(msg, "[ERROR] Pattern not found");
+            }
+        }
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle typeseq command
+    /// Format: nozen.typeseq(c:ms,c:ms,...), each key held `ms` before the next
+    /// Characters with no US-layout scancode are skipped. Each key is pressed on
+    /// top of the held keys and modifiers and released back to them.
+    fn handle_typeseq(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.typeseq(".len()..];
+        // ')' may itself be typed, so the list ends at the last one
+        let args = match args.iter().rposition(|&c| c == b')') {
+            Some(end) => &args[..end],
+            None => return CommandType::NoOp,
+        };
+        
+        let keys = match parse_typeseq(args) {
+            Some(keys) => keys,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.typeseq(c:ms,c:ms,...)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let mapped = keys.iter().filter(|(c, _)| ascii_to_scancode(*c).is_some()).count();
+        let fits = keys.iter()
+            .filter_map(|&(c, _)| ascii_to_scancode(c))
+            .all(|(scancode, _)| {
+                let mut chord = self.held;
+                chord.press_key(scancode)
+            });
+        if !fits {
+            write_str(&mut self.response_buffer[..], b"[ERROR] All six key slots held\n", &mut self.response_len);
+            return CommandType::Response;
+        }
+        if self.pending.reserve(mapped * 2).is_err() {
+            write_str(&mut self.response_buffer[..], QUEUE_FULL, &mut self.response_len);
+            return CommandType::Response;
+        }
+        
+        let release = self.held.keyboard_report();
+        let mut due = self.now_ms;
+        for &(c, dwell) in keys.iter() {
+            let Some((scancode, modifiers)) = ascii_to_scancode(c) else { continue };
+            let mut chord = self.held;
+            chord.set_modifiers(modifiers, true);
+            chord.press_key(scancode);
+            let pressed = chord.keyboard_report();
+            let _ = self.pending.push(PendingStep::new(due, FpgaCode::InjectKbd as u8, &pressed));
+            due = due.wrapping_add(dwell as u32);
+            let _ = self.pending.push(PendingStep::new(due, FpgaCode::InjectKbd as u8, &release));
+        }
+        
+        let mut msg = heapless::String::<64>::new();
+        let _ = writeln!(msg, "[OK] Queued {} keys, skipped {}", mapped, keys.len() - mapped);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle keyseq command
+    /// Format: nozen.keyseq(chord,chord,...), e.g. nozen.keyseq(ctrl+k,ctrl+c);
+    /// see parse_chord. Each chord is pressed on top of the held keys and
+    /// modifiers, held for CHORD_HOLD_MS and released back to them,
+    /// CHORD_GAP_MS before the next
+    fn handle_keyseq(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.keyseq(".len()..];
+        let args = &args[..args.iter().position(|&c| c == b')').unwrap_or(args.len())];
+        
+        let mut chords: heapless::Vec<[u8; 8], KEYSEQ_MAX> = heapless::Vec::new();
+        for segment in args.split(|&c| c == b',') {
+            let Some((scancode, modifiers)) = parse_chord(segment).filter(|_| !chords.is_full()) else {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.keyseq(mod+key,...), up to 16 chords\n", &mut self.response_len);
+                return CommandType::Response;
+            };
+            let mut chord = self.held;
+            chord.set_modifiers(modifiers, true);
+            if !chord.press_key(scancode) {
+                write_str(&mut self.response_buffer[..], b"[ERROR] All six key slots held\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+            let _ = chords.push(chord.keyboard_report());
+        }
+        
+        if self.pending.reserve(chords.len() * 2).is_err() {
+            write_str(&mut self.response_buffer[..], QUEUE_FULL, &mut self.response_len);
+            return CommandType::Response;
+        }
+        
+        let release = self.held.keyboard_report();
+        let mut due = self.now_ms;
+        for pressed in chords.iter() {
+            let _ = self.pending.push(PendingStep::new(due, FpgaCode::InjectKbd as u8, pressed));
+            due = due.wrapping_add(CHORD_HOLD_MS);
+            let _ = self.pending.push(PendingStep::new(due, FpgaCode::InjectKbd as u8, &release));
+            due = due.wrapping_add(CHORD_GAP_MS);
+        }
+        
+        let mut msg = heapless::String::<32>::new();
+        let _ = writeln!(msg, "[OK] Queued {} chords", chords.len());
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Drop held buttons/keys and pending steps, and stop anti-idle, wander
+    /// and any moveto.verify
+    /// Returns a neutral mouse report and queues a neutral keyboard report
+    /// so the target releases anything we were holding
+    fn release_all(&mut self) -> Command {
+        self.held.clear();
+        self.pending.clear();
+        self.anti_idle_ms = 0;
+        self.wander = None;
+        // Corrections are queued steps too, so a verify in progress stops here
+        self.settle = None;
+        let _ = self.pending.push(PendingStep::new(self.now_ms, FpgaCode::InjectKbd as u8, &KeyboardReport::empty().to_bytes()));
+        
+        Command {
+            code: FpgaCode::InjectMouse as u8,
+            payload: [0u8; 128],
+            length: 5,
+        }
+    }
+    
+    /// Handle releaseall command
+    fn handle_releaseall(&mut self) -> CommandType {
+        let cmd = self.release_all();
+        self.response_len = 0;
+        write_str(&mut self.response_buffer[..], b"[OK] Released all inputs\n", &mut self.response_len);
+        CommandType::FpgaCommand(cmd)
+    }
+    
+    /// Handle reset command
+    /// Clears tracked position, held inputs, pending steps, anti-idle and wander.
+    /// Recoil patterns, cached descriptors, target and screen size persist.
+    fn handle_reset(&mut self) -> CommandType {
+        self.mouse_state.set_position(0, 0);
+        let cmd = self.release_all();
+        self.latency = LatencyStats::new();
+        self.link = LinkStats::new();
+        
+        self.response_len = 0;
+        write_str(&mut self.response_buffer[..], b"[OK] Reset: position, held inputs, pending steps, anti-idle, wander, latency and link counters\n", &mut self.response_len);
+        write_str(&mut self.response_buffer[..], b"[OK] Kept: recoil patterns, descriptors, target, screen, modes, wheel step, lenient, default button\n", &mut self.response_len);
+        
+        CommandType::FpgaCommand(cmd)
+    }
+    
+    /// Handle target command
+    /// Format: nozen.target(addr,iface)
+    fn handle_target(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let (addr, iface) = match parse_addr_iface(line, b"nozen.target(".len()) {
+            Some(v) => v,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Invalid target\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        self.active_target = Some((addr, iface));
+        
+        let mut msg = heapless::String::<64>::new();
+        let _ = writeln!(msg, "[OK] Target: addr={} iface={}", addr, iface);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        if descriptor_cache.get(addr, iface).is_none() {
+            write_str(&mut self.response_buffer[..], b"[WARN] Target not in descriptor cache\n", &mut self.response_len);
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle target.clear command
+    fn handle_target_clear(&mut self) -> CommandType {
+        self.active_target = None;
+        self.response_len = 0;
+        write_str(&mut self.response_buffer[..], b"[OK] Target cleared\n", &mut self.response_len);
+        CommandType::Response
+    }
+    
+    /// Handle screen command
+    /// Format: nozen.screen(width,height)
+    fn handle_screen(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.screen(".len()..];
+        let size = args.iter().position(|&c| c == b',').and_then(|comma| {
+            let width = parse_int(&args[..comma])?;
+            let height = parse_int(&args[comma + 1..])?;
+            Some((width, height))
+        });
+        
+        match size {
+            Some((width, height)) if width > 0 && height > 0 => {
+                self.screen_size = (width as u16, height as u16);
+                let mut msg = heapless::String::<64>::new();
+                let _ = writeln!(msg, "[OK] Screen: {}x{}", width, height);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Invalid screen size\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle wheel.step command
+    /// Format: nozen.wheel.step(n), n in 1..=127 wheel units per notch
+    fn handle_wheel_step(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.wheel.step(".len()..];
+        let step = args.iter().position(|&c| c == b')')
+            .and_then(|end| parse_int(&args[..end]));
+        
+        match step {
+            Some(step) if (1..=127).contains(&step) => {
+                self.wheel_step = step as u8;
+                let mut msg = heapless::String::<32>::new();
+                let _ = writeln!(msg, "[OK] Wheel step: {}", step);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Wheel step must be 1-127\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle lenient command
+    /// Format: nozen.lenient(1) to truncate over-long input with a warning, nozen.lenient(0) to reject it
+    fn handle_lenient(&mut self, line: &[u8]) -> CommandType {
+        self.response_len = 0;
+        match parse_u8_from_slice(&line[b"nozen.lenient(".len()..]) {
+            Some(0) => {
+                self.lenient = false;
+                write_str(&mut self.response_buffer[..], b"[OK] Lenient: off\n", &mut self.response_len);
+            }
+            Some(1) => {
+                self.lenient = true;
+                write_str(&mut self.response_buffer[..], b"[OK] Lenient: on\n", &mut self.response_len);
+            }
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.lenient(0|1)\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle strict command
+    /// Format: nozen.strict(on|off), also accepts 1|0
+    fn handle_strict(&mut self, line: &[u8]) -> CommandType {
+        self.response_len = 0;
+        let args = &line[b"nozen.strict(".len()..];
+        let arg = args.iter().position(|&c| c == b')').map(|end| &args[..end]);
+        
+        match arg {
+            Some(b"on") | Some(b"1") => {
+                self.strict = true;
+                write_str(&mut self.response_buffer[..], b"[OK] Strict: on\n", &mut self.response_len);
+            }
+            Some(b"off") | Some(b"0") => {
+                self.strict = false;
+                write_str(&mut self.response_buffer[..], b"[OK] Strict: off\n", &mut self.response_len);
+            }
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.strict(on|off)\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle dryrun command
+    /// Format: nozen.dryrun(1) to stop sending to the FPGA, nozen.dryrun(0) to resume
+    fn handle_dryrun(&mut self, line: &[u8]) -> CommandType {
+        self.response_len = 0;
+        match parse_u8_from_slice(&line[b"nozen.dryrun(".len()..]) {
+            Some(0) => {
+                self.dry_run = false;
+                write_str(&mut self.response_buffer[..], b"[OK] Dry-run: off\n", &mut self.response_len);
+            }
+            Some(1) => {
+                self.dry_run = true;
+                write_str(&mut self.response_buffer[..], b"[OK] Dry-run: on\n", &mut self.response_len);
+            }
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.dryrun(0|1)\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle maxspeed command
+    /// Format: nozen.maxspeed(px), 1-127, caps each relative moveto report
+    fn handle_maxspeed(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.maxspeed(".len()..];
+        let px = args.iter().position(|&c| c == b')')
+            .and_then(|end| parse_int(&args[..end]));
+        
+        match px {
+            Some(px) if (1..=127).contains(&px) => {
+                self.max_speed = px as u8;
+                let mut msg = heapless::String::<48>::new();
+                let _ = writeln!(msg, "[OK] Max speed: {} px/report", px);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Max speed must be 1-127\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle reportid command
+    /// Format: nozen.reportid(id), 1-255 forces the report ID byte on injected
+    /// mouse and keyboard reports, 0 goes back to sending them unprefixed
+    fn handle_reportid(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.reportid(".len()..];
+        let id = args.iter().position(|&c| c == b')')
+            .and_then(|end| parse_int(&args[..end]));
+        
+        match id {
+            Some(0) => {
+                self.report_id = 0;
+                write_str(&mut self.response_buffer[..], b"[OK] Report ID: default\n", &mut self.response_len);
+            }
+            Some(id) if (1..=255).contains(&id) => {
+                self.report_id = id as u8;
+                let mut msg = heapless::String::<48>::new();
+                let _ = writeln!(msg, "[OK] Report ID: {} forced", id);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Report ID must be 0-255\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle antiidle command
+    /// Format: nozen.antiidle(ms,1) jiggles the mouse every ms (100-30000),
+    /// nozen.antiidle(0,0) stops it
+    fn handle_antiidle(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.antiidle(".len()..];
+        let args = &args[..args.iter().position(|&c| c == b')').unwrap_or(args.len())];
+        let mut parts = args.splitn(2, |&c| c == b',');
+        let setting = (parts.next().and_then(parse_int), parts.next().and_then(parse_int));
+        
+        match setting {
+            (Some(_), Some(0)) => {
+                self.anti_idle_ms = 0;
+                write_str(&mut self.response_buffer[..], b"[OK] Anti-idle off\n", &mut self.response_len);
+            }
+            (Some(ms), Some(1)) if (ANTI_IDLE_MIN_MS..=30_000).contains(&ms) => {
+                self.anti_idle_ms = ms as u16;
+                self.anti_idle_due_ms = self.now_ms.wrapping_add(ms as u32);
+                let mut msg = heapless::String::<48>::new();
+                let _ = writeln!(msg, "[OK] Anti-idle every {} ms", ms);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            _ => {
+                let mut msg = heapless::String::<80>::new();
+                let _ = writeln!(msg, "[ERROR] Usage: nozen.antiidle({}-30000,1) or nozen.antiidle(0,0)", ANTI_IDLE_MIN_MS);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle wander command
+    /// Format: nozen.wander(radius,ms) drifts one count every ms (10-30000)
+    /// within radius (1-1000) of the current position, nozen.wander(0,0) stops
+    fn handle_wander(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.wander(".len()..];
+        let args = &args[..args.iter().position(|&c| c == b')').unwrap_or(args.len())];
+        let mut parts = args.splitn(2, |&c| c == b',');
+        let setting = (parts.next().and_then(parse_int), parts.next().and_then(parse_int));
+        
+        let mut msg = heapless::String::<80>::new();
+        match setting {
+            (Some(0), Some(0)) => {
+                self.wander = None;
+                let _ = writeln!(msg, "[OK] Wander off");
+            }
+            (Some(radius), Some(ms)) if (1..=WANDER_MAX_RADIUS).contains(&radius)
+                && (WANDER_MIN_MS..=30_000).contains(&ms) => {
+                self.wander = Some(Wander::new(radius, 0x2545_F491 ^ self.now_ms));
+                self.wander_ms = ms as u16;
+                self.wander_due_ms = self.now_ms.wrapping_add(ms as u32);
+                let _ = writeln!(msg, "[OK] Wander: {} px every {} ms", radius, ms);
+            }
+            _ => {
+                let _ = writeln!(msg, "[ERROR] Usage: nozen.wander(1-{},{}-30000) or nozen.wander(0,0)",
+                    WANDER_MAX_RADIUS, WANDER_MIN_MS);
+            }
+        }
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle deadman command
+    /// Format: nozen.deadman(ms), 100-30000; 0 turns it off
+    /// With no command for ms, held inputs are released and the queue flushed
+    fn handle_deadman(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.deadman(".len()..];
+        let ms = args.iter().position(|&c| c == b')')
+            .and_then(|end| parse_int(&args[..end]));
+        
+        let mut msg = heapless::String::<64>::new();
+        match ms {
+            Some(0) => {
+                self.deadman_ms = 0;
+                let _ = writeln!(msg, "[OK] Deadman off");
+            }
+            Some(ms) if (DEADMAN_MIN_MS..=30_000).contains(&ms) => {
+                self.deadman_ms = ms as u16;
+                let _ = writeln!(msg, "[OK] Deadman: release after {} ms idle", ms);
+            }
+            _ => {
+                let _ = writeln!(msg, "[ERROR] Usage: nozen.deadman(0 or {}-30000)", DEADMAN_MIN_MS);
+            }
+        }
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle canon command
+    /// Format: nozen.canon(on|off), also accepts 1|0
+    fn handle_canon(&mut self, line: &[u8]) -> CommandType {
+        self.response_len = 0;
+        let args = &line[b"nozen.canon(".len()..];
+        let arg = args.iter().position(|&c| c == b')').map(|end| &args[..end]);
+        
+        match arg {
+            Some(b"on") | Some(b"1") => {
+                self.canon = true;
+                write_str(&mut self.response_buffer[..], b"[OK] Canon: on\n", &mut self.response_len);
+            }
+            Some(b"off") | Some(b"0") => {
+                self.canon = false;
+                write_str(&mut self.response_buffer[..], b"[OK] Canon: off\n", &mut self.response_len);
+            }
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.canon(on|off)\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle track command
+    /// Format: nozen.track(on|off), also accepts 1|0
+    fn handle_track(&mut self, line: &[u8]) -> CommandType {
+        self.response_len = 0;
+        let args = &line[b"nozen.track(".len()..];
+        let arg = args.iter().position(|&c| c == b')').map(|end| &args[..end]);
+        
+        match arg {
+            Some(b"on") | Some(b"1") => {
+                self.track = true;
+                write_str(&mut self.response_buffer[..], b"[OK] Track: on\n", &mut self.response_len);
+            }
+            Some(b"off") | Some(b"0") => {
+                self.track = false;
+                write_str(&mut self.response_buffer[..], b"[OK] Track: off\n", &mut self.response_len);
+            }
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.track(on|off)\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle sensitivity command
+    /// Format: nozen.sensitivity(percent), 1-1000, scales nozen.move deltas
+    fn handle_sensitivity(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.sensitivity(".len()..];
+        let percent = args.iter().position(|&c| c == b')')
+            .and_then(|end| parse_int(&args[..end]));
+        
+        match percent {
+            Some(percent) if (1..=1000).contains(&percent) => {
+                self.sensitivity = percent as u16;
+                let mut msg = heapless::String::<32>::new();
+                let _ = writeln!(msg, "[OK] Sensitivity: {}%", percent);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Sensitivity must be 1-1000\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle profile command
+    /// Format: nozen.profile(command), e.g. nozen.profile(nozen.move(10,5))
+    /// Times how long the inner command takes to parse. Only the commands
+    /// nozen.frame accepts are run; their frame is not sent and position, held
+    /// inputs and pending steps are rolled back.
+    fn handle_profile(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        let args = &line[b"nozen.profile(".len()..];
+        let inner = match args.iter().rposition(|&c| c == b')') {
+            Some(end) if end > 0 => &args[..end],
+            _ => {
+                self.response_len = 0;
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.profile(command)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        // Anything else would take effect for real: settings would be saved,
+        // stats cleared or timed commands started
+        let entry = match find_command(COMMAND_TABLE, inner).filter(|entry| builds_frame(entry.prefix)) {
+            Some(entry) => entry,
+            None => {
+                self.response_len = 0;
+                write_str(&mut self.response_buffer[..], b"[ERROR] Not an FPGA command\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let timer = self.cycle_timer;
+        let (result, cycles) = self.rolled_back(|p| timer.time(|| (entry.handler)(p, inner, descriptor_cache)));
+        
+        let mut msg = heapless::String::<160>::new();
+        let _ = write!(msg, "[Profile] {}: {} cycles, {} us",
+                       core::str::from_utf8(inner).unwrap_or("?"), cycles, timer.to_us(cycles));
+        if result == CommandType::NoOp {
+            let _ = msg.push_str(" (not parsed)");
+        }
+        let _ = msg.push('\n');
+        self.response_len = 0;
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        CommandType::Response
+    }
+    
+    /// Handle deadzone command
+    /// Format: nozen.deadzone(n), n 0-127; 0 turns it off
+    fn handle_deadzone(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.deadzone(".len()..];
+        let n = args.iter().position(|&c| c == b')')
+            .and_then(|end| parse_int(&args[..end]));
+        
+        match n {
+            Some(n) if (0..=127).contains(&n) => {
+                self.deadzone = n as u8;
+                let mut msg = heapless::String::<32>::new();
+                let _ = writeln!(msg, "[OK] Deadzone: {}", n);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Deadzone must be 0-127\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle invert command
+    /// Format: nozen.invert(x,y), each 0 or 1, negates nozen.move deltas per axis
+    fn handle_invert(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        match parse_u8_pair(line, b"nozen.invert(".len()) {
+            Some((x, y)) if x <= 1 && y <= 1 => {
+                self.invert = (x == 1, y == 1);
+                let mut msg = heapless::String::<32>::new();
+                let _ = writeln!(msg, "[OK] Invert: x={} y={}", on_off(self.invert.0), on_off(self.invert.1));
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.invert(x,y) with 0 or 1\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Current mode settings
+    pub fn mode_snapshot(&self) -> ModeSnapshot {
+        ModeSnapshot {
+            dry_run: self.dry_run,
+            human: self.humanizer.profile,
+            sensitivity: self.sensitivity,
+            max_speed: self.max_speed,
+            invert: self.invert,
+            canon: self.canon,
+            deadzone: self.deadzone,
+            wheel_step: self.wheel_step,
+            strict: self.strict,
+            track: self.track,
+            report_id: self.report_id,
+        }
+    }
+    
+    /// Handle mode.save command
+    /// Keeps one snapshot of the mode settings; saving again replaces it
+    fn handle_mode_save(&mut self) -> CommandType {
+        self.saved_mode = Some(self.mode_snapshot());
+        self.response_len = 0;
+        write_str(&mut self.response_buffer[..], b"[OK] Mode saved\n", &mut self.response_len);
+        CommandType::Response
+    }
+    
+    /// Handle mode.restore command
+    /// Puts back the settings from nozen.mode.save and drops the snapshot
+    fn handle_mode_restore(&mut self) -> CommandType {
+        self.response_len = 0;
+        let Some(mode) = self.saved_mode.take() else {
+            write_str(&mut self.response_buffer[..], b"[ERROR] No saved mode, use nozen.mode.save first\n", &mut self.response_len);
+            return CommandType::Response;
+        };
+        
+        self.dry_run = mode.dry_run;
+        self.humanizer.profile = mode.human;
+        self.sensitivity = mode.sensitivity;
+        self.max_speed = mode.max_speed;
+        self.invert = mode.invert;
+        self.canon = mode.canon;
+        self.deadzone = mode.deadzone;
+        self.wheel_step = mode.wheel_step;
+        self.strict = mode.strict;
+        self.track = mode.track;
+        self.report_id = mode.report_id;
+        write_str(&mut self.response_buffer[..], b"[OK] Mode restored\n", &mut self.response_len);
+        CommandType::Response
+    }
+    
+    /// Handle mode command
+    /// Lists every setting that changes what gets injected
+    fn handle_mode(&mut self, descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        let coords = if self.absolute_axes(descriptor_cache).is_some() { "absolute" } else { "relative" };
+        
+        self.response_len = 0;
+        let mut msg = heapless::String::<64>::new();
+        let _ = writeln!(msg, "Mode:\n  Coordinates: {}", coords);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        msg.clear();
+        let _ = writeln!(msg, "  Dry-run: {}\n  Humanizer: {}", on_off(self.dry_run), self.humanizer.profile.name());
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        msg.clear();
+        let _ = writeln!(msg, "  Sensitivity: {}%\n  Max speed: {}", self.sensitivity, self.max_speed);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        msg.clear();
+        let _ = writeln!(msg, "  Deadzone: {}\n  Wheel step: {}", self.deadzone, self.wheel_step);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        msg.clear();
+        let _ = writeln!(msg, "  Invert: x={} y={}", on_off(self.invert.0), on_off(self.invert.1));
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        msg.clear();
+        let _ = writeln!(msg, "  Canon: {}\n  Strict: {}\n  Track: {}", on_off(self.canon), on_off(self.strict), on_off(self.track));
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        msg.clear();
+        let _ = match self.report_id {
+            0 => writeln!(msg, "  Report ID: default"),
+            id => writeln!(msg, "  Report ID: {} forced", id),
+        };
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle human command
+    /// Format: nozen.human(off|low|med|high)
+    fn handle_human(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.human(".len()..];
+        let profile = args.iter().position(|&c| c == b')')
+            .and_then(|end| HumanProfile::from_name(&args[..end]));
+        
+        match profile {
+            Some(profile) => {
+                self.humanizer.profile = profile;
+                let params = profile.params();
+                let mut msg = heapless::String::<96>::new();
+                let _ = writeln!(msg, "[OK] Human: {} (jitter={} easing={} variance={}ms)",
+                    profile.name(), params.jitter, params.easing_steps, params.timing_variance_ms);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.human(off|low|med|high)\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle loopfreq command
+    /// Reports main loop iterations per second
+    fn handle_loopfreq(&mut self) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        match self.loop_rate.hz() {
+            Some(hz) => {
+                let mut msg = heapless::String::<64>::new();
+                let _ = writeln!(msg, "[OK] Loop: {} Hz", hz);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            None => {
+                write_str(&mut self.response_buffer[..], b"[WARN] Loop rate not measured yet\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle uptime command
+    /// Milliseconds since boot, raw and as h:mm:ss
+    fn handle_uptime(&mut self) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let mut msg = heapless::String::<80>::new();
+        let _ = writeln!(msg, "[OK] {}", self.uptime.format());
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle rtt command
+    /// Format: nozen.rtt or nozen.rtt(token), token up to RTT_TOKEN_MAX printable
+    /// characters. Answers straight away with the token and the ms clock, so the
+    /// host can time the round trip; nothing is queued or sent to the FPGA
+    fn handle_rtt(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let token = match line[b"nozen.rtt".len()..].strip_prefix(b"(") {
+            None => &[][..],
+            Some(args) => match args.iter().position(|&c| c == b')') {
+                Some(end) if end <= RTT_TOKEN_MAX && args[..end].iter().all(|&c| c.is_ascii_graphic()) => &args[..end],
+                _ => {
+                    write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.rtt(token), token up to 32 characters\n", &mut self.response_len);
+                    return CommandType::Response;
+                }
+            },
+        };
+        
+        write_str(&mut self.response_buffer[..], b"[RTT] ", &mut self.response_len);
+        if !token.is_empty() {
+            write_str(&mut self.response_buffer[..], token, &mut self.response_len);
+            write_str(&mut self.response_buffer[..], b" ", &mut self.response_len);
+        }
+        let mut msg = heapless::String::<24>::new();
+        let _ = writeln!(msg, "t={}", self.now_ms);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle telemetry command
+    /// Format: nozen.telemetry or nozen.telemetry(page), pages numbered from 1
+    /// One line per subsystem, split into pages that fit the response buffer
+    fn handle_telemetry(&mut self, line: &[u8], descriptor_cache: &DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+
+        let page = line.iter().position(|&c| c == b'(')
+            .and_then(|p| parse_u8_from_slice(&line[p + 1..]))
+            .unwrap_or(1)
+            .max(1);
+
+        let mut sections: [heapless::String<128>; TELEMETRY_SECTIONS] = Default::default();
+        let _ = sections[0].push_str(&self.uptime.format());
+        let _ = sections[1].push_str(&self.loop_rate.format());
+        let _ = sections[2].push_str(&self.latency.format());
+        let _ = sections[3].push_str(&self.link.format());
+        let _ = write!(sections[4], "Cache: {}", descriptor_cache.get_stats().format());
+        let _ = write!(sections[5], "Recoil: {}/{} patterns", self.recoil_manager.count(), MAX_PATTERNS);
+
+        let budget = self.response_buffer.len() - HELP_OVERHEAD;
+        let mut page_of = [0u8; TELEMETRY_SECTIONS];
+        let mut pages = 1u8;
+        let mut used = 0;
+        for (i, section) in sections.iter().enumerate() {
+            let len = section.len() + 1;
+            if used + len > budget {
+                pages += 1;
+                used = 0;
+            }
+            used += len;
+            page_of[i] = pages;
+        }
+
+        self.response_len = 0;
+        if page > pages {
+            write_str(&mut self.response_buffer[..], b"[ERROR] No such telemetry page\n", &mut self.response_len);
+            return CommandType::Response;
+        }
+
+        let mut msg = heapless::String::<32>::new();
+        let _ = writeln!(msg, "[Telemetry] ({}/{})", page, pages);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+
+        for (i, section) in sections.iter().enumerate() {
+            if page_of[i] == page {
+                write_str(&mut self.response_buffer[..], section.as_bytes(), &mut self.response_len);
+                write_str(&mut self.response_buffer[..], b"\n", &mut self.response_len);
+            }
+        }
+
+        if page < pages {
+            msg.clear();
+            let _ = writeln!(msg, "More: nozen.telemetry({})", page + 1);
+            write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        }
+
+        CommandType::Response
+    }
+
+    /// Handle uart.capture command
+    /// Format: nozen.uart.capture(ms), 1-30000; the trace follows once the window ends
+    fn handle_uart_capture(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.uart.capture(".len()..];
+        let ms = args.iter().position(|&c| c == b')')
+            .and_then(|end| parse_int(&args[..end]));
+        
+        match ms {
+            Some(ms) if (1..=30000).contains(&ms) => {
+                self.capture.start(self.now_ms, ms as u32);
+                let mut msg = heapless::String::<48>::new();
+                let _ = writeln!(msg, "[OK] Capturing FPGA lines for {} ms", ms);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Capture time must be 1-30000 ms\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle bench command
+    /// Format: nozen.bench(count); the main loop sends the reports and
+    /// reports the timing through finish_bench
+    fn handle_bench(&mut self, line: &[u8]) -> CommandType {
+        self.response_len = 0;
+        let args = &line[b"nozen.bench(".len()..];
+        let count = args.iter().position(|&c| c == b')')
+            .and_then(|end| parse_int(&args[..end]));
+        
+        match count {
+            Some(count) if count >= 1 && count as u16 <= BENCH_MAX_COUNT => {
+                if self.dry_run {
+                    write_str(&mut self.response_buffer[..], b"[ERROR] Bench sends reports, turn off dry-run first\n", &mut self.response_len);
+                    return CommandType::Response;
+                }
+                CommandType::Bench(count as u16)
+            }
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Bench count must be 1-1000\n", &mut self.response_len);
+                CommandType::Response
+            }
+        }
+    }
+    
+    /// Handle serial command
+    /// Format: nozen.serial to show, nozen.serial(set,ABC123) to change
+    /// A new serial is saved to flash and used from the next boot
+    fn handle_serial(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.serial".len()..];
+        if args.is_empty() || args == b"()" {
+            let mut msg = heapless::String::<48>::new();
+            let _ = writeln!(msg, "[Serial] {}", self.config.serial);
+            write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            return CommandType::Response;
+        }
+        
+        let value = args.strip_prefix(b"(set,")
+            .and_then(parse_text_arg)
+            .filter(|(value, used)| *used == args.len() - b"(set,".len() && is_valid_serial(value))
+            .map(|(value, _)| value);
+        match value {
+            Some(value) => {
+                self.config.serial.clear();
+                for &b in value.iter() {
+                    let _ = self.config.serial.push(b as char);
+                }
+                self.config_dirty = true;
+                let mut msg = heapless::String::<64>::new();
+                let _ = writeln!(msg, "[OK] Serial: {} (applies after restart)", self.config.serial);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Serial must be 1-16 printable ASCII characters\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle banner command
+    /// Format: nozen.banner to show, nozen.banner(on|off) to change
+    /// Saved to flash; off keeps the CDC port silent at boot until a command
+    fn handle_banner(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.banner".len()..];
+        let mut msg = heapless::String::<48>::new();
+        let banner = match args {
+            b"" | b"()" => {
+                let _ = writeln!(msg, "[Banner] {}", on_off(self.config.banner));
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+                return CommandType::Response;
+            }
+            b"(on)" | b"(1)" => true,
+            b"(off)" | b"(0)" => false,
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.banner(on|off)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        self.config.banner = banner;
+        self.config_dirty = true;
+        let _ = writeln!(msg, "[OK] Banner: {} (applies after restart)", on_off(banner));
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        CommandType::Response
+    }
+    
+    /// Handle usb.ids command
+    /// Format: nozen.usb.ids to show, nozen.usb.ids(vid,pid) in hex to change
+    /// The IDs are saved to flash and used from the next boot (nozen.restart);
+    /// nozen.usb.reattach re-enumerates with the IDs the device booted with
+    fn handle_usb_ids(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.usb.ids".len()..];
+        let mut msg = heapless::String::<64>::new();
+        if args.is_empty() || args == b"()" {
+            let _ = writeln!(msg, "[USB] VID:PID {:04x}:{:04x}", self.config.vid, self.config.pid);
+            write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            return CommandType::Response;
+        }
+        
+        let ids = args.strip_prefix(b"(")
+            .and_then(|rest| rest.strip_suffix(b")"))
+            .and_then(|rest| {
+                let comma = rest.iter().position(|&c| c == b',')?;
+                Some((parse_hex_u16(&rest[..comma])?, parse_hex_u16(&rest[comma + 1..])?))
+            });
+        match ids {
+            Some((vid, pid)) => {
+                self.config.vid = vid;
+                self.config.pid = pid;
+                self.config_dirty = true;
+                let _ = writeln!(msg, "[OK] VID:PID {:04x}:{:04x} (applies after restart)", vid, pid);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.usb.ids(vid,pid), 1-4 hex digits each\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle usb.strings command
+    /// Format: nozen.usb.strings to show, nozen.usb.strings(product|manufacturer,text) to change
+    /// Text may be quoted as for nozen.print; it is saved to flash and used from
+    /// the next boot (nozen.restart), not from a nozen.usb.reattach
+    fn handle_usb_strings(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.usb.strings".len()..];
+        let mut msg = heapless::String::<96>::new();
+        if args.is_empty() || args == b"()" {
+            let _ = writeln!(msg, "[USB] Product: {}\nManufacturer: {}", self.config.product, self.config.manufacturer);
+            write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            return CommandType::Response;
+        }
+        
+        let parsed = args.strip_prefix(b"(").and_then(|rest| {
+            let comma = rest.iter().position(|&c| c == b',')?;
+            let (text, used) = parse_text_arg(&rest[comma + 1..])?;
+            let valid = comma + 1 + used == rest.len() && is_valid_usb_string(&text);
+            valid.then_some((&rest[..comma], text))
+        });
+        let target = match parsed {
+            Some((b"product", text)) => Some(("Product", &mut self.config.product, text)),
+            Some((b"manufacturer", text)) => Some(("Manufacturer", &mut self.config.manufacturer, text)),
+            _ => None,
+        };
+        match target {
+            Some((name, field, text)) => {
+                field.clear();
+                for &b in text.iter() {
+                    let _ = field.push(b as char);
+                }
+                self.config_dirty = true;
+                let _ = writeln!(msg, "[OK] {}: {} (applies after restart)", name, field);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.usb.strings(product|manufacturer,text), 1-32 printable chars\n", &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle errors command
+    /// Reports how many lines produced no action and the last of them
+    fn handle_errors(&mut self) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let mut msg = heapless::String::<32>::new();
+        let _ = writeln!(msg, "[Errors] {}", self.bad_lines);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        if !self.last_bad_line.is_empty() {
+            write_str(&mut self.response_buffer[..], b"Last: ", &mut self.response_len);
+            write_str(&mut self.response_buffer[..], &self.last_bad_line, &mut self.response_len);
+            write_str(&mut self.response_buffer[..], b"\n", &mut self.response_len);
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle cmdstats command
+    fn handle_cmdstats(&mut self) -> CommandType {
+        self.response_len = 0;
+        write_str(&mut self.response_buffer[..], b"[CmdStats] ", &mut self.response_len);
+        write_str(&mut self.response_buffer[..], self.cmd_stats.format().as_bytes(), &mut self.response_len);
+        write_str(&mut self.response_buffer[..], b"\n", &mut self.response_len);
+        CommandType::Response
+    }
+    
+    /// Handle codes command: every FpgaCode as NAME=0xXX
+    fn handle_codes(&mut self) -> CommandType {
+        use core::fmt::Write;
+
+        self.response_len = 0;
+        write_str(&mut self.response_buffer[..], b"[Codes]", &mut self.response_len);
+        for code in FpgaCode::ALL {
+            let mut entry: heapless::String<24> = heapless::String::new();
+            let _ = write!(entry, " {}=0x{:02X}", code.name(), code as u8);
+            write_str(&mut self.response_buffer[..], entry.as_bytes(), &mut self.response_len);
+        }
+        write_str(&mut self.response_buffer[..], b"\n", &mut self.response_len);
+        CommandType::Response
+    }
+
+    /// Handle cmdstats.reset command
+    fn handle_cmdstats_reset(&mut self) -> CommandType {
+        self.cmd_stats = CommandStats::default();
+        
+        self.response_len = 0;
+        write_str(&mut self.response_buffer[..], b"[OK] Command stats cleared\n", &mut self.response_len);
+        CommandType::Response
+    }
+    
+    /// Handle errors.clear command
+    fn handle_errors_clear(&mut self) -> CommandType {
+        self.bad_lines = 0;
+        self.last_bad_line.clear();
+        
+        self.response_len = 0;
+        write_str(&mut self.response_buffer[..], b"[OK] Errors cleared\n", &mut self.response_len);
+        CommandType::Response
+    }
+    
+    /// Handle uart.history command
+    /// Lists the most recent FPGA lines, oldest first
+    fn handle_uart_history(&mut self) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let mut msg = heapless::String::<32>::new();
+        let _ = writeln!(msg, "[History] {} lines", self.uart_history.len());
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        for line in self.uart_history.iter() {
+            write_str(&mut self.response_buffer[..], line, &mut self.response_len);
+            write_str(&mut self.response_buffer[..], b"\n", &mut self.response_len);
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle queue.depth command
+    /// Steps waiting in the pending-step queue out of MAX_QUEUE_DEPTH, and how
+    /// many commands were refused with "Queue full" since boot
+    fn handle_queue_depth(&mut self) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let mut msg = heapless::String::<64>::new();
+        let _ = writeln!(msg, "[Queue] {}/{} used, {} rejected", self.pending.len(), MAX_QUEUE_DEPTH, self.pending.rejected());
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        CommandType::Response
+    }
+    
+    /// Handle queue.list command
+    /// Counts the pending steps by kind and says when the last one is due;
+    /// nothing is removed from the queue
+    fn handle_queue_list(&mut self) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        if self.pending.is_empty() {
+            write_str(&mut self.response_buffer[..], b"[Queue] empty\n", &mut self.response_len);
+            return CommandType::Response;
+        }
+        
+        let mut msg = heapless::String::<128>::new();
+        let _ = write!(msg, "[Queue] {} pending:", self.pending.len());
+        let mut sep = " ";
+        for kind in StepKind::ALL {
+            let count = self.pending.count(kind);
+            if count > 0 {
+                let _ = write!(msg, "{}{} {}", sep, count, kind.name(count));
+                sep = ", ";
+            }
+        }
+        let last_ms = self.pending.iter()
+            .map(|step| (step.due_ms.wrapping_sub(self.now_ms) as i32).max(0))
+            .max()
+            .unwrap_or(0);
+        let _ = writeln!(msg, "; last due in {} ms", last_ms);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        CommandType::Response
+    }
+    
+    /// Handle buffer command
+    /// Reports the bytes waiting in the line accumulator as hex without consuming them
+    fn handle_buffer(&mut self) -> CommandType {
+        use core::fmt::Write;
+        
+        // Two hex digits per byte, leaving room for the header and overflow note
+        const MAX_SHOWN: usize = 96;
+        
+        self.response_len = 0;
+        let mut msg = heapless::String::<64>::new();
+        let _ = writeln!(msg, "[Buffer] {} bytes", self.index);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        let shown = self.index.min(MAX_SHOWN);
+        for i in 0..shown {
+            let byte = self.buffer[i];
+            write_str(&mut self.response_buffer[..], &[hex_digit(byte >> 4), hex_digit(byte & 0x0F)], &mut self.response_len);
+        }
+        if self.index > shown {
+            msg.clear();
+            let _ = write!(msg, " (+{} more)", self.index - shown);
+            write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        }
+        write_str(&mut self.response_buffer[..], b"\n", &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Run a builds_frame handler and put back position, held inputs and
+    /// pending steps afterwards. The handler is called directly, so the
+    /// command isn't counted by nozen.cmdstats.
+    fn rolled_back<T>(&mut self, run: impl FnOnce(&mut Self) -> T) -> T {
+        let position = self.mouse_state.position();
+        let held = self.held;
+        let pending = self.pending.clone();
+        let result = run(self);
+        self.mouse_state.set_position(position.0, position.1);
+        self.held = held;
+        self.pending = pending;
+        result
+    }
+    
+    /// Handle frame command
+    /// Format: nozen.frame(nozen.move(10,5))
+    /// Runs the inner command and returns its UART frame as hex instead of
+    /// sending it. Position, held inputs and pending steps are rolled back.
+    fn handle_frame(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        const HEADER: &[u8] = b"[Frame] ";
+        
+        let args = &line[b"nozen.frame(".len()..];
+        let inner = match args.iter().rposition(|&c| c == b')') {
+            Some(end) if args.starts_with(b"nozen.") => &args[..end],
+            _ => {
+                self.response_len = 0;
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.frame(nozen.command(...))\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        // Only single-frame commands are run; anything else would take effect
+        // for real instead of being previewed
+        let entry = match find_command(COMMAND_TABLE, inner).filter(|entry| builds_frame(entry.prefix)) {
+            Some(entry) => entry,
+            None => {
+                self.response_len = 0;
+                write_str(&mut self.response_buffer[..], b"[ERROR] Not an FPGA command\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let result = self.rolled_back(|p| (entry.handler)(p, inner, descriptor_cache));
+        
+        self.response_len = 0;
+        let cmd = match result {
+            CommandType::FpgaCommand(cmd) => cmd,
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Not an FPGA command\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let frame_len = cmd.uart_frame_len();
+        if HEADER.len() + frame_len * 2 + 1 > self.response_buffer.len() {
+            write_str(&mut self.response_buffer[..], b"[ERROR] Frame too large to show\n", &mut self.response_len);
+            return CommandType::Response;
+        }
+        
+        write_str(&mut self.response_buffer[..], HEADER, &mut self.response_len);
+        for &byte in &cmd.to_uart_frame()[..frame_len] {
+            self.response_buffer[self.response_len] = hex_digit(byte >> 4);
+            self.response_buffer[self.response_len + 1] = hex_digit(byte & 0x0F);
+            self.response_len += 2;
+        }
+        write_str(&mut self.response_buffer[..], b"\n", &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle help command
+    /// Format: nozen.help or nozen.help(page), pages numbered from 1
+    fn handle_help(&mut self, line: &[u8]) -> CommandType {
+        use core::fmt::Write;
+
+        let page = line.iter().position(|&c| c == b'(')
+            .and_then(|p| parse_u8_from_slice(&line[p + 1..]))
+            .unwrap_or(1)
+            .max(1);
+
+        // Split the command list into pages that fit the response buffer
+        // once the header and footer lines are added
+        let budget = self.response_buffer.len() - HELP_OVERHEAD;
+        let mut page_of = [0u8; COMMAND_TABLE.len()];
+        let mut pages = 1u8;
+        let mut used = 0;
+        for (i, entry) in COMMAND_TABLE.iter().enumerate() {
+            let len = command_name(entry.prefix).len() + 1;
+            if used + len > budget {
+                pages += 1;
+                used = 0;
+            }
+            used += len;
+            page_of[i] = pages;
+        }
+
+        self.response_len = 0;
+        if page > pages {
+            write_str(&mut self.response_buffer[..], b"[ERROR] No such help page\n", &mut self.response_len);
+            return CommandType::Response;
+        }
+
+        let mut msg = heapless::String::<32>::new();
+        let _ = writeln!(msg, "Commands ({}/{}):", page, pages);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+
+        for (i, entry) in COMMAND_TABLE.iter().enumerate() {
+            if page_of[i] == page {
+                write_str(&mut self.response_buffer[..], command_name(entry.prefix), &mut self.response_len);
+                write_str(&mut self.response_buffer[..], b"\n", &mut self.response_len);
+            }
+        }
+
+        if page < pages {
+            msg.clear();
+            let _ = writeln!(msg, "More: nozen.help({})", page + 1);
+            write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        }
+
+        CommandType::Response
+    }
+
+    /// Handle FPGA-forwarded descriptor
+    /// Format: [DESC:addr:iface]{hex_data}
+    /// This is automatically sent by FPGA when it detects GET_DESCRIPTOR for HID Report
+    fn handle_fpga_descriptor(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        let (addr, iface, hex_data) = match parse_forwarded(line, b"[DESC:") {
+            Some(parts) => parts,
+            None => return CommandType::NoOp,
+        };
+        let mut descriptor_bytes = [0u8; 1024];
+        let mut desc_len = 0;
+        
+        let mut i = 0;
+        while i < hex_data.len() && desc_len < 1024 {
+            // Skip whitespace/commas
+            while i < hex_data.len() && (hex_data[i] == b' ' || hex_data[i] == b',') {
+                i += 1;
+            }
+            
+            if i + 1 < hex_data.len() {
+                let high = hex_to_nibble(hex_data[i]);
+                let low = hex_to_nibble(hex_data[i + 1]);
+                
+                if high.is_some() && low.is_some() {
+                    descriptor_bytes[desc_len] = (high.unwrap() << 4) | low.unwrap();
+                    desc_len += 1;
+                }
+                i += 2;
+            } else {
+                break;
+            }
+        }
+        
+        // Auto-parse and cache
+        match descriptor_cache.add(addr, iface, &descriptor_bytes[..desc_len]) {
+            Ok(()) => {
+                // Get the cached descriptor
+                let desc = descriptor_cache.get(addr, iface).unwrap();
+                
+                // Log successful auto-parse
+                self.response_len = 0;
+                let mut msg = heapless::String::<128>::new();
+                let _ = write!(msg, "[AUTO] HID descriptor: dev={} if={} ", addr, iface);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+                
+                if desc.is_keyboard {
+                    write_str(&mut self.response_buffer[..], b"[Keyboard] ", &mut self.response_len);
+                }
+                if desc.is_mouse {
+                    write_str(&mut self.response_buffer[..], b"[Mouse] ", &mut self.response_len);
+                }
+                if desc.is_gamepad {
+                    write_str(&mut self.response_buffer[..], b"[Gamepad] ", &mut self.response_len);
+                }
+                
+                let _ = writeln!(msg, "{}B", desc_len);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+                
+                CommandType::Response
+            }
+            Err(ParseError::CacheFull) => {
+                self.response_len = 0;
+                let mut msg = heapless::String::<128>::new();
+                let _ = writeln!(msg, "[WARN] Descriptor cache full, all entries pinned: dev={} if={} not cached, unpin one with nozen.descriptor.unpin", addr, iface);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+                CommandType::Response
+            }
+            Err(ParseError::TooShort(len)) => {
+                self.response_len = 0;
+                let mut msg = heapless::String::<128>::new();
+                let _ = writeln!(msg, "[WARN] Descriptor too short ({}B), not cached: dev={} if={}", len, addr, iface);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+                CommandType::Response
+            }
+            Err(_) => {
+                // Parsing failed - still log it
+                self.response_len = 0;
+                let mut msg = heapless::String::<128>::new();
+                let _ = writeln!(msg, "[WARN] Failed to parse descriptor: dev={} if={}", addr, iface);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+                CommandType::Response
+            }
+        }
+    }
+    
+    /// Handle FPGA-forwarded input report
+    /// Format: [RPT:addr:iface]{hex_data}
+    /// Keyboard reports are decoded with the device's cached descriptor and
+    /// shown as key names, e.g. "[Keys] dev=1 if=0 Ctrl+A"; other reports
+    /// and devices without a cached keyboard descriptor are ignored.
+    fn handle_fpga_report(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        let mut report = [0u8; 64];
+        let decoded = parse_forwarded(line, b"[RPT:").and_then(|(addr, iface, hex)| {
+            let len = decode_hex(hex, &mut report)?;
+            let keys = descriptor_cache.get(addr, iface)?.decode_keys(&report[..len])?;
+            Some((addr, iface, keys))
+        });
+        let (addr, iface, (modifiers, keys)) = match decoded {
+            Some(decoded) => decoded,
+            None => return CommandType::NoOp,
+        };
+        
+        let mut msg = heapless::String::<128>::new();
+        let _ = write!(msg, "[Keys] dev={} if={} ", addr, iface);
+        write_key_names(&mut msg, modifiers, &keys);
+        let _ = msg.push('\n');
+        self.response_len = 0;
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        CommandType::Response
+    }
+    
+    /// Handle descriptor.add command - DEPRECATED, use FPGA auto-forward instead
+    /// Kept for manual testing only
+    #[allow(dead_code)]
+    fn handle_descriptor_add(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        // Parse address and interface
+        let mut idx = b"nozen.descriptor.add(".len();
+        
+        // Parse address
+        let addr = match parse_u8_from_slice(&line[idx..]) {
+            Some(v) => v,
+            None => {
+                self.response_len = 0;
+                write_str(&mut self.response_buffer[..], b"[ERROR] Invalid address\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        // Skip to comma
+        while idx < line.len() && line[idx] != b',' {
+            idx += 1;
+        }
+        idx += 1;
+        
+        // Parse interface
+        let iface = match parse_u8_from_slice(&line[idx..]) {
+            Some(v) => v,
+            None => {
+                self.response_len = 0;
+                write_str(&mut self.response_buffer[..], b"[ERROR] Invalid interface\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        // Find hex data in braces
+        while idx < line.len() && line[idx] != b'{' {
+            idx += 1;
+        }
+        idx += 1;
+        
+        let start = idx;
+        while idx < line.len() && line[idx] != b'}' {
+            idx += 1;
+        }
+        
+        // Parse hex data
+        let hex_data = &line[start..idx];
+        let mut descriptor_bytes = [0u8; 1024];
+        let mut desc_len = 0;
+        
+        let mut i = 0;
+        while i < hex_data.len() && desc_len < 1024 {
+            // Skip whitespace
+            while i < hex_data.len() && (hex_data[i] == b' ' || hex_data[i] == b',') {
+                i += 1;
+            }
+            
+            if i + 1 < hex_data.len() {
+                let high = hex_to_nibble(hex_data[i]);
+                let low = hex_to_nibble(hex_data[i + 1]);
+                
+                if high.is_none() || low.is_none() {
+                    self.response_len = 0;
+                    write_str(&mut self.response_buffer[..], b"[ERROR] Invalid hex data\n", &mut self.response_len);
+                    return CommandType::Response;
+                }
+                
+                descriptor_bytes[desc_len] = (high.unwrap() << 4) | low.unwrap();
+                desc_len += 1;
+                i += 2;
+            } else {
+                break;
+            }
+        }
+        
+        // Add to cache
+        match descriptor_cache.add(addr, iface, &descriptor_bytes[..desc_len]) {
+            Ok(()) => {
+                // Get the cached descriptor
+                let desc = descriptor_cache.get(addr, iface).unwrap();
+                
+                self.response_len = 0;
+                let mut msg = heapless::String::<128>::new();
+                let _ = write!(msg, "[OK] Descriptor cached: addr={} iface={} type=", addr, iface);
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+                
+                if desc.is_keyboard {
+                    write_str(&mut self.response_buffer[..], b"Keyboard ", &mut self.response_len);
+                }
+                if desc.is_mouse {
+                    write_str(&mut self.response_buffer[..], b"Mouse ", &mut self.response_len);
+                }
+                if desc.is_gamepad {
+                    write_str(&mut self.response_buffer[..], b"Gamepad ", &mut self.response_len);
+                }
+                
+                write_str(&mut self.response_buffer[..], b"\n", &mut self.response_len);
+                CommandType::Response
+            }
+            Err(_) => {
+                self.response_len = 0;
+                write_str(&mut self.response_buffer[..], b"[ERROR] Failed to parse descriptor\n", &mut self.response_len);
+                CommandType::Response
+            }
+        }
+    }
+    
+    /// Handle descriptor.get command
+    /// Format: nozen.descriptor.get(addr,iface)
+    fn handle_descriptor_get(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        // Parse address and interface
+        let mut idx = b"nozen.descriptor.get(".len();
+        
+        let addr = match parse_u8_from_slice(&line[idx..]) {
+            Some(v) => v,
+            None => {
+                self.response_len = 0;
+                write_str(&mut self.response_buffer[..], b"[ERROR] Invalid address\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        while idx < line.len() && line[idx] != b',' {
+            idx += 1;
+        }
+        idx += 1;
+        
+        let iface = match parse_u8_from_slice(&line[idx..]) {
+            Some(v) => v,
+            None => {
+                self.response_len = 0;
+                write_str(&mut self.response_buffer[..], b"[ERROR] Invalid interface\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        // Get from cache
         if let Some(desc) = descriptor_cache.get(addr, iface) {
             self.response_len = 0;
             let mut msg = heapless::String::<128>::new();
-            let _ = write!(msg, "[Descriptor] addr={} iface={}\n", addr, iface);
+            let _ = writeln!(msg, "[Descriptor] addr={} iface={}", addr, iface);
             write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
             
+            msg.clear();
             let _ = write!(msg, "  Type: ");
             if desc.is_keyboard { let _ = write!(msg, "Keyboard "); }
             if desc.is_mouse { let _ = write!(msg, "Mouse "); }
             if desc.is_gamepad { let _ = write!(msg, "Gamepad "); }
-            let _ = write!(msg, "\n");
+            if desc.is_composite { let _ = write!(msg, "(Composite) "); }
+            let _ = writeln!(msg);
             write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
             
-            let _ = write!(msg, "  Fields: {}\n", desc.fields.len());
+            msg.clear();
+            let _ = writeln!(msg, "  Fields: {}{}", desc.fields.len(),
+                           if desc.truncated { " (truncated, more declared)" } else { "" });
             write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
             
             CommandType::Response
         } else {
-            self.response_len = 0;
-            write_str(&mut self.response_buffer[..], b"[ERROR] Descriptor not found\n", &mut self.response_len);
-            CommandType::Response
+            self.response_len = 0;
+            write_str(&mut self.response_buffer[..], b"[ERROR] Descriptor not found\n", &mut self.response_len);
+            CommandType::Response
+        }
+    }
+    
+    /// Handle reportids command
+    /// Format: nozen.reportids or nozen.reportids(page), pages numbered from 1
+    /// Lists every (address, interface, report ID) in the cache with its input size
+    fn handle_reportids(&mut self, line: &[u8], descriptor_cache: &DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+
+        let page = line.iter().position(|&c| c == b'(')
+            .and_then(|p| parse_u8_from_slice(&line[p + 1..]))
+            .unwrap_or(1)
+            .max(1) as usize;
+
+        let rows = || descriptor_cache.iter().flat_map(|entry| {
+            entry.descriptor.input_report_sizes.iter()
+                .map(move |&(id, size)| (entry.device_address, entry.interface_num, id, size))
+        });
+        let total = rows().count();
+        let pages = total.div_ceil(REPORTIDS_PER_PAGE).max(1);
+
+        self.response_len = 0;
+        if page > pages {
+            write_str(&mut self.response_buffer[..], b"[ERROR] No such report ID page\n", &mut self.response_len);
+            return CommandType::Response;
+        }
+
+        let mut msg = heapless::String::<48>::new();
+        let _ = writeln!(msg, "Report IDs ({}/{}, {} total):", page, pages, total);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+
+        for (addr, iface, id, size) in rows().skip((page - 1) * REPORTIDS_PER_PAGE).take(REPORTIDS_PER_PAGE) {
+            msg.clear();
+            let _ = writeln!(msg, "{}:{} id={} in={}", addr, iface, id, size);
+            write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        }
+
+        if page < pages {
+            msg.clear();
+            let _ = writeln!(msg, "More: nozen.reportids({})", page + 1);
+            write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        }
+
+        CommandType::Response
+    }
+
+    /// Handle descriptor.export command
+    /// Emits the whole cache as one hex blob accepted by descriptor.import
+    fn handle_descriptor_export(&mut self, descriptor_cache: &DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        const HEADER: &[u8] = b"[Export] ";
+        let mut blob = [0u8; DESCRIPTOR_EXPORT_MAX];
+        
+        self.response_len = 0;
+        match descriptor_cache.export(&mut blob) {
+            Some(len) => {
+                write_str(&mut self.response_buffer[..], HEADER, &mut self.response_len);
+                for &byte in &blob[..len] {
+                    write_str(&mut self.response_buffer[..], &[hex_digit(byte >> 4), hex_digit(byte & 0x0F)], &mut self.response_len);
+                }
+                write_str(&mut self.response_buffer[..], b"\n", &mut self.response_len);
+            }
+            _ => {
+                let mut msg = heapless::String::<80>::new();
+                let _ = writeln!(msg, "[ERROR] Cache too large to export (max {} bytes)", blob.len());
+                write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+            }
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle descriptor.import command
+    /// Format: nozen.descriptor.import{hex blob from descriptor.export}
+    fn handle_descriptor_import(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let hex = &line[b"nozen.descriptor.import{".len()..];
+        let hex = match hex.iter().position(|&c| c == b'}') {
+            Some(end) => &hex[..end],
+            None => &[][..],
+        };
+        
+        let mut blob = [0u8; 128];
+        let decoded = decode_hex(hex, &mut blob).filter(|&len| len > 0);
+        
+        let mut msg = heapless::String::<64>::new();
+        match decoded.map(|len| descriptor_cache.import(&blob[..len])) {
+            Some(Ok(count)) => {
+                let _ = writeln!(msg, "[OK] Imported {} descriptors", count);
+            }
+            Some(Err(ParseError::CacheFull)) => {
+                let _ = writeln!(msg, "[ERROR] Import failed: cache full, all entries pinned");
+            }
+            Some(Err(e)) => {
+                let _ = writeln!(msg, "[ERROR] Import failed: {:?}", e);
+            }
+            None => {
+                let _ = writeln!(msg, "[ERROR] Invalid import blob");
+            }
+        }
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle descriptor.pin / descriptor.unpin commands
+    /// Format: nozen.descriptor.pin(addr,iface); pinned entries are never evicted
+    fn handle_descriptor_pin(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache, pin: bool) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let prefix: &[u8] = if pin { b"nozen.descriptor.pin(" } else { b"nozen.descriptor.unpin(" };
+        let (addr, iface) = match parse_addr_iface(line, prefix.len()) {
+            Some(v) => v,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Invalid address/interface\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let found = if pin { descriptor_cache.pin(addr, iface) } else { descriptor_cache.unpin(addr, iface) };
+        if !found {
+            write_str(&mut self.response_buffer[..], b"[ERROR] Descriptor not found\n", &mut self.response_len);
+            return CommandType::Response;
+        }
+        
+        let mut msg = heapless::String::<64>::new();
+        let _ = writeln!(msg, "[OK] {} addr={} iface={}", if pin { "Pinned" } else { "Unpinned" }, addr, iface);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle descriptor.sens command
+    /// Format: nozen.descriptor.sens(addr,iface,percent), 1-1000
+    /// Scales nozen.move deltas while that device is the target, on top of nozen.sensitivity
+    fn handle_descriptor_sens(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.descriptor.sens(".len()..];
+        let args = &args[..args.iter().position(|&c| c == b')').unwrap_or(args.len())];
+        let mut parts = args.split(|&c| c == b',');
+        let addr = parts.next().and_then(parse_u8_from_slice);
+        let iface = parts.next().and_then(parse_u8_from_slice);
+        let percent = parts.next().and_then(parse_int);
+        let (addr, iface, percent) = match (addr, iface, percent, parts.next()) {
+            (Some(addr), Some(iface), Some(percent), None) if (1..=1000).contains(&percent) => (addr, iface, percent as u16),
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.descriptor.sens(addr,iface,1-1000)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        if !descriptor_cache.set_sensitivity(addr, iface, percent) {
+            write_str(&mut self.response_buffer[..], b"[ERROR] Descriptor not found\n", &mut self.response_len);
+            return CommandType::Response;
+        }
+        
+        let mut msg = heapless::String::<64>::new();
+        let _ = writeln!(msg, "[OK] Sensitivity addr={} iface={}: {}%", addr, iface, percent);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle descriptor.validate command
+    /// Format: nozen.descriptor.validate(addr,iface)
+    /// Re-parses the cached raw bytes with the strict parser
+    fn handle_descriptor_validate(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let (addr, iface) = match parse_addr_iface(line, b"nozen.descriptor.validate(".len()) {
+            Some(v) => v,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.descriptor.validate(addr,iface)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        let raw = match descriptor_cache.raw(addr, iface) {
+            Some(raw) => raw,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Descriptor not found\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let mut msg = heapless::String::<96>::new();
+        match DescriptorParser::new_strict().parse(raw) {
+            Ok(()) => {
+                let _ = writeln!(msg, "[OK] addr={} iface={} passes strict parse", addr, iface);
+            }
+            Err(e) => {
+                let _ = writeln!(msg, "[ERROR] addr={} iface={} strict parse failed: {:?}", addr, iface, e);
+            }
+        }
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle descriptor.expect command
+    /// Format: nozen.descriptor.expect(addr,iface,class)
+    /// class is mouse, keyboard, gamepad, consumer or digitizer
+    fn handle_descriptor_expect(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.descriptor.expect(".len()..];
+        let args = &args[..args.iter().position(|&c| c == b')').unwrap_or(args.len())];
+        let mut parts = args.split(|&c| c == b',');
+        let addr = parts.next().and_then(parse_u8_from_slice);
+        let iface = parts.next().and_then(parse_u8_from_slice);
+        let class = parts.next();
+        
+        let (addr, iface, class) = match (addr, iface, class, parts.next()) {
+            (Some(addr), Some(iface), Some(class), None) => (addr, iface, class),
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.descriptor.expect(addr,iface,class)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let desc = match descriptor_cache.get(addr, iface) {
+            Some(desc) => desc,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Descriptor not found\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let matches = match class {
+            b"mouse" => desc.is_mouse,
+            b"keyboard" => desc.is_keyboard,
+            b"gamepad" => desc.is_gamepad,
+            b"consumer" => desc.is_consumer,
+            b"digitizer" => desc.is_digitizer,
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Unknown class (mouse|keyboard|gamepad|consumer|digitizer)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let class = core::str::from_utf8(class).unwrap_or("???");
+        let mut msg = heapless::String::<128>::new();
+        if matches {
+            let _ = writeln!(msg, "[OK] addr={} iface={} is {}", addr, iface, class);
+        } else {
+            let _ = write!(msg, "[ERROR] addr={} iface={} is not {}, detected:", addr, iface, class);
+            for (flag, name) in [
+                (desc.is_mouse, " mouse"),
+                (desc.is_keyboard, " keyboard"),
+                (desc.is_gamepad, " gamepad"),
+                (desc.is_consumer, " consumer"),
+                (desc.is_digitizer, " digitizer"),
+            ] {
+                if flag {
+                    let _ = msg.push_str(name);
+                }
+            }
+            let _ = msg.push('\n');
+        }
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle descriptor.info command
+    /// Format: nozen.descriptor.info(addr,iface)
+    /// Raw length, parsed field count and detected classes on one line
+    fn handle_descriptor_info(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let (addr, iface) = match parse_addr_iface(line, b"nozen.descriptor.info(".len()) {
+            Some(target) => target,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.descriptor.info(addr,iface)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let raw_len = descriptor_cache.raw(addr, iface).map(|raw| raw.len());
+        let (raw_len, desc) = match (raw_len, descriptor_cache.get(addr, iface)) {
+            (Some(raw_len), Some(desc)) => (raw_len, desc),
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Descriptor not found\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let mut msg = heapless::String::<128>::new();
+        let _ = write!(msg, "[Info] addr={} iface={} bytes={} fields={} class=", addr, iface, raw_len, desc.fields.len());
+        let mut any = false;
+        for (flag, name) in [
+            (desc.is_mouse, "mouse"),
+            (desc.is_keyboard, "keyboard"),
+            (desc.is_gamepad, "gamepad"),
+            (desc.is_consumer, "consumer"),
+            (desc.is_digitizer, "digitizer"),
+        ] {
+            if flag {
+                let _ = msg.push_str(if any { "+" } else { "" });
+                let _ = msg.push_str(name);
+                any = true;
+            }
+        }
+        let _ = msg.push_str(if any { "" } else { "none" });
+        if desc.is_composite {
+            let _ = msg.push_str(" composite");
+        }
+        if desc.max_contacts() > 0 {
+            let _ = write!(msg, " contacts={}", desc.max_contacts());
+        }
+        let _ = msg.push('\n');
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle descriptor.csv command
+    /// Format: nozen.descriptor.csv(addr,iface) or nozen.descriptor.csv(addr,iface,page)
+    /// One row per parsed field; the header row starts page 1 and every page but
+    /// the last ends with a "More:" line naming the next one
+    fn handle_descriptor_csv(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.descriptor.csv(".len()..];
+        let args = &args[..args.iter().position(|&c| c == b')').unwrap_or(args.len())];
+        let mut parts = args.split(|&c| c == b',');
+        let addr = parts.next().and_then(parse_u8_from_slice);
+        let iface = parts.next().and_then(parse_u8_from_slice);
+        let page = parts.next().map(parse_u8_from_slice);
+        let (addr, iface, page) = match (addr, iface, page, parts.next()) {
+            (Some(addr), Some(iface), None, None) => (addr, iface, 1),
+            (Some(addr), Some(iface), Some(Some(page)), None) => (addr, iface, page.max(1)),
+            _ => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.descriptor.csv(addr,iface[,page])\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let desc = match descriptor_cache.get(addr, iface) {
+            Some(desc) => desc,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Descriptor not found\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        // Fields first, then the input padding runs with "padding" as their usage
+        let rows = desc.fields.len() + desc.padding.len();
+        let row = |i: usize| {
+            let mut row = heapless::String::<48>::new();
+            let Some(field) = desc.fields.get(i) else {
+                let (id, offset, size) = desc.padding[i - desc.fields.len()];
+                let _ = writeln!(row, "{},input,padding,,{},{},0,0", id, offset, size);
+                return row;
+            };
+            let report_type = match field.report_type {
+                ReportType::Input => "input",
+                ReportType::Output => "output",
+                ReportType::Feature => "feature",
+            };
+            let _ = writeln!(row, "{},{},0x{:02X},0x{:04X},{},{},{},{}",
+                field.report_id, report_type, u16::from(field.usage.page), field.usage.id,
+                field.bit_offset, field.bit_size, field.is_relative as u8, field.is_array as u8);
+            row
+        };
+        
+        // Split the rows into pages that fit the response buffer, leaving room
+        // for the footer unless everything fits on one page
+        let total = CSV_HEADER.len() + (0..rows).map(|i| row(i).len()).sum::<usize>();
+        let budget = if total <= self.response_buffer.len() {
+            self.response_buffer.len()
+        } else {
+            self.response_buffer.len() - CSV_FOOTER_LEN
+        };
+        let mut pages = 1u8;
+        let mut used = CSV_HEADER.len();
+        let mut page_of = [0u8; MAX_REPORT_ITEMS + MAX_PADDING_RUNS];
+        for (i, page_of) in page_of[..rows].iter_mut().enumerate() {
+            let len = row(i).len();
+            if used + len > budget {
+                pages += 1;
+                used = 0;
+            }
+            used += len;
+            *page_of = pages;
+        }
+        
+        if page > pages {
+            write_str(&mut self.response_buffer[..], b"[ERROR] No such CSV page\n", &mut self.response_len);
+            return CommandType::Response;
+        }
+        
+        if page == 1 {
+            write_str(&mut self.response_buffer[..], CSV_HEADER, &mut self.response_len);
+        }
+        for (i, _) in page_of[..rows].iter().enumerate().filter(|&(_, &p)| p == page) {
+            write_str(&mut self.response_buffer[..], row(i).as_bytes(), &mut self.response_len);
+        }
+        
+        if page < pages {
+            let mut msg = heapless::String::<48>::new();
+            let _ = writeln!(msg, "More: nozen.descriptor.csv({},{},{})", addr, iface, page + 1);
+            write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        }
+        
+        CommandType::Response
+    }
+    
+    /// Handle descriptor.axes command
+    /// Format: nozen.descriptor.axes(addr,iface)
+    /// Which pointer axes the cached descriptor declares: X, Y and Wheel on the
+    /// Generic Desktop page, Pan as Consumer AC Pan (0x0238)
+    fn handle_descriptor_axes(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let (addr, iface) = match parse_addr_iface(line, b"nozen.descriptor.axes(".len()) {
+            Some(parsed) => parsed,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.descriptor.axes(addr,iface)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let desc = match descriptor_cache.get(addr, iface) {
+            Some(desc) => desc,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Descriptor not found\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let mut msg = heapless::String::<96>::new();
+        let _ = write!(msg, "[Axes] addr={} iface={}", addr, iface);
+        for (name, page, id) in [
+            ("X", UsagePage::GenericDesktop, 0x30),
+            ("Y", UsagePage::GenericDesktop, 0x31),
+            ("Wheel", UsagePage::GenericDesktop, 0x38),
+            ("Pan", UsagePage::Consumer, 0x0238),
+        ] {
+            let _ = write!(msg, " {}:{}", name, if desc.has_usage(page, id) { "yes" } else { "no" });
+        }
+        let _ = msg.push('\n');
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle descriptor.bits command
+    /// Format: nozen.descriptor.bits(addr,iface,field), field numbered as in nozen.descriptor.csv
+    /// Where the field sits in its report as sent, report ID byte included:
+    /// the byte range, the bits within it and the mask over those bytes
+    fn handle_descriptor_bits(&mut self, line: &[u8], descriptor_cache: &mut DescriptorCache) -> CommandType {
+        use core::fmt::Write;
+        
+        self.response_len = 0;
+        let args = &line[b"nozen.descriptor.bits(".len()..];
+        let args = &args[..args.iter().position(|&c| c == b')').unwrap_or(args.len())];
+        let mut parts = args.split(|&c| c == b',');
+        let parsed = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(addr), Some(iface), Some(index), None) => {
+                parse_u8_from_slice(addr).zip(parse_u8_from_slice(iface)).zip(parse_u8_from_slice(index))
+            }
+            _ => None,
+        };
+        let ((addr, iface), index) = match parsed {
+            Some(parsed) => parsed,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Usage: nozen.descriptor.bits(addr,iface,field)\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let desc = match descriptor_cache.get(addr, iface) {
+            Some(desc) => desc,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] Descriptor not found\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        let field = match desc.fields.get(index as usize) {
+            Some(field) => field,
+            None => {
+                write_str(&mut self.response_buffer[..], b"[ERROR] No such field\n", &mut self.response_len);
+                return CommandType::Response;
+            }
+        };
+        
+        let id_bits = if desc.uses_report_ids() { 8 } else { 0 };
+        let start = desc.report_position(field) + id_bits;
+        let size = field.bit_size.max(1) as u16;
+        let (first, last) = (start / 8, (start + size - 1) / 8);
+        let low = start % 8;
+        let mask = ((1u64 << size.min(32)) - 1) << low;
+        
+        let mut msg = heapless::String::<96>::new();
+        let _ = write!(msg, "[Bits] field {}: ", index);
+        let _ = match first == last {
+            true => write!(msg, "byte {}", first),
+            false => write!(msg, "bytes {}-{}", first, last),
+        };
+        let _ = writeln!(msg, " bits {}-{} mask 0x{:0width$X}", low, low + size - 1, mask,
+                       width = 2 * (last - first + 1) as usize);
+        write_str(&mut self.response_buffer[..], msg.as_bytes(), &mut self.response_len);
+        
+        CommandType::Response
+    }
+    
+    /// Handle descriptor.stats command
+    fn handle_descriptor_stats(&mut self, descriptor_cache: &DescriptorCache) -> CommandType {
+        let stats = descriptor_cache.get_stats();
+        
+        self.response_len = 0;
+        let stats_str = stats.format();
+        write_str(&mut self.response_buffer[..], stats_str.as_bytes(), &mut self.response_len);
+        write_str(&mut self.response_buffer[..], b"\n", &mut self.response_len);
+        
+        CommandType::Response
+    }
+}
+
+/// Parse "addr,iface" decimal arguments that follow a command prefix
+fn parse_addr_iface(line: &[u8], args_start: usize) -> Option<(u8, u8)> {
+    let args = line.get(args_start..)?;
+    let comma = args.iter().position(|&c| c == b',')?;
+    let addr = parse_u8_from_slice(&args[..comma])?;
+    let iface = parse_u8_from_slice(&args[comma + 1..])?;
+    Some((addr, iface))
+}
+
+/// Parse two "a,b" decimal arguments, each 0-255, up to the closing ')'
+/// Unlike parse_addr_iface, empty, out-of-range or trailing text is rejected.
+fn parse_u8_pair(line: &[u8], args_start: usize) -> Option<(u8, u8)> {
+    let number = |text: &[u8]| -> Option<u8> {
+        let text = text.trim_ascii();
+        if text.is_empty() {
+            return None;
+        }
+        text.iter().try_fold(0u8, |value, &c| {
+            let digit = c.checked_sub(b'0').filter(|&d| d <= 9)?;
+            value.checked_mul(10)?.checked_add(digit)
+        })
+    };
+    
+    let args = line.get(args_start..)?;
+    let end = args.iter().position(|&c| c == b')')?;
+    let comma = args[..end].iter().position(|&c| c == b',')?;
+    Some((number(&args[..comma])?, number(&args[comma + 1..end])?))
+}
+
+/// Free-text argument up to and including its closing ')'
+/// Unquoted text runs to the first ')'. Double-quoted text may contain ')' and
+/// uses \" and \\ as escapes; the closing quote must be followed by ')'.
+/// Returns the text and the bytes consumed, or None if a quote or paren is unclosed.
+fn parse_text_arg(args: &[u8]) -> Option<(heapless::Vec<u8, 256>, usize)> {
+    let mut text = heapless::Vec::new();
+    let Some(quoted) = args.strip_prefix(b"\"") else {
+        let end = args.iter().position(|&c| c == b')')?;
+        let _ = text.extend_from_slice(&args[..end.min(text.capacity())]);
+        return Some((text, end + 1));
+    };
+    
+    let mut bytes = quoted.iter().enumerate();
+    while let Some((i, &c)) = bytes.next() {
+        let c = match c {
+            b'"' if quoted.get(i + 1) == Some(&b')') => return Some((text, i + 3)),
+            b'"' => return None,
+            b'\\' => *bytes.next()?.1,
+            _ => c,
+        };
+        let _ = text.push(c);
+    }
+    None
+}
+
+/// Parse "name,n1,n2,..." arguments up to ')', filling exactly `values.len()` integers
+fn parse_name_and_ints<'a>(line: &'a [u8], args_start: usize, values: &mut [i16]) -> Option<&'a [u8]> {
+    let args = line.get(args_start..)?;
+    let args = &args[..args.iter().position(|&c| c == b')')?];
+    
+    let mut parts = args.split(|&c| c == b',');
+    let name = parts.next().filter(|name| !name.is_empty())?;
+    for value in values.iter_mut() {
+        let part = parts.next()?;
+        if !part.iter().any(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        *value = parse_int(part)?;
+    }
+    
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(name)
+}
+
+/// Parse "c:ms,c:ms,..." where each c is a single character (',' and ':' included)
+fn parse_typeseq(args: &[u8]) -> Option<heapless::Vec<(u8, u16), 64>> {
+    let mut keys = heapless::Vec::new();
+    let mut rest = args;
+    
+    while !rest.is_empty() {
+        if rest.len() < 3 || rest[1] != b':' {
+            return None;
+        }
+        let c = rest[0];
+        let digits = rest[2..].iter().take_while(|d| d.is_ascii_digit()).count();
+        // Up to 9999ms keeps parse_int clear of i16 overflow
+        if !(1..=4).contains(&digits) {
+            return None;
+        }
+        let dwell = parse_int(&rest[2..2 + digits]).filter(|&ms| ms > 0)?;
+        keys.push((c, dwell as u16)).ok()?;
+        
+        rest = &rest[2 + digits..];
+        if let Some((&b',', tail)) = rest.split_first() {
+            if tail.is_empty() {
+                return None;
+            }
+            rest = tail;
+        } else if !rest.is_empty() {
+            return None;
+        }
+    }
+    
+    if keys.is_empty() { None } else { Some(keys) }
+}
+
+/// Reports needed so neither axis of (dx, dy) moves more than `max` in one
+fn speed_parts(dx: i16, dy: i16, max: i16) -> usize {
+    let max = max.max(1) as usize;
+    let largest = dx.unsigned_abs().max(dy.unsigned_abs()) as usize;
+    largest.div_ceil(max).max(1)
+}
+
+/// The `index`th of `parts` near-equal pieces of `value`; the pieces sum to `value`
+fn split_part(value: i16, index: usize, parts: usize) -> i16 {
+    let (value, index, parts) = (value as i32, index as i32, parts as i32);
+    (value * (index + 1) / parts - value * index / parts) as i16
+}
+
+/// Map a screen coordinate (0..extent) onto a descriptor's logical range
+fn scale_to_logical(pos: i16, extent: u16, logical_min: i32, logical_max: i32) -> u16 {
+    let span = extent.max(2) as i64 - 1;
+    let pos = (pos as i64).clamp(0, span);
+    let value = logical_min as i64 + pos * (logical_max as i64 - logical_min as i64) / span;
+    // Truncation keeps the two's-complement encoding for signed ranges
+    value as u16
+}
+
+/// Parse u8 from byte slice
+fn parse_u8_from_slice(data: &[u8]) -> Option<u8> {
+    let mut value = 0u8;
+    let mut idx = 0;
+    
+    while idx < data.len() && data[idx] >= b'0' && data[idx] <= b'9' {
+        value = value.wrapping_mul(10).wrapping_add(data[idx] - b'0');
+        idx += 1;
+    }
+    
+    if idx > 0 {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// Convert hex character to nibble
+fn hex_to_nibble(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Movement reported by the FPGA: "[DELTA:dx,dy]"
+fn parse_delta_line(line: &[u8]) -> Option<(i16, i16)> {
+    let args = line.strip_prefix(b"[DELTA:")?.strip_suffix(b"]")?;
+    let comma = args.iter().position(|&c| c == b',')?;
+    Some((parse_int(&args[..comma])?, parse_int(&args[comma + 1..])?))
+}
+
+/// Mouse button mask for a click button name
+fn button_from_name(name: &[u8]) -> Option<u8> {
+    match name {
+        b"left" => Some(0x01),
+        b"right" => Some(0x02),
+        b"middle" => Some(0x04),
+        _ => None,
+    }
+}
+
+/// Name of a mask produced by button_from_name
+fn button_name(mask: u8) -> &'static str {
+    match mask {
+        0x02 => "right",
+        0x04 => "middle",
+        _ => "left",
+    }
+}
+
+/// "on"/"off" for boolean settings in responses
+fn on_off(flag: bool) -> &'static str {
+    if flag { "on" } else { "off" }
+}
+
+/// Parse a 16-bit hex value of 1-4 digits, with or without a "0x" prefix
+fn parse_hex_u16(text: &[u8]) -> Option<u16> {
+    let digits = text.strip_prefix(b"0x").or_else(|| text.strip_prefix(b"0X")).unwrap_or(text);
+    if digits.is_empty() || digits.len() > 4 {
+        return None;
+    }
+    digits.iter().try_fold(0u16, |value, &c| Some((value << 4) | hex_to_nibble(c)? as u16))
+}
+
+/// Write held modifiers and keys joined by '+', e.g. "Ctrl+Shift+A"
+/// Letters are shown in capitals; "(none)" when nothing is held.
+fn write_key_names<W: core::fmt::Write>(out: &mut W, modifiers: u8, keys: &[u8]) {
+    const MODIFIERS: [&str; 8] = ["Ctrl", "Shift", "Alt", "Gui", "RCtrl", "RShift", "RAlt", "RGui"];
+    
+    let mut sep = "";
+    for (bit, name) in MODIFIERS.iter().enumerate() {
+        if modifiers & (1 << bit) != 0 {
+            let _ = write!(out, "{}{}", sep, name);
+            sep = "+";
+        }
+    }
+    for &key in keys {
+        let _ = match (key_name(key), scancode_to_ascii(key)) {
+            (Some(name), _) => write!(out, "{}{}", sep, name),
+            (None, Some(c)) => write!(out, "{}{}", sep, c.to_ascii_uppercase() as char),
+            (None, None) => write!(out, "{}0x{:02X}", sep, key),
+        };
+        sep = "+";
+    }
+    if sep.is_empty() {
+        let _ = out.write_str("(none)");
+    }
+}
+
+/// Split an FPGA-forwarded "[TAG:AA:I]{hex}" line into (addr, iface, hex)
+/// The interface is one or two hex digits; current gateware sends one.
+fn parse_forwarded<'a>(line: &'a [u8], tag: &[u8]) -> Option<(u8, u8, &'a [u8])> {
+    let rest = line.strip_prefix(tag)?;
+    let addr = parse_hex_byte(*rest.first()?, *rest.get(1)?)?;
+    if rest.get(2) != Some(&b':') {
+        return None;
+    }
+    let (iface, rest) = match (rest.get(3), rest.get(4)) {
+        (Some(&high), Some(&low)) if low != b']' => (parse_hex_byte(high, low)?, &rest[5..]),
+        (Some(&digit), _) => (hex_to_nibble(digit)?, &rest[4..]),
+        _ => return None,
+    };
+    // Header must be closed by ']', data follows in braces
+    let rest = rest.strip_prefix(b"]")?;
+    let start = rest.iter().position(|&c| c == b'{')? + 1;
+    let end = rest[start..].iter().position(|&c| c == b'}').map_or(rest.len(), |end| start + end);
+    Some((addr, iface, &rest[start..end]))
+}
+
+/// Combine two hex characters into a byte
+fn parse_hex_byte(high: u8, low: u8) -> Option<u8> {
+    Some((hex_to_nibble(high)? << 4) | hex_to_nibble(low)?)
+}
+
+/// Decode an even-length hex string into `out`, returning the byte count
+fn decode_hex(hex: &[u8], out: &mut [u8]) -> Option<usize> {
+    if !hex.len().is_multiple_of(2) || hex.len() / 2 > out.len() {
+        return None;
+    }
+    for (i, pair) in hex.chunks_exact(2).enumerate() {
+        out[i] = parse_hex_byte(pair[0], pair[1])?;
+    }
+    Some(hex.len() / 2)
+}
+
+/// Write string to buffer
+fn write_str(buf: &mut [u8], data: &[u8], len: &mut usize) {
+    let copy_len = data.len().min(buf.len() - *len);
+    buf[*len..*len + copy_len].copy_from_slice(&data[..copy_len]);
+    *len += copy_len;
+}
+
+
+fn hex_digit(nibble: u8) -> u8 {
+    match nibble & 0x0F {
+        0..=9 => b'0' + nibble,
+        10..=15 => b'A' + (nibble - 10),
+        _ => b'?',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_to_uart_frame_basic() {
+        let cmd = Command {
+            code: 0x11,
+            payload: [0x01, 0x02, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            length: 3,
+        };
+        
+        let frame = cmd.to_uart_frame();
+        
+        // Check that frame starts with [CMD:
+        assert_eq!(&frame[0..5], b"[CMD:");
+        
+        // Check command code is 11 (0x11)
+        assert_eq!(frame[5], b'1');
+        assert_eq!(frame[6], b'1');
+    }
+
+    fn mouse_command(length: usize) -> Command {
+        let mut payload = [0u8; 128];
+        payload[..5].copy_from_slice(&[0x00, 0x0A, 0xFB, 0x00, 0x00]);
+        Command { code: 0x11, payload, length }
+    }
+
+    #[test]
+    fn test_uart_frame_checksum_covers_length() {
+        let cmd = mouse_command(5);
+        // 0x11 + 0x05 + 0x00 + 0x0A + 0xFB = 0x11B
+        assert_eq!(cmd.checksum(), 0x1B);
+
+        let frame = cmd.to_uart_frame();
+        assert_eq!(&frame[25..36], b" [CKSUM:1B]");
+        assert!(verify_uart_frame(&frame));
+    }
+
+    #[test]
+    fn test_uart_frame_len_matches_frame() {
+        let frame = mouse_command(5).to_uart_frame();
+        let len = mouse_command(5).uart_frame_len();
+        assert_eq!(&frame[len - 2..len], b"]\n");
+        assert!(frame[len..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_max_length_command_frame_is_well_formed() {
+        let data = [0xA5u8; MAX_PAYLOAD];
+        let cmd = Command::new(0x10, &data);
+        assert_eq!(cmd.length, MAX_PAYLOAD);
+
+        let frame = cmd.to_uart_frame();
+        let len = cmd.uart_frame_len();
+        assert!(len <= UART_FRAME_SIZE);
+        assert_eq!(&frame[..20], b"[CMD:10] [LEN:0080] ");
+        assert_eq!(&frame[20..20 + MAX_PAYLOAD], &data[..]);
+        assert_eq!(&frame[len - 2..len], b"]\n");
+        assert!(verify_uart_frame(&frame[..len]));
+    }
+
+    #[test]
+    fn test_oversized_length_is_clamped() {
+        // Built by hand past the constructor
+        let cmd = Command { code: 0x11, payload: [1; MAX_PAYLOAD], length: 300 };
+        assert_eq!(cmd.payload_len(), MAX_PAYLOAD);
+        assert_eq!(cmd.uart_frame_len(), 20 + MAX_PAYLOAD + 12);
+
+        // The frame is built from the clamped length, trailer intact
+        let frame = cmd.to_uart_frame();
+        let len = cmd.uart_frame_len();
+        assert_eq!(&frame[..20], b"[CMD:11] [LEN:0080] ");
+        assert_eq!(&frame[len - 12..len - 4], b" [CKSUM:");
+        assert_eq!(&frame[len - 2..len], b"]\n");
+        assert!(verify_uart_frame(&frame[..len]));
+    }
+
+    #[test]
+    fn test_uart_frame_tampered_length_fails() {
+        let good = mouse_command(5).to_uart_frame();
+
+        // Same payload sum with the trailing zero dropped: only the length
+        // coverage can catch it
+        let mut tampered = mouse_command(4).to_uart_frame();
+        tampered[32..34].copy_from_slice(&good[33..35]);
+        assert!(!verify_uart_frame(&tampered));
+
+        // Flipped LEN digit on an otherwise intact frame
+        let mut tampered = good;
+        tampered[17] = b'4';
+        assert!(!verify_uart_frame(&tampered));
+    }
+
+    #[test]
+    fn test_parse_int_positive() {
+        assert_eq!(parse_int(b"42"), Some(42));
+        assert_eq!(parse_int(b"0"), Some(0));
+        assert_eq!(parse_int(b"1234"), Some(1234));
+    }
+
+    #[test]
+    fn test_parse_int_negative() {
+        assert_eq!(parse_int(b"-42"), Some(-42));
+        assert_eq!(parse_int(b"-1"), Some(-1));
+        assert_eq!(parse_int(b"-999"), Some(-999));
+    }
+
+    #[test]
+    fn test_parse_int_with_whitespace() {
+        assert_eq!(parse_int(b"  42"), Some(42));
+        assert_eq!(parse_int(b"   -42"), Some(-42));
+    }
+
+    #[test]
+    fn test_format_i16_positive() {
+        let mut buf = [0u8; 10];
+        let len = format_i16(123, &mut buf);
+        assert_eq!(&buf[..len], b"123");
+        
+        let len = format_i16(0, &mut buf);
+        assert_eq!(&buf[..len], b"0");
+    }
+
+    #[test]
+    fn test_format_i16_negative() {
+        let mut buf = [0u8; 10];
+        let len = format_i16(-123, &mut buf);
+        assert_eq!(&buf[..len], b"-123");
+        
+        let len = format_i16(-1, &mut buf);
+        assert_eq!(&buf[..len], b"-1");
+    }
+
+    #[test]
+    fn test_command_processor_new() {
+        let processor = CommandProcessor::new();
+        assert_eq!(processor.index, 0);
+        assert_eq!(processor.response_len, 0);
+    }
+
+    #[test]
+    fn test_parse_mouse_move() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        let cmd = processor.parse(b"nozen.move(10,20)\n", &mut cache);
+        
+        match cmd {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.code, 0x11); // INJECT_MOUSE
+                assert_eq!(c.length, 5);
+                assert_eq!(c.payload[0], 0x00); // no buttons
+                assert_eq!(c.payload[1], 10); // x
+                assert_eq!(c.payload[2], 20); // y
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+        
+        // Check that mouse state was updated
+        assert_eq!(processor.mouse_state.position(), (10, 20));
+    }
+
+    #[test]
+    fn test_parse_mouse_move_negative() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        let cmd = processor.parse(b"nozen.move(-5,-10)\n", &mut cache);
+        
+        match cmd {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.code, 0x11);
+                assert_eq!(c.payload[1] as i8, -5);
+                assert_eq!(c.payload[2] as i8, -10);
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+        
+        assert_eq!(processor.mouse_state.position(), (-5, -10));
+    }
+
+    #[test]
+    fn test_parse_mouse_moveto() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        // Set initial position
+        processor.mouse_state.set_position(10, 20);
+        
+        // Move to absolute position
+        let cmd = processor.parse(b"nozen.moveto(50,100)\n", &mut cache);
+        
+        match cmd {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.code, 0x11);
+                // Should send delta: (50-10, 100-20) = (40, 80)
+                assert_eq!(c.payload[1], 40);
+                assert_eq!(c.payload[2], 80);
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+        
+        // State should be updated to new position
+        assert_eq!(processor.mouse_state.position(), (50, 100));
+    }
+
+    #[test]
+    fn test_moveto_without_tracking_leaves_state() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.parse(b"nozen.track(off)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Track: off\n");
+        for _ in 0..2 {
+            match processor.parse(b"nozen.moveto(100,100)\n", &mut cache) {
+                CommandType::FpgaCommand(c) => assert_eq!(&c.payload[1..3], &[100, 100]),
+                other => panic!("unexpected {:?}", other),
+            }
+            assert_eq!(processor.mouse_state.position(), (0, 0));
+        }
+
+        processor.parse(b"nozen.track(on)\n", &mut cache);
+        processor.parse(b"nozen.moveto(100,100)\n", &mut cache);
+        assert_eq!(processor.mouse_state.position(), (100, 100));
+        processor.parse(b"nozen.track(maybe)\n", &mut cache);
+        assert!(response_text(&processor).contains("Usage"));
+    }
+
+    #[test]
+    fn test_parse_left_click_press() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        let cmd = processor.parse(b"nozen.left(1)\n", &mut cache);
+        
+        match cmd {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.code, 0x11);
+                assert_eq!(c.payload[0], 0x01); // left button mask
+                assert_eq!(c.payload[1], 0); // no movement
+                assert_eq!(c.payload[2], 0);
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+    }
+
+    #[test]
+    fn test_parse_left_click_release() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        let cmd = processor.parse(b"nozen.left(0)\n", &mut cache);
+        
+        match cmd {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.payload[0], 0x00); // no buttons
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+    }
+
+    #[test]
+    fn test_click_uses_default_button() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.tick(100, 0);
+
+        match processor.parse(b"nozen.click()\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(c.payload[0], 0x01),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        processor.parse(b"nozen.button.default(right)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Default button: right\n");
+        match processor.parse(b"nozen.click()\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(c.payload[0], 0x02),
+            other => panic!("unexpected {:?}", other),
+        }
+        // An explicit button still wins
+        match processor.parse(b"nozen.click(middle)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(c.payload[0], 0x04),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Each click queues its release
+        processor.tick(100 + CLICK_HOLD_MS, 1);
+        let release = processor.next_due().unwrap();
+        assert_eq!(release.payload[0], 0x00);
+
+        processor.parse(b"nozen.button.default(side9)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+        assert_eq!(processor.default_button, 0x02);
+    }
+
+    #[test]
+    fn test_tap_holds_for_press_ms() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.tick(100, 0);
+
+        match processor.parse(b"nozen.tap(right,250)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(c.payload[0], 0x02),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        processor.tick(349, 1);
+        assert!(processor.next_due().is_none());
+        processor.tick(350, 2);
+        let release = processor.next_due().unwrap();
+        assert_eq!(release.payload[0], 0x00);
+
+        for (i, line) in [&b"nozen.tap(right,0)\n"[..], b"nozen.tap(side,10)\n", b"nozen.tap(left)\n"].into_iter().enumerate() {
+            processor.tick(1000 + i as u32 * ERROR_COALESCE_MS, 3);
+            assert_eq!(processor.parse(line, &mut cache), CommandType::Response);
+            assert!(response_text(&processor).starts_with("[ERROR] Usage: nozen.tap"));
+        }
+    }
+
+    #[test]
+    fn test_parse_right_click() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        let cmd = processor.parse(b"nozen.right(1)\n", &mut cache);
+        
+        match cmd {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.payload[0], 0x02); // right button mask
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+    }
+
+    #[test]
+    fn test_parse_middle_click() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        let cmd = processor.parse(b"nozen.middle(1)\n", &mut cache);
+        
+        match cmd {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.payload[0], 0x04); // middle button mask
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+    }
+
+    #[test]
+    fn test_parse_wheel() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        let cmd = processor.parse(b"nozen.wheel(5)\n", &mut cache);
+        
+        match cmd {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.code, 0x11);
+                assert_eq!(c.payload[0], 0); // no buttons
+                assert_eq!(c.payload[1], 0); // no x movement
+                assert_eq!(c.payload[2], 0); // no y movement
+                assert_eq!(c.payload[3], 5); // wheel
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+    }
+
+    #[test]
+    fn test_parse_wheel_negative() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        let cmd = processor.parse(b"nozen.wheel(-3)\n", &mut cache);
+        
+        match cmd {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.payload[3] as i8, -3);
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+    }
+
+    #[test]
+    fn test_wheel_step_splits_into_notches() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.tick(500, 0);
+
+        processor.parse(b"nozen.wheel.step(4)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Wheel step: 4\n");
+
+        match processor.parse(b"nozen.wheel(2)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(&c.payload[..5], &[0, 0, 0, 4, 0]),
+            _ => panic!("Expected FpgaCommand"),
+        }
+        assert_eq!(processor.pending.len(), 1);
+        processor.tick(500 + WHEEL_NOTCH_INTERVAL_MS, 1);
+        let second = processor.next_due().unwrap();
+        assert_eq!(second.code, 0x11);
+        assert_eq!(&second.payload[..5], &[0, 0, 0, 4, 0]);
+        assert!(processor.pending.is_empty());
+
+        // Negative amounts keep their sign per notch
+        match processor.parse(b"nozen.wheel(-3)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(c.payload[3] as i8, -4),
+            _ => panic!("Expected FpgaCommand"),
+        }
+        assert_eq!(processor.pending.len(), 2);
+    }
+
+    #[test]
+    fn test_queue_depth_reports_overflow() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.queue.depth\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Queue] 0/64 used, 0 rejected\n");
+
+        // 63 repeats and the release fill the queue exactly
+        processor.parse(b"nozen.key.hold(4,640,10)\n", &mut cache);
+        assert_eq!(processor.pending.free(), 0);
+        processor.parse(b"nozen.queue.depth\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Queue] 64/64 used, 0 rejected\n");
+
+        assert_eq!(processor.parse(b"nozen.click()\n", &mut cache), CommandType::Response);
+        assert_eq!(response_text(&processor), "[ERROR] Queue full\n");
+        processor.tick(ERROR_COALESCE_MS, 1);
+        processor.parse(b"nozen.tap(left,50)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Queue full\n");
+        processor.parse(b"nozen.queue.depth\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Queue] 64/64 used, 2 rejected\n");
+    }
+
+    #[test]
+    fn test_queue_list_counts_kinds() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.queue.list\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Queue] empty\n");
+
+        processor.parse(b"nozen.recoil.add(a){1,2,30,3,4,50,5,6,0}\n", &mut cache);
+        processor.parse(b"nozen.recoil.run(a)\n", &mut cache);
+        processor.parse(b"nozen.click()\n", &mut cache);
+        processor.parse(b"nozen.queue.list\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Queue] 4 pending: 3 moves, 1 release; last due in 80 ms\n");
+        assert_eq!(processor.pending.len(), 4);
+
+        processor.tick(10, 1);
+        processor.parse(b"nozen.queue.list\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Queue] 4 pending: 3 moves, 1 release; last due in 70 ms\n");
+    }
+
+    #[test]
+    fn test_wheel_step_default_and_bounds() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        assert_eq!(processor.wheel_step, 1);
+
+        // Step 1 keeps the single-report behavior
+        processor.parse(b"nozen.wheel(3)\n", &mut cache);
+        assert!(processor.pending.is_empty());
+
+        processor.parse(b"nozen.wheel.step(0)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+        // Past the window that would coalesce the identical error
+        processor.tick(ERROR_COALESCE_MS, 1);
+        processor.parse(b"nozen.wheel.step(128)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+        assert_eq!(processor.wheel_step, 1);
+
+        processor.parse(b"nozen.wheel.step(2)\n", &mut cache);
+        processor.parse(b"nozen.wheel(100)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Queue full\n");
+        assert!(processor.pending.is_empty());
+    }
+
+    #[test]
+    fn test_parse_getpos() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        processor.mouse_state.set_position(100, 200);
+        
+        let cmd = processor.parse(b"nozen.getpos\n", &mut cache);
+        
+        match cmd {
+            CommandType::Response => {
+                assert!(processor.response_len > 0);
+                let response = &processor.response_buffer[..processor.response_len];
+                // Should contain "km.pos(100,200)\n"
+                assert!(response.starts_with(b"km.pos("));
+            }
+            _ => panic!("Expected Response"),
+        }
+    }
+
+    #[test]
+    fn test_parse_restart() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        let cmd = processor.parse(b"nozen.restart\n", &mut cache);
+        
+        match cmd {
+            CommandType::Restart => {}
+            _ => panic!("Expected Restart"),
+        }
+    }
+
+    #[test]
+    fn test_parse_uart_flush() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        assert_eq!(processor.parse(b"nozen.uart.flush\n", &mut cache), CommandType::UartFlush);
+    }
+
+    #[test]
+    fn test_bench_count_and_result() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        assert_eq!(processor.parse(b"nozen.bench(500)\n", &mut cache), CommandType::Bench(500));
+        processor.finish_bench(500, 250);
+        assert_eq!(response_text(&processor), "[Bench] 500 reports in 250 ms (2000 reports/s)\n");
+        
+        // Held buttons stay down through the burst
+        processor.parse(b"nozen.mouse.buttons(1)\n", &mut cache);
+        let report = processor.bench_report();
+        assert_eq!(&report.payload[..report.length], &[1, 0, 0, 0, 0]);
+        
+        assert_eq!(processor.parse(b"nozen.bench(1001)\n", &mut cache), CommandType::Response);
+        assert_eq!(response_text(&processor), "[ERROR] Bench count must be 1-1000\n");
+        processor.tick(ERROR_COALESCE_MS, 1);
+        assert_eq!(processor.parse(b"nozen.bench(0)\n", &mut cache), CommandType::Response);
+        assert_eq!(response_text(&processor), "[ERROR] Bench count must be 1-1000\n");
+        
+        processor.parse(b"nozen.dryrun(1)\n", &mut cache);
+        assert_eq!(processor.parse(b"nozen.bench(10)\n", &mut cache), CommandType::Response);
+    }
+
+    #[test]
+    fn test_usb_reattach_keeps_state() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.recoil.add(ak47){1,2,30}\n", &mut cache);
+        processor.parse(&desc_line("[DESC:02:00]"), &mut cache);
+
+        assert_eq!(processor.parse(b"nozen.usb.reattach\n", &mut cache), CommandType::UsbReattach);
+        assert!(processor.recoil_manager.get_pattern("ak47").is_some());
+        assert!(cache.get(2, 0).is_some());
+    }
+
+    #[test]
+    fn test_uart_capture_window() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.tick(5000, 0);
+
+        processor.parse(b"nozen.uart.capture(100)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Capturing FPGA lines for 100 ms\n");
+
+        processor.tick(5010, 1);
+        processor.on_uart_line(b"[STATUS:inject]");
+        processor.tick(5099, 2);
+        processor.on_uart_line(b"[STATUS:idle]");
+        assert_eq!(processor.finish_capture(), None);
+
+        processor.tick(5100, 3);
+        processor.on_uart_line(b"[STATUS:after]");
+        assert_eq!(processor.finish_capture(), Some((2, 0)));
+        assert_eq!(processor.capture.trace(), b"+10ms [STATUS:inject]\n+99ms [STATUS:idle]\n");
+        assert_eq!(processor.finish_capture(), None);
+
+        processor.parse(b"nozen.uart.capture(0)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+    }
+
+    #[test]
+    fn test_uart_history_lists_last_lines() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.uart.history\n", &mut cache);
+        assert_eq!(response_text(&processor), "[History] 0 lines\n");
+
+        for i in 0..10 {
+            processor.on_uart_line(format!("[STATUS:{}]", i).as_bytes());
+        }
+        processor.parse(b"nozen.uart.history\n", &mut cache);
+        let expected: String = (2..10).map(|i| format!("[STATUS:{}]\n", i)).collect();
+        assert_eq!(response_text(&processor), format!("[History] 8 lines\n{}", expected));
+    }
+
+    #[test]
+    fn test_serial_set_and_show() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.serial\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Serial] HID-INJ-001\n");
+
+        processor.parse(b"nozen.serial(set,ABC123)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Serial: ABC123 (applies after restart)\n");
+        assert!(processor.config_dirty);
+        processor.parse(b"nozen.serial\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Serial] ABC123\n");
+
+        processor.config_dirty = false;
+        processor.parse(b"nozen.serial(set,bad serial)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+        // Past the window that would coalesce the identical error
+        processor.tick(ERROR_COALESCE_MS, 1);
+        processor.parse(b"nozen.serial(set,ABCDEFGHIJKLMNOPQ)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+        assert_eq!(processor.config.serial.as_str(), "ABC123");
+        assert!(!processor.config_dirty);
+    }
+
+    #[test]
+    fn test_parse_hex_u16() {
+        assert_eq!(parse_hex_u16(b"1d50"), Some(0x1d50));
+        assert_eq!(parse_hex_u16(b"0x046D"), Some(0x046d));
+        assert_eq!(parse_hex_u16(b"F"), Some(0x000f));
+        assert_eq!(parse_hex_u16(b""), None);
+        assert_eq!(parse_hex_u16(b"0x"), None);
+        assert_eq!(parse_hex_u16(b"12345"), None);
+        assert_eq!(parse_hex_u16(b"12g4"), None);
+    }
+
+    #[test]
+    fn test_banner_set_and_show() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.banner\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Banner] on\n");
+        
+        processor.parse(b"nozen.banner(off)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Banner: off (applies after restart)\n");
+        assert!(!processor.config.banner);
+        assert!(processor.config_dirty);
+        
+        processor.config_dirty = false;
+        processor.parse(b"nozen.banner(quiet)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage"));
+        assert!(!processor.config.banner);
+        assert!(!processor.config_dirty);
+    }
+
+    #[test]
+    fn test_startup_banner_once_per_configuration() {
+        let mut processor = CommandProcessor::new();
+        assert!(!processor.take_startup_banner(false));
+        assert!(processor.take_startup_banner(true));
+        assert!(!processor.take_startup_banner(true));
+
+        // A USB reset sends it again on the next configuration, unless turned off
+        assert!(!processor.take_startup_banner(false));
+        assert!(processor.take_startup_banner(true));
+        processor.config.banner = false;
+        assert!(!processor.take_startup_banner(false));
+        assert!(!processor.take_startup_banner(true));
+    }
+
+    #[test]
+    fn test_usb_ids_set_and_show() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.usb.ids\n", &mut cache);
+        assert_eq!(response_text(&processor), "[USB] VID:PID 1d50:615c\n");
+
+        processor.parse(b"nozen.usb.ids(046d,0xC08B)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] VID:PID 046d:c08b (applies after restart)\n");
+        assert_eq!((processor.config.vid, processor.config.pid), (0x046d, 0xc08b));
+        assert!(processor.config_dirty);
+
+        processor.config_dirty = false;
+        processor.parse(b"nozen.usb.ids(046d)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+        processor.tick(ERROR_COALESCE_MS, 1);
+        processor.parse(b"nozen.usb.ids(xyz,1)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+        assert_eq!(processor.config.vid, 0x046d);
+        assert!(!processor.config_dirty);
+    }
+
+    #[test]
+    fn test_usb_strings_set_and_show() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.usb.strings\n", &mut cache);
+        assert_eq!(response_text(&processor),
+            "[USB] Product: Cynthion HID Injector\nManufacturer: Great Scott Gadgets\n");
+
+        processor.parse(b"nozen.usb.strings(product,Acme Mouse)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Product: Acme Mouse (applies after restart)\n");
+        assert!(processor.config_dirty);
+        processor.parse(b"nozen.usb.strings(manufacturer,\"Acme (EU)\")\n", &mut cache);
+        assert_eq!(processor.config.manufacturer.as_str(), "Acme (EU)");
+
+        processor.config_dirty = false;
+        let long = format!("nozen.usb.strings(product,{})\n", "x".repeat(33));
+        processor.parse(long.as_bytes(), &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+        processor.tick(ERROR_COALESCE_MS, 1);
+        processor.parse(b"nozen.usb.strings(vendor,Acme)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+        assert_eq!(processor.config.product.as_str(), "Acme Mouse");
+        assert!(!processor.config_dirty);
+    }
+
+    #[test]
+    fn test_identical_errors_are_coalesced() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.tick(1000, 0);
+
+        let mut responses = 0;
+        for i in 0..5 {
+            processor.tick(1000 + i * 50, i);
+            if processor.parse(b"nozen.maxspeed(0)\n", &mut cache) == CommandType::Response {
+                responses += 1;
+            }
+        }
+        assert_eq!(responses, 1);
+        assert_eq!(processor.take_error_run(), None);
+
+        // A different error breaks the run and reports it first
+        assert_eq!(processor.parse(b"nozen.sensitivity(0)\n", &mut cache), CommandType::Response);
+        assert_eq!(response_text(&processor), "[ERR] x5\n[ERROR] Sensitivity must be 1-1000\n");
+
+        // A single error has no run to report
+        assert_eq!(processor.parse(b"nozen.maxspeed(0)\n", &mut cache), CommandType::Response);
+        assert_eq!(response_text(&processor), "[ERROR] Max speed must be 1-127\n");
+    }
+
+    #[test]
+    fn test_error_run_reported_after_window() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.tick(1000, 0);
+        processor.parse(b"nozen.maxspeed(0)\n", &mut cache);
+        processor.parse(b"nozen.maxspeed(0)\n", &mut cache);
+        processor.parse(b"nozen.maxspeed(0)\n", &mut cache);
+
+        processor.tick(1000 + ERROR_COALESCE_MS, 1);
+        assert_eq!(processor.take_error_run(), Some(3));
+        assert_eq!(processor.take_error_run(), None);
+
+        // The same error after the window is sent again
+        assert_eq!(processor.parse(b"nozen.maxspeed(0)\n", &mut cache), CommandType::Response);
+    }
+
+    #[test]
+    fn test_cmdstats_counts_by_kind() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.move(1,2)\n", &mut cache);
+        processor.parse(b"nozen.move(-3,4)\n", &mut cache);
+        processor.parse(b"nozen.click()\n", &mut cache);
+        // Malformed lines aren't counted
+        processor.parse(b"nozen.move(1)\n", &mut cache);
+        processor.parse(b"nozen.recoil.add(ak47){1,2,30}\n", &mut cache);
+        processor.parse(b"nozen.recoil.fire(ak47,0)\n", &mut cache);
+
+        assert_eq!(processor.cmd_stats.moves, 2);
+        assert_eq!(processor.cmd_stats.clicks, 1);
+        assert_eq!(processor.cmd_stats.keys, 0);
+        assert_eq!(processor.cmd_stats.recoil_runs, 1);
+
+        processor.parse(b"nozen.cmdstats\n", &mut cache);
+        assert_eq!(response_text(&processor),
+            "[CmdStats] Moves:2 Clicks:1 Wheel:0 Keys:0 Recoil:1 Other:1\n");
+
+        processor.parse(b"nozen.cmdstats.reset\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Command stats cleared\n");
+        assert_eq!(processor.cmd_stats, CommandStats::default());
+        processor.parse(b"nozen.cmdstats\n", &mut cache);
+        assert_eq!(processor.cmd_stats, CommandStats::default());
+    }
+
+    #[test]
+    fn test_print_quoted_message() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.print(Hello World)\n", &mut cache);
+        assert_eq!(response_text(&processor), "Hello World\n");
+
+        processor.parse(b"nozen.print(\"hello (world)\")\n", &mut cache);
+        assert_eq!(response_text(&processor), "hello (world)\n");
+
+        processor.parse(b"nozen.print(\"say \\\"hi\\\" \\\\ bye\")\n", &mut cache);
+        assert_eq!(response_text(&processor), "say \"hi\" \\ bye\n");
+
+        assert_eq!(processor.parse(b"nozen.print(\"unclosed)\n", &mut cache), CommandType::NoOp);
+    }
+
+    #[test]
+    fn test_parse_text_arg() {
+        let (text, used) = parse_text_arg(b"a(b)c").unwrap();
+        assert_eq!((&text[..], used), (&b"a(b"[..], 4));
+
+        let (text, used) = parse_text_arg(b"\"x)y\")rest").unwrap();
+        assert_eq!((&text[..], used), (&b"x)y"[..], 6));
+
+        // Quote closed but not followed by ')'
+        assert_eq!(parse_text_arg(b"\"x\"y)"), None);
+        assert_eq!(parse_text_arg(b"\"x\\"), None);
+        assert_eq!(parse_text_arg(b"no paren"), None);
+    }
+
+    #[test]
+    fn test_errors_counts_bad_lines() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.errors\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Errors] 0\n");
+
+        assert_eq!(processor.parse(b"nozen.mvoe(1,2)\n", &mut cache), CommandType::NoOp);
+        processor.parse(b"nozen.move(1,2)\n", &mut cache);
+        assert_eq!(processor.parse(b"nozen.move(1)\n", &mut cache), CommandType::NoOp);
+
+        processor.parse(b"nozen.errors\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Errors] 2\nLast: nozen.move(1)\n");
+
+        // Long lines are truncated
+        let long = [b'x'; 100];
+        processor.parse(&long, &mut cache);
+        processor.parse(b"\n", &mut cache);
+        assert_eq!(processor.last_bad_line.len(), BAD_LINE_KEEP);
+
+        processor.parse(b"nozen.errors.clear\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Errors cleared\n");
+        processor.parse(b"nozen.errors\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Errors] 0\n");
+    }
+
+    #[test]
+    fn test_parse_unknown_command() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        let cmd = processor.parse(b"nozen.invalid()\n", &mut cache);
+        
+        match cmd {
+            CommandType::NoOp => {}
+            _ => panic!("Expected NoOp"),
+        }
+    }
+
+    #[test]
+    fn test_parse_multiline() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        // First line
+        let cmd1 = processor.parse(b"nozen.move(10,20)\n", &mut cache);
+        assert!(matches!(cmd1, CommandType::FpgaCommand(_)));
+        
+        // Second line
+        let cmd2 = processor.parse(b"nozen.left(1)\n", &mut cache);
+        assert!(matches!(cmd2, CommandType::FpgaCommand(_)));
+    }
+
+    #[test]
+    fn test_parse_partial_then_complete() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        // Send partial command
+        let cmd1 = processor.parse(b"nozen.move(", &mut cache);
+        assert!(matches!(cmd1, CommandType::NoOp));
+        
+        // Complete the command
+        let cmd2 = processor.parse(b"10,20)\n", &mut cache);
+        assert!(matches!(cmd2, CommandType::FpgaCommand(_)));
+    }
+
+    #[test]
+    fn test_hex_digit() {
+        assert_eq!(hex_digit(0), b'0');
+        assert_eq!(hex_digit(9), b'9');
+        assert_eq!(hex_digit(10), b'A');
+        assert_eq!(hex_digit(15), b'F');
+    }
+
+    #[test]
+    fn test_hex_to_nibble() {
+        assert_eq!(hex_to_nibble(b'0'), Some(0));
+        assert_eq!(hex_to_nibble(b'9'), Some(9));
+        assert_eq!(hex_to_nibble(b'A'), Some(10));
+        assert_eq!(hex_to_nibble(b'F'), Some(15));
+        assert_eq!(hex_to_nibble(b'a'), Some(10));
+        assert_eq!(hex_to_nibble(b'f'), Some(15));
+        assert_eq!(hex_to_nibble(b'G'), None);
+    }
+
+    #[test]
+    fn test_parse_u8_from_slice() {
+        assert_eq!(parse_u8_from_slice(b"42"), Some(42));
+        assert_eq!(parse_u8_from_slice(b"0"), Some(0));
+        assert_eq!(parse_u8_from_slice(b"255"), Some(255));
+        assert_eq!(parse_u8_from_slice(b"abc"), None);
+    }
+
+    fn response_text(processor: &CommandProcessor) -> &str {
+        core::str::from_utf8(&processor.response_buffer[..processor.response_len]).unwrap()
+    }
+
+    #[test]
+    fn test_help_lists_commands() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        let cmd = processor.parse(b"nozen.help\n", &mut cache);
+        assert_eq!(cmd, CommandType::Response);
+        let text = response_text(&processor);
+        assert!(text.starts_with("Commands (1/"));
+        assert!(text.lines().any(|l| l == "move"));
+        assert!(text.lines().any(|l| l == "recoil.add"));
+    }
+
+    #[test]
+    fn test_help_pages_cover_table() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        let mut seen = 0;
+
+        for page in 1..=10 {
+            let line = std::format!("nozen.help({})\n", page);
+            processor.parse(line.as_bytes(), &mut cache);
+            let text = response_text(&processor);
+            if text.starts_with("[ERROR]") {
+                break;
+            }
+            seen += text.lines().filter(|l| !l.starts_with("Commands") && !l.starts_with("More:")).count();
+        }
+
+        assert_eq!(seen, COMMAND_TABLE.len());
+    }
+
+    #[test]
+    fn test_dispatch_prefers_longest_prefix() {
+        let short_first = [
+            CommandEntry { prefix: b"nozen.recoil.list", handler: |_, _, _| CommandType::NoOp },
+            CommandEntry { prefix: b"nozen.recoil.listall", handler: |_, _, _| CommandType::Restart },
+        ];
+        let long_first = [
+            CommandEntry { prefix: b"nozen.recoil.listall", handler: |_, _, _| CommandType::Restart },
+            CommandEntry { prefix: b"nozen.recoil.list", handler: |_, _, _| CommandType::NoOp },
+        ];
+
+        for table in [&short_first[..], &long_first[..]] {
+            let entry = find_command(table, b"nozen.recoil.listall").unwrap();
+            assert_eq!(entry.prefix, b"nozen.recoil.listall");
+            let entry = find_command(table, b"nozen.recoil.list").unwrap();
+            assert_eq!(entry.prefix, b"nozen.recoil.list");
+            assert!(find_command(table, b"nozen.recoil.lis").is_none());
+        }
+    }
+
+    #[test]
+    fn test_dispatch_move_and_moveto_distinct() {
+        let entry = find_command(COMMAND_TABLE, b"nozen.moveto(1,2)").unwrap();
+        assert_eq!(entry.prefix, b"nozen.moveto(");
+        let entry = find_command(COMMAND_TABLE, b"nozen.move(1,2)").unwrap();
+        assert_eq!(entry.prefix, b"nozen.move(");
+    }
+
+    #[test]
+    fn test_command_table_prefixes_unique() {
+        for (i, a) in COMMAND_TABLE.iter().enumerate() {
+            for b in &COMMAND_TABLE[i + 1..] {
+                assert_ne!(a.prefix, b.prefix);
+            }
+        }
+    }
+
+    // 3-button relative mouse (X/Y 8-bit), hex encoded as forwarded by the FPGA
+    const MOUSE_DESC_HEX: &str =
+        "05010902A1010901A100050919012903150025019503750181029501750581030501093009311581257F750895028106C0C0";
+
+    // Same layout with 16-bit absolute X/Y in 0..32767 (tablet style)
+    const ABS_MOUSE_DESC_HEX: &str =
+        "05010902A1010901A10005091901290315002501950375018102950175058103050109300931150026FF7F751095028102C0C0";
+
+    // 5-button variant of MOUSE_DESC_HEX (3 padding bits instead of 5)
+    const MOUSE5_DESC_HEX: &str =
+        "05010902A1010901A100050919012905150025019505750181029501750381030501093009311581257F750895028106C0C0";
+
+    // MOUSE_DESC_HEX with an 8-bit relative wheel after X/Y
+    const WHEEL_MOUSE_DESC_HEX: &str =
+        "05010902A1010901A1000509190129031500250195037501810295017505810305010930093109381581257F750895038106C0C0";
+
+    fn desc_line(header: &str) -> heapless::Vec<u8, 256> {
+        desc_line_with(header, MOUSE_DESC_HEX)
+    }
+
+    fn desc_line_with(header: &str, hex: &str) -> heapless::Vec<u8, 256> {
+        let mut line = heapless::Vec::new();
+        line.extend_from_slice(header.as_bytes()).unwrap();
+        line.push(b'{').unwrap();
+        line.extend_from_slice(hex.as_bytes()).unwrap();
+        line.extend_from_slice(b"}\n").unwrap();
+        line
+    }
+
+    #[test]
+    fn test_descriptor_csv_rows() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(&desc_line("[DESC:03:00]"), &mut cache);
+
+        processor.parse(b"nozen.descriptor.csv(3,0)\n", &mut cache);
+        let text = response_text(&processor);
+        let rows: Vec<&str> = text.lines().collect();
+        assert_eq!(rows[0], "report_id,report_type,usage_page,usage_id,bit_offset,bit_size,relative,array");
+        assert_eq!(rows[1], "0,input,0x09,0x0001,0,1,0,0");
+        assert!(rows.contains(&"0,input,0x01,0x0030,8,8,1,0"));
+        assert!(rows.contains(&"0,input,0x01,0x0031,16,8,1,0"));
+        // The 5 padding bits after the buttons complete the layout
+        assert_eq!(rows.last(), Some(&"0,input,padding,,3,5,0,0"));
+        assert!(!text.contains("More:"));
+
+        processor.parse(b"nozen.descriptor.csv(3,0,2)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] No such CSV page\n");
+    }
+
+    #[test]
+    fn test_descriptor_csv_paginates() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        // 16 one-bit buttons then X/Y
+        processor.parse(&desc_line_with("[DESC:04:00]",
+            "05010902A1010901A100050919012910150025019510750181020501093009311581257F750895028106C0C0"), &mut cache);
+        let desc = cache.get(4, 0).unwrap();
+        let fields = desc.fields.len() + desc.padding.len();
+
+        let mut rows = 0;
+        let mut page = 1;
+        loop {
+            let cmd = format!("nozen.descriptor.csv(4,0,{})\n", page);
+            processor.parse(cmd.as_bytes(), &mut cache);
+            assert!(processor.response_len <= processor.response_buffer.len());
+            let text = response_text(&processor);
+            rows += text.lines().filter(|l| l.starts_with("0,")).count();
+            if !text.contains("More: nozen.descriptor.csv(4,0,") {
+                break;
+            }
+            page += 1;
+        }
+        assert!(page > 1);
+        assert_eq!(rows, fields);
+    }
+
+    #[test]
+    fn test_descriptor_axes_reports_wheel_not_pan() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(&desc_line_with("[DESC:02:01]", WHEEL_MOUSE_DESC_HEX), &mut cache);
+
+        processor.parse(b"nozen.descriptor.axes(2,1)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Axes] addr=2 iface=1 X:yes Y:yes Wheel:yes Pan:no\n");
+
+        processor.parse(&desc_line("[DESC:03:00]"), &mut cache);
+        processor.parse(b"nozen.descriptor.axes(3,0)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Axes] addr=3 iface=0 X:yes Y:yes Wheel:no Pan:no\n");
+
+        processor.parse(b"nozen.descriptor.axes(9,0)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Descriptor not found\n");
+    }
+
+    #[test]
+    fn test_fpga_descriptor_interface_zero() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        let cmd = processor.parse(&desc_line("[DESC:05:00]"), &mut cache);
+        assert_eq!(cmd, CommandType::Response);
+        assert!(cache.get(0x05, 0x00).is_some());
+    }
+
+    #[test]
+    fn test_fpga_descriptor_two_digit_interface() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        let cmd = processor.parse(&desc_line("[DESC:05:0A]"), &mut cache);
+        assert_eq!(cmd, CommandType::Response);
+        assert!(cache.get(0x05, 0x0A).is_some());
+        assert!(cache.get(0x05, 0x00).is_none());
+
+        // Single-digit interface as sent by the descriptor forwarder gateware
+        let cmd = processor.parse(&desc_line("[DESC:06:a]"), &mut cache);
+        assert_eq!(cmd, CommandType::Response);
+        assert!(cache.get(0x06, 0x0A).is_some());
+    }
+
+    #[test]
+    fn test_fpga_descriptor_empty_not_cached() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.parse(b"[DESC:05:00]{}\n", &mut cache);
+        assert_eq!(response_text(&processor), "[WARN] Descriptor too short (0B), not cached: dev=5 if=0\n");
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_fpga_descriptor_missing_separator() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        let cmd = processor.parse(&desc_line("[DESC:0500]"), &mut cache);
+        assert_eq!(cmd, CommandType::NoOp);
+        assert!(cache.is_empty());
+
+        // Header without braces must not index past the end of the line
+        let cmd = processor.parse(b"[DESC:05:00]\n", &mut cache);
+        assert_eq!(cmd, CommandType::NoOp);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_moveto_absolute_target_scales_to_logical_range() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(&desc_line_with("[DESC:07:00]", ABS_MOUSE_DESC_HEX), &mut cache);
+        processor.parse(b"nozen.target(7,0)\n", &mut cache);
+        assert_eq!(processor.active_target, Some((7, 0)));
+
+        // Laid out as the tablet's own report: buttons, then 16-bit X and Y
+        let expect = |cmd: CommandType, x: u16, y: u16| match cmd {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.code, 0x11); // INJECT_MOUSE
+                assert_eq!(c.length, 5);
+                assert_eq!(c.payload[0], 0);
+                assert_eq!(u16::from_le_bytes([c.payload[1], c.payload[2]]), x);
+                assert_eq!(u16::from_le_bytes([c.payload[3], c.payload[4]]), y);
+            }
+            _ => panic!("Expected FpgaCommand"),
+        };
+
+        expect(processor.parse(b"nozen.moveto(0,0)\n", &mut cache), 0, 0);
+        expect(processor.parse(b"nozen.moveto(1919,1079)\n", &mut cache), 32767, 32767);
+        expect(processor.parse(b"nozen.moveto(960,540)\n", &mut cache), 16392, 16398);
+        assert_eq!(processor.mouse_state.position(), (960, 540));
+
+        // Coordinates past the screen edge clamp to the logical maximum
+        processor.parse(b"nozen.screen(100,100)\n", &mut cache);
+        expect(processor.parse(b"nozen.moveto(500,99)\n", &mut cache), 32767, 32767);
+
+        // Held buttons go in the button bits
+        processor.held.set_buttons(0x01, true);
+        match processor.parse(b"nozen.moveto(0,0)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(&c.payload[..c.length], &[0x01, 0, 0, 0, 0]),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // With a report ID the tablet's report no longer fits INJECT_MOUSE
+        let with_id = ABS_MOUSE_DESC_HEX.replacen("A101", "A1018501", 1);
+        processor.parse(&desc_line_with("[DESC:08:00]", &with_id), &mut cache);
+        processor.parse(b"nozen.target(8,0)\n", &mut cache);
+        assert_eq!(processor.parse(b"nozen.moveto(10,10)\n", &mut cache), CommandType::Response);
+        assert!(response_text(&processor).contains("longer than INJECT_MOUSE"));
+        assert_eq!(processor.mouse_state.position(), (0, 0));
+    }
+
+    #[test]
+    fn test_moveto_relative_target_falls_back_to_delta() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(&desc_line("[DESC:07:00]"), &mut cache);
+        processor.parse(b"nozen.target(7,0)\n", &mut cache);
+        processor.mouse_state.set_position(10, 20);
+
+        match processor.parse(b"nozen.moveto(50,100)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.code, 0x11);
+                assert_eq!(c.payload[1], 40);
+                assert_eq!(c.payload[2], 80);
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+    }
+
+    #[test]
+    fn test_descriptor_validate_strict() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(&desc_line("[DESC:02:00]"), &mut cache);
+        // Leading reserved local item, skipped by the normal parse
+        let reserved = format!("6900{}", MOUSE_DESC_HEX);
+        processor.parse(&desc_line_with("[DESC:03:00]", &reserved), &mut cache);
+
+        processor.parse(b"nozen.descriptor.validate(2,0)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] addr=2 iface=0 passes strict parse\n");
+
+        processor.parse(b"nozen.descriptor.validate(3,0)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] addr=3 iface=0 strict parse failed: UnknownItem(0)\n");
+
+        processor.parse(b"nozen.descriptor.validate(9,0)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Descriptor not found\n");
+    }
+
+    #[test]
+    fn test_target_not_cached_warns() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.parse(b"nozen.target(3,1)\n", &mut cache);
+        assert!(response_text(&processor).contains("[WARN]"));
+        assert_eq!(processor.active_target, Some((3, 1)));
+
+        processor.parse(b"nozen.target.clear\n", &mut cache);
+        assert_eq!(processor.active_target, None);
+    }
+
+    #[test]
+    fn test_scale_to_logical_signed_range() {
+        assert_eq!(scale_to_logical(0, 1001, -1000, 1000) as i16, -1000);
+        assert_eq!(scale_to_logical(500, 1001, -1000, 1000) as i16, 0);
+        assert_eq!(scale_to_logical(-5, 1001, -1000, 1000) as i16, -1000);
+    }
+
+    #[test]
+    fn test_button_beyond_target_count_rejected() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(&desc_line("[DESC:03:00]"), &mut cache);
+        processor.parse(&desc_line_with("[DESC:04:00]", MOUSE5_DESC_HEX), &mut cache);
+        assert_eq!(cache.get(3, 0).unwrap().button_count(), 3);
+        assert_eq!(cache.get(4, 0).unwrap().button_count(), 5);
+
+        processor.parse(b"nozen.target(3,0)\n", &mut cache);
+        assert_eq!(processor.parse(b"nozen.side1(1)\n", &mut cache), CommandType::Response);
+        assert_eq!(response_text(&processor), "[ERROR] Button 4 not supported, target has 3\n");
+        assert_eq!(processor.held.buttons, 0);
+        assert!(matches!(processor.parse(b"nozen.middle(1)\n", &mut cache), CommandType::FpgaCommand(_)));
+        // Releases are never refused
+        assert!(matches!(processor.parse(b"nozen.side1(0)\n", &mut cache), CommandType::FpgaCommand(_)));
+
+        processor.parse(b"nozen.target(4,0)\n", &mut cache);
+        match processor.parse(b"nozen.side2(1)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(c.payload[0], 0x14),
+            _ => panic!("Expected FpgaCommand"),
+        }
+    }
+
+    #[test]
+    fn test_mouse_buttons_sets_whole_mask() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.right(1)\n", &mut cache);
+
+        match processor.parse(b"nozen.mouse.buttons(0x05)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.code, 0x11);
+                assert_eq!(&c.payload[..c.length], &[0x05, 0, 0, 0, 0]);
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+        assert_eq!(processor.held.buttons, 0x05);
+        // Later single-button commands build on the new state
+        match processor.parse(b"nozen.left(0)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(c.payload[0], 0x04),
+            _ => panic!("Expected FpgaCommand"),
+        }
+
+        // 0x10 is button 5; the sample mouse has 3
+        processor.parse(&desc_line("[DESC:03:00]"), &mut cache);
+        processor.parse(b"nozen.target(3,0)\n", &mut cache);
+        processor.parse(b"nozen.mouse.buttons(16)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Button 5 not supported, target has 3\n");
+        assert_eq!(processor.held.buttons, 0x04);
+
+        processor.parse(b"nozen.mouse.buttons(0x5)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage"));
+    }
+
+    #[test]
+    fn test_buttons_compose_while_held() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.parse(b"nozen.left(1)\n", &mut cache);
+        match processor.parse(b"nozen.right(1)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(c.payload[0], 0x03),
+            _ => panic!("Expected FpgaCommand"),
+        }
+        match processor.parse(b"nozen.left(0)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(c.payload[0], 0x02),
+            _ => panic!("Expected FpgaCommand"),
+        }
+    }
+
+    #[test]
+    fn test_recoil_add_long_name_rejected_with_limit() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        let name = "a".repeat(MAX_PATTERN_NAME_LEN + 1);
+
+        let line = format!("nozen.recoil.add({}){{1,2,3}}\n", name);
+        processor.parse(line.as_bytes(), &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Name too long (max 32 chars)\n");
+        assert_eq!(processor.recoil_manager.list_patterns().count(), 0);
+    }
+
+    #[test]
+    fn test_recoil_add_long_name_truncated_when_lenient() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        let name = "a".repeat(MAX_PATTERN_NAME_LEN + 1);
+
+        processor.parse(b"nozen.lenient(1)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Lenient: on\n");
+
+        let line = format!("nozen.recoil.add({}){{1,2,3}}\n", name);
+        processor.parse(line.as_bytes(), &mut cache);
+        let text = response_text(&processor);
+        assert!(text.starts_with("[WARN] Name truncated to 32 chars: "));
+        assert!(text.ends_with("Recoil pattern added\n"));
+        assert!(processor.recoil_manager.get_pattern(&name[..MAX_PATTERN_NAME_LEN]).is_some());
+
+        processor.parse(b"nozen.lenient(0)\n", &mut cache);
+        assert!(!processor.lenient);
+    }
+
+    #[test]
+    fn test_reset_clears_state_but_keeps_patterns() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.parse(b"nozen.recoil.add(ak47){1,2,3}\n", &mut cache);
+        processor.parse(b"nozen.move(10,20)\n", &mut cache);
+        processor.parse(b"nozen.left(1)\n", &mut cache);
+        assert!(processor.held.any());
+        processor.latency.record(250);
+        processor.link.record_rx(b"[DELTA:1,1]");
+
+        match processor.parse(b"nozen.reset\n", &mut cache) {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.code, 0x11);
+                assert_eq!(&c.payload[..5], &[0, 0, 0, 0, 0]);
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+
+        assert!(!processor.held.any());
+        assert_eq!(processor.mouse_state.position(), (0, 0));
+        assert!(processor.recoil_manager.get_pattern("ak47").is_some());
+        assert_eq!(processor.latency.summary(), None);
+        assert_eq!((processor.link.rx_lines, processor.link.rx_bytes), (0, 0));
+        assert!(response_text(&processor).contains("latency and link counters"));
+        assert!(response_text(&processor).contains("Kept"));
+    }
+
+    #[test]
+    fn test_loopfreq_reports_rate() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.parse(b"nozen.loopfreq\n", &mut cache);
+        assert!(response_text(&processor).contains("not measured"));
+
+        processor.tick(0, 0);
+        processor.tick(1000, 850);
+        assert_eq!(processor.parse(b"nozen.loopfreq\n", &mut cache), CommandType::Response);
+        assert_eq!(response_text(&processor), "[OK] Loop: 850 Hz\n");
+    }
+
+    #[test]
+    fn test_key_hold_queues_repeats_and_release() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.tick(1000, 0);
+
+        match processor.parse(b"nozen.key.hold(4,100,20)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.code, 0x10);
+                assert_eq!(&c.payload[..8], &[0, 0, 4, 0, 0, 0, 0, 0]);
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+
+        // Repeats at +20, +40, +60, +80 then release at +100
+        assert_eq!(processor.pending.len(), 5);
+        let dues: Vec<u32> = processor.pending.iter().map(|s| s.due_ms).collect();
+        assert_eq!(dues, vec![1020, 1040, 1060, 1080, 1100]);
+
+        processor.tick(1019, 1);
+        assert!(processor.next_due().is_none());
+        processor.tick(1080, 2);
+        for _ in 0..4 {
+            assert_eq!(processor.next_due().unwrap().payload[2], 4);
+        }
+        assert!(processor.next_due().is_none());
+        processor.tick(1100, 3);
+        let release = processor.next_due().unwrap();
+        assert_eq!(&release.payload[..8], &[0u8; 8]);
+        assert!(processor.pending.is_empty());
+
+        // A key held with nozen.key stays in the press, repeats and release
+        processor.parse(b"nozen.key(5,1)\n", &mut cache);
+        match processor.parse(b"nozen.key.hold(4,40,20)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(&c.payload[..8], &[0, 0, 5, 4, 0, 0, 0, 0]),
+            _ => panic!("Expected FpgaCommand"),
+        }
+        let steps: Vec<[u8; 8]> = processor.pending.iter().map(|s| s.data[..8].try_into().unwrap()).collect();
+        assert_eq!(steps, vec![[0, 0, 5, 4, 0, 0, 0, 0], [0, 0, 5, 0, 0, 0, 0, 0]]);
+    }
+
+    #[test]
+    fn test_mod_shift_composes_with_key_press() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        match processor.parse(b"nozen.mod(shift,1)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.code, 0x10);
+                assert_eq!(&c.payload[..8], &[0x02, 0, 0, 0, 0, 0, 0, 0]);
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+
+        match processor.parse(b"nozen.key(4,1)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(&c.payload[..8], &[0x02, 0, 0x04, 0, 0, 0, 0, 0]),
+            _ => panic!("Expected FpgaCommand"),
+        }
+
+        match processor.parse(b"nozen.mod(shift,0)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(&c.payload[..8], &[0, 0, 0x04, 0, 0, 0, 0, 0]),
+            _ => panic!("Expected FpgaCommand"),
+        }
+        match processor.parse(b"nozen.key(4,0)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(&c.payload[..8], &[0u8; 8]),
+            _ => panic!("Expected FpgaCommand"),
+        }
+        assert!(!processor.held.any());
+    }
+
+    #[test]
+    fn test_mod_applies_to_key_hold() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.mod(ctrl,1)\n", &mut cache);
+
+        match processor.parse(b"nozen.key.hold(6,10,20)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(&c.payload[..8], &[0x01, 0, 0x06, 0, 0, 0, 0, 0]),
+            _ => panic!("Expected FpgaCommand"),
+        }
+        processor.tick(10, 1);
+        // Releasing the key leaves Ctrl held
+        assert_eq!(&processor.next_due().unwrap().payload[..8], &[0x01, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_held_reports_keys_and_buttons() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        processor.parse(b"nozen.held\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Held] Buttons:0x00 Mods:0x00 Keys:none\n");
+        
+        processor.parse(b"nozen.key(4,1)\n", &mut cache);
+        processor.parse(b"nozen.key(22,1)\n", &mut cache);
+        processor.parse(b"nozen.mod(shift,1)\n", &mut cache);
+        processor.parse(b"nozen.right(1)\n", &mut cache);
+        processor.parse(b"nozen.held\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Held] Buttons:0x02 Mods:0x02 Keys:4,22\n");
+        
+        processor.parse(b"nozen.key(4,0)\n", &mut cache);
+        processor.parse(b"nozen.held\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Held] Buttons:0x02 Mods:0x02 Keys:22\n");
+    }
+
+    // Boot keyboard: modifiers, reserved byte, LED output, six key slots
+    const KEYBOARD_DESC_HEX: &str =
+        "05010906A101050719E029E71500250175019508810295017508810195057501050819012905910295017503910195067508150025650507190029658100C0";
+
+    #[test]
+    fn test_descriptor_bits_locates_fields() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(&desc_line("[DESC:01:0]"), &mut cache);
+        processor.parse(&desc_line_with("[DESC:02:0]", ABS_MOUSE_DESC_HEX), &mut cache);
+        
+        // Three button bits and five of padding, then X and Y
+        let x = cache.get(1, 0).unwrap().fields[3];
+        assert_eq!(x.bit_offset, 8);
+        processor.parse(b"nozen.descriptor.bits(1,0,3)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Bits] field 3: byte 1 bits 0-7 mask 0xFF\n");
+        processor.parse(b"nozen.descriptor.bits(1,0,1)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Bits] field 1: byte 0 bits 1-1 mask 0x02\n");
+        
+        // 16-bit Y of the tablet layout spans two bytes
+        processor.parse(b"nozen.descriptor.bits(2,0,4)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Bits] field 4: bytes 3-4 bits 0-15 mask 0xFFFF\n");
+        
+        processor.parse(b"nozen.descriptor.bits(1,0,5)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] No such field\n");
+        processor.parse(b"nozen.descriptor.bits(1,0)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage"));
+    }
+
+    #[test]
+    fn test_keyboard_report_decodes_to_key_names() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(&desc_line_with("[DESC:01:0]", KEYBOARD_DESC_HEX), &mut cache);
+        
+        processor.parse(b"[RPT:01:0]{0100040000000000}\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Keys] dev=1 if=0 Ctrl+A\n");
+        
+        processor.parse(b"[RPT:01:0]{22002C3A00000000}\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Keys] dev=1 if=0 Shift+RShift+Space+F1\n");
+        
+        processor.parse(b"[RPT:01:0]{0000000000000000}\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Keys] dev=1 if=0 (none)\n");
+        
+        // Reports from devices without a cached keyboard layout are ignored
+        processor.parse(&desc_line("[DESC:02:0]"), &mut cache);
+        assert_eq!(processor.parse(b"[RPT:02:0]{0100000000}\n", &mut cache), CommandType::NoOp);
+        assert_eq!(processor.parse(b"[RPT:03:0]{0100040000000000}\n", &mut cache), CommandType::NoOp);
+    }
+
+    #[test]
+    fn test_calibrate_traces_closed_box() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        processor.parse(b"nozen.calibrate(200)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Calibrating: 200 px box, 8 moves\n");
+        
+        // Position after each move, and where the pauses fall
+        let mut at = (0i32, 0i32);
+        let mut corners = Vec::new();
+        let mut last_due = 0;
+        for step in processor.pending.iter() {
+            if step.due_ms - last_due > SPLIT_INTERVAL_MS {
+                corners.push(at);
+            }
+            last_due = step.due_ms;
+            at.0 += step.data[1] as i8 as i32;
+            at.1 += step.data[2] as i8 as i32;
+        }
+        corners.push(at);
+        assert_eq!(corners, vec![(200, 0), (200, 200), (0, 200), (0, 0)]);
+        assert_eq!(processor.mouse_state.position(), (0, 0));
+        
+        processor.parse(b"nozen.calibrate(0)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage"));
+    }
+
+    #[test]
+    fn test_key_isheld_follows_presses() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        processor.parse(b"nozen.key(4,1)\n", &mut cache);
+        processor.parse(b"nozen.key.isheld(4)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Key] 4 held=1\n");
+        processor.parse(b"nozen.key.isheld(5)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Key] 5 held=0\n");
+        
+        // Modifier scancodes read the modifier byte
+        processor.parse(b"nozen.mod(shift,1)\n", &mut cache);
+        processor.parse(b"nozen.key.isheld(225)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Key] 225 held=1\n");
+        
+        processor.parse(b"nozen.key(4,0)\n", &mut cache);
+        processor.parse(b"nozen.key.isheld(4)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Key] 4 held=0\n");
+        
+        processor.parse(b"nozen.key.isheld(0)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage"));
+    }
+
+    #[test]
+    fn test_keys_table_covers_printable_ascii() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        let mut rows = Vec::new();
+        let mut page = 1;
+        loop {
+            processor.parse(format!("nozen.keys.table({})\n", page).as_bytes(), &mut cache);
+            let text = response_text(&processor);
+            assert!(text.len() <= 256);
+            assert!(text.starts_with(&format!("[Keys] ({}/", page)));
+            rows.extend(text.lines().skip(1).filter(|l| !l.starts_with("More:")).map(String::from));
+            if !text.contains("More: nozen.keys.table(") {
+                break;
+            }
+            page += 1;
+        }
+        
+        assert!(rows.contains(&"a 0x04".to_string()));
+        assert!(rows.contains(&"A 0x04 shift".to_string()));
+        assert!(rows.contains(&"space 0x2C".to_string()));
+        assert_eq!(rows.len(), (0x20u8..=0x7E).filter(|&c| ascii_to_scancode(c).is_some()).count());
+        
+        processor.parse(format!("nozen.keys.table({})\n", page + 1).as_bytes(), &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] No such key table page\n");
+    }
+
+    #[test]
+    fn test_mod_and_key_reject_bad_args() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        for (i, line) in [&b"nozen.mod(hyper,1)\n"[..], b"nozen.mod(shift,2)\n", b"nozen.key(0,1)\n", b"nozen.key(4,3)\n",
+                                      b"nozen.key(260,1)\n", b"nozen.key(4,)\n", b"nozen.key(4,1x)\n"].into_iter().enumerate() {
+            // Spaced past the window that would coalesce identical errors
+            processor.tick(i as u32 * ERROR_COALESCE_MS, i as u32);
+            assert_eq!(processor.parse(line, &mut cache), CommandType::Response);
+            assert!(response_text(&processor).starts_with("[ERROR]"));
+        }
+
+        for key in 4..10 {
+            processor.parse(format!("nozen.key({},1)\n", key).as_bytes(), &mut cache);
+        }
+        processor.parse(b"nozen.key(10,1)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Six keys already held\n");
+    }
+
+    #[test]
+    fn test_key_hold_rejects_bad_args() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.parse(b"nozen.key.hold(4,100,0)\n", &mut cache);
+        assert!(response_text(&processor).contains("[ERROR]"));
+        // Too many repeats to fit the queue
+        processor.parse(b"nozen.key.hold(4,10000,1)\n", &mut cache);
+        assert!(response_text(&processor).contains("Queue full"));
+        assert!(processor.pending.is_empty());
+    }
+
+    #[test]
+    fn test_recoil_step_get_and_set() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.recoil.add(ak47){1,2,30,4,5,60}\n", &mut cache);
+
+        processor.parse(b"nozen.recoil.step(ak47,1)\n", &mut cache);
+        assert_eq!(response_text(&processor), "ak47[1]: 4,5,60\n");
+
+        processor.parse(b"nozen.recoil.step(ak47,2)\n", &mut cache);
+        assert!(response_text(&processor).contains("out of range"));
+        // Past the window that would coalesce the identical error
+        processor.tick(ERROR_COALESCE_MS, 1);
+        processor.parse(b"nozen.recoil.step(ak47,-1)\n", &mut cache);
+        assert!(response_text(&processor).contains("out of range"));
+
+        processor.parse(b"nozen.recoil.setstep(ak47,0,-3,7,45)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[OK]"));
+        processor.parse(b"nozen.recoil.step(ak47,0)\n", &mut cache);
+        assert_eq!(response_text(&processor), "ak47[0]: -3,7,45\n");
+
+        processor.parse(b"nozen.recoil.setstep(ak47,5,0,0,0)\n", &mut cache);
+        assert!(response_text(&processor).contains("out of range"));
+        processor.parse(b"nozen.recoil.setstep(ak47,0,1)\n", &mut cache);
+        assert!(response_text(&processor).contains("Usage"));
+    }
+
+    #[test]
+    fn test_recoil_fire_sends_one_step() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.recoil.add(ak47){1,2,30,4,-5,60}\n", &mut cache);
+
+        match processor.parse(b"nozen.recoil.fire(ak47,1)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.code, 0x11);
+                assert_eq!(&c.payload[..c.length], &[0, 4, (-5i8) as u8, 0, 0]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(processor.pending.is_empty());
+
+        processor.parse(b"nozen.recoil.fire(ak47,2)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Step index out of range (2 steps)\n");
+        processor.parse(b"nozen.recoil.fire(m4,0)\n", &mut cache);
+        assert!(response_text(&processor).contains("not found"));
+    }
+
+    #[test]
+    fn test_recoil_absolute_far_target_clamps_delta() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.recoil.add(far,abs){30000,-30000,0}\n", &mut cache);
+        processor.mouse_state.set_position(-30000, 30000);
+
+        // Too many reports to queue, refused before anything moves
+        processor.parse(b"nozen.recoil.run(far)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Queue full\n");
+        assert_eq!(processor.mouse_state.position(), (-30000, 30000));
+
+        // The tracked position follows the clamped delta, not the target
+        assert!(matches!(processor.parse(b"nozen.recoil.fire(far,0)\n", &mut cache), CommandType::FpgaCommand(_)));
+        assert_eq!(processor.mouse_state.position(), (2767, -2768));
+    }
+
+    #[test]
+    fn test_recoil_batch_adds_patterns() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.parse(b"nozen.recoil.batch{ak47:1,-2,30,4,5,60;m4:7,8,9}\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Batch: 2 added, 0 failed\n");
+        assert_eq!(processor.recoil_manager.get_pattern("ak47").unwrap().steps.as_slice(), &[1, -2, 30, 4, 5, 60]);
+        assert_eq!(processor.recoil_manager.get_pattern("m4").unwrap().steps.as_slice(), &[7, 8, 9]);
+
+        // Bad segments are counted, good ones still land
+        processor.parse(b"nozen.recoil.batch{bad:1,2;ok:1,2,3;nocolon}\n", &mut cache);
+        assert_eq!(response_text(&processor), "[WARN] Batch: 1 added, 2 failed\n");
+        assert!(processor.recoil_manager.get_pattern("ok").is_some());
+    }
+
+    #[test]
+    fn test_recoil_batch_stops_when_storage_full() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        for i in 0..15 {
+            processor.recoil_manager.add_pattern(&format!("p{}", i), &[1, 1, 1]).unwrap();
+        }
+
+        processor.parse(b"nozen.recoil.batch{a:1,1,1;b:2,2,2;c:3,3,3}\n", &mut cache);
+        assert_eq!(response_text(&processor), "[WARN] Batch: 1 added, 2 failed\n[ERROR] Pattern storage full\n");
+        assert!(processor.recoil_manager.get_pattern("a").is_some());
+        assert!(processor.recoil_manager.get_pattern("c").is_none());
+    }
+
+    #[test]
+    fn test_typeseq_queues_press_release_pairs() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.tick(500, 0);
+
+        processor.parse(b"nozen.typeseq(h:50,I:80,\x01:10,,:20)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Queued 3 keys, skipped 1\n");
+
+        let steps: Vec<(u32, u8, u8)> = processor.pending.iter()
+            .map(|s| (s.due_ms, s.data[0], s.data[2]))
+            .collect();
+        assert_eq!(steps, vec![
+            (500, 0, 0x0B), (550, 0, 0),        // h
+            (550, 0x02, 0x0C), (630, 0, 0),     // I (shifted)
+            (630, 0, 0x36), (650, 0, 0),        // ,
+        ]);
+
+        // Keys and modifiers already held stay down while typing
+        processor.pending.clear();
+        processor.parse(b"nozen.mod(ctrl,1)\n", &mut cache);
+        processor.parse(b"nozen.key(5,1)\n", &mut cache);
+        processor.parse(b"nozen.typeseq(a:10)\n", &mut cache);
+        let steps: Vec<[u8; 8]> = processor.pending.iter().map(|s| s.data[..8].try_into().unwrap()).collect();
+        assert_eq!(steps, vec![[0x01, 0, 5, 4, 0, 0, 0, 0], [0x01, 0, 5, 0, 0, 0, 0, 0]]);
+    }
+
+    #[test]
+    fn test_typeseq_rejects_bad_format() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        for (i, line) in [&b"nozen.typeseq(h50)\n"[..], b"nozen.typeseq(h:)\n", b"nozen.typeseq(h:0)\n", b"nozen.typeseq(h:5,)\n", b"nozen.typeseq()\n"].into_iter().enumerate() {
+            // Spaced past the window that would coalesce identical errors
+            processor.tick(i as u32 * ERROR_COALESCE_MS, i as u32);
+            processor.parse(line, &mut cache);
+            assert!(response_text(&processor).starts_with("[ERROR]"));
+        }
+        assert!(processor.pending.is_empty());
+    }
+
+    #[test]
+    fn test_suspend_releases_buttons_and_keys() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.tick(200, 0);
+
+        processor.parse(b"nozen.left(1)\n", &mut cache);
+        processor.held.press_key(0x04);
+        processor.parse(b"nozen.key.hold(5,100,20)\n", &mut cache);
+        assert!(processor.held.any());
+
+        let cmd = processor.on_suspend();
+        assert_eq!(cmd.code, 0x11);
+        assert_eq!(&cmd.payload[..5], &[0u8; 5]);
+        assert!(!processor.held.any());
+        assert_eq!(processor.response_len, 0);
+
+        // Only the neutral keyboard report is left, due immediately
+        let kbd = processor.next_due().unwrap();
+        assert_eq!((kbd.code, &kbd.payload[..8]), (0x10, &[0u8; 8][..]));
+        assert!(processor.next_due().is_none());
+        assert!(processor.pending.is_empty());
+    }
+
+    #[test]
+    fn test_releaseall_command() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.parse(b"nozen.right(1)\n", &mut cache);
+        match processor.parse(b"nozen.releaseall\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(c.payload[0], 0),
+            _ => panic!("Expected FpgaCommand"),
+        }
+        assert!(!processor.held.any());
+        assert_eq!(response_text(&processor), "[OK] Released all inputs\n");
+    }
+
+    #[test]
+    fn test_buffer_reports_partial_line() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        assert_eq!(processor.parse(b"nozen.mov", &mut cache), CommandType::NoOp);
+        assert_eq!(processor.parse(b"nozen.buffer\n", &mut cache), CommandType::Response);
+        assert_eq!(response_text(&processor), "[Buffer] 9 bytes\n6E6F7A656E2E6D6F76\n");
+
+        // The partial line is still there and completes normally
+        match processor.parse(b"e(3,4)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => assert_eq!(&c.payload[1..3], &[3, 4]),
+            _ => panic!("Expected FpgaCommand"),
+        }
+
+        processor.parse(b"nozen.buffer\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Buffer] 0 bytes\n\n");
+    }
+
+    #[test]
+    fn test_crlf_is_one_line_boundary() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        assert!(matches!(processor.parse(b"nozen.left(1)\r\n", &mut cache), CommandType::FpgaCommand(_)));
+        // Terminator split across reads
+        assert!(matches!(processor.parse(b"nozen.left(0)\r", &mut cache), CommandType::FpgaCommand(_)));
+        assert_eq!(processor.parse(b"\n", &mut cache), CommandType::NoOp);
+
+        assert_eq!(processor.parse(b"\r\n\r\n", &mut cache), CommandType::NoOp);
+        assert_eq!(processor.parse(b"\n\r", &mut cache), CommandType::NoOp);
+
+        // Leading blank lines don't swallow the command behind them
+        assert!(matches!(processor.parse(b"\r\nnozen.left(1)\n", &mut cache), CommandType::FpgaCommand(_)));
+    }
+
+    #[test]
+    fn test_descriptor_export_import_commands() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        cache.add(3, 0, &[0x05, 0x01, 0x09, 0x30, 0x75, 0x08, 0x95, 0x01, 0x81, 0x06]).unwrap();
+        cache.add(5, 1, &[0x05, 0x07, 0x09, 0x04, 0x75, 0x08, 0x95, 0x01, 0x81, 0x00]).unwrap();
+
+        processor.parse(b"nozen.descriptor.export\n", &mut cache);
+        let text = response_text(&processor);
+        let hex = text.strip_prefix("[Export] ").unwrap().trim_end().to_string();
+        assert!(hex.starts_with("020300"));
+
+        let mut restored = DescriptorCache::new();
+        let line = format!("nozen.descriptor.import{{{}}}\n", hex);
+        processor.parse(line.as_bytes(), &mut restored);
+        assert_eq!(response_text(&processor), "[OK] Imported 2 descriptors\n");
+        assert!(restored.get(3, 0).unwrap().is_mouse);
+        assert!(restored.get(5, 1).unwrap().is_keyboard);
+
+        processor.parse(b"nozen.descriptor.import{0G}\n", &mut restored);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+    }
+
+    #[test]
+    fn test_descriptor_export_max_size_round_trip() {
+        let mut processor = CommandProcessor::new();
+        // Count byte and record header leave the rest for the raw descriptor
+        let mut raw = vec![0x05, 0x01, 0x09, 0x02];
+        while raw.len() < DESCRIPTOR_EXPORT_MAX - 5 {
+            raw.extend_from_slice(&[0x75, 0x08]);
+        }
+        assert_eq!(raw.len(), DESCRIPTOR_EXPORT_MAX - 5);
+        let mut cache = DescriptorCache::new();
+        cache.add(7, 1, &raw).unwrap();
+
+        processor.parse(b"nozen.descriptor.export\n", &mut cache);
+        let text = response_text(&processor);
+        let hex = text.strip_prefix("[Export] ").unwrap().trim_end().to_string();
+        assert_eq!(hex.len(), DESCRIPTOR_EXPORT_MAX * 2);
+
+        let line = format!("nozen.descriptor.import{{{}}}\n", hex);
+        assert!(line.len() - 1 <= 256);
+        let mut restored = DescriptorCache::new();
+        processor.parse(line.as_bytes(), &mut restored);
+        assert_eq!(response_text(&processor), "[OK] Imported 1 descriptors\n");
+        assert_eq!(restored.raw(7, 1), Some(&raw[..]));
+
+        // One byte more is refused instead of producing an unimportable line
+        let mut raw = raw.clone();
+        raw.push(0x74);
+        cache.add(7, 1, &raw).unwrap();
+        processor.parse(b"nozen.descriptor.export\n", &mut cache);
+        assert_eq!(
+            response_text(&processor),
+            format!("[ERROR] Cache too large to export (max {} bytes)\n", DESCRIPTOR_EXPORT_MAX)
+        );
+    }
+
+    #[test]
+    fn test_descriptor_pin_command() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(&desc_line("[DESC:01:0]"), &mut cache);
+
+        processor.parse(b"nozen.descriptor.pin(1,0)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Pinned addr=1 iface=0\n");
+        assert!(cache.iter().all(|e| e.pinned));
+
+        processor.parse(b"nozen.descriptor.pin(9,0)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Descriptor not found\n");
+
+        processor.parse(b"nozen.descriptor.unpin(1,0)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Unpinned addr=1 iface=0\n");
+        assert!(!cache.iter().any(|e| e.pinned));
+    }
+
+    #[test]
+    fn test_descriptor_all_pinned_is_reported() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        for addr in 0..crate::descriptor_cache::MAX_CACHED_DEVICES as u8 {
+            processor.parse(&desc_line(&format!("[DESC:{:02X}:00]", addr)), &mut cache);
+            assert!(cache.pin(addr, 0));
         }
+
+        processor.parse(&desc_line("[DESC:20:00]"), &mut cache);
+        assert_eq!(
+            response_text(&processor),
+            "[WARN] Descriptor cache full, all entries pinned: dev=32 if=0 not cached, unpin one with nozen.descriptor.unpin\n"
+        );
+        assert!(cache.get(32, 0).is_none());
+
+        let mut blob = [0u8; 128];
+        let mut source = DescriptorCache::new();
+        source.add(33, 0, &[0x05, 0x01, 0x09, 0x02]).unwrap();
+        let len = source.export(&mut blob).unwrap();
+        let hex: String = blob[..len].iter().map(|b| format!("{:02X}", b)).collect();
+        processor.parse(format!("nozen.descriptor.import{{{}}}\n", hex).as_bytes(), &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Import failed: cache full, all entries pinned\n");
+
+        // Unpinning one makes room again
+        processor.parse(b"nozen.descriptor.unpin(3,0)\n", &mut cache);
+        processor.parse(&desc_line("[DESC:20:00]"), &mut cache);
+        assert!(cache.get(32, 0).is_some());
+        assert!(cache.get(3, 0).is_none());
+    }
+
+    #[test]
+    fn test_reportids_lists_every_cached_device() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        // Composite keyboard (report ID 1) + mouse (report ID 2)
+        cache.add(2, 0, &[
+            0x05, 0x01, 0x09, 0x06, 0xA1, 0x01, 0x85, 0x01, 0x05, 0x07, 0x19, 0xE0, 0x29, 0xE7,
+            0x15, 0x00, 0x25, 0x01, 0x75, 0x01, 0x95, 0x08, 0x81, 0x02, 0xC0,
+            0x05, 0x01, 0x09, 0x02, 0xA1, 0x01, 0x85, 0x02, 0x09, 0x30, 0x09, 0x31,
+            0x15, 0x81, 0x25, 0x7F, 0x75, 0x08, 0x95, 0x02, 0x81, 0x06, 0xC0,
+        ]).unwrap();
+        // Plain mouse without report IDs
+        cache.add(3, 1, &[0x05, 0x01, 0x09, 0x30, 0x75, 0x08, 0x95, 0x01, 0x81, 0x06]).unwrap();
+
+        processor.parse(b"nozen.reportids\n", &mut cache);
+        let text = response_text(&processor);
+        assert!(text.starts_with("Report IDs (1/1, 3 total):\n"));
+        assert!(text.contains("2:0 id=1 in="));
+        assert!(text.contains("2:0 id=2 in="));
+        assert!(text.contains("3:1 id=0 in=1\n"));
+        assert!(!text.contains("More:"));
+
+        processor.parse(b"nozen.reportids(2)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+    }
+
+    #[test]
+    fn test_reportids_paginates() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        // Ten rows: more than one page
+        let two_ids = [
+            0x85, 0x01, 0x05, 0x01, 0x09, 0x30, 0x75, 0x08, 0x95, 0x01, 0x81, 0x06,
+            0x85, 0x02, 0x09, 0x31, 0x75, 0x08, 0x95, 0x01, 0x81, 0x06,
+        ];
+        for addr in 1..=5 {
+            cache.add(addr, 0, &two_ids).unwrap();
+        }
+        processor.parse(b"nozen.reportids\n", &mut cache);
+        let text = response_text(&processor);
+        assert!(text.starts_with("Report IDs (1/2, 10 total):\n"));
+        assert!(text.ends_with("More: nozen.reportids(2)\n"));
+
+        processor.parse(b"nozen.reportids(2)\n", &mut cache);
+        let text = response_text(&processor);
+        assert!(text.starts_with("Report IDs (2/2, 10 total):\n"));
+        assert!(text.contains("5:0 id=2"));
+    }
+
+    #[test]
+    fn test_frame_returns_hex_of_uart_frame() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.mouse_state.set_position(7, 7);
+
+        let expected = match processor.parse(b"nozen.move(10,5)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => c,
+            _ => panic!("Expected FpgaCommand"),
+        };
+        processor.mouse_state.set_position(7, 7);
+
+        assert_eq!(processor.parse(b"nozen.frame(nozen.move(10,5))\n", &mut cache), CommandType::Response);
+        let hex: String = expected.to_uart_frame()[..expected.uart_frame_len()]
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect();
+        assert_eq!(response_text(&processor), format!("[Frame] {}\n", hex));
+
+        // Nothing was injected, so tracked state is untouched
+        assert_eq!(processor.mouse_state.position(), (7, 7));
+    }
+
+    #[test]
+    fn test_frame_rolls_back_state_and_rejects_non_fpga() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.parse(b"nozen.frame(nozen.left(1))\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[Frame] "));
+        assert_eq!(processor.held.buttons, 0);
+
+        processor.parse(b"nozen.frame(nozen.key.hold(4,100,20))\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[Frame] "));
+        assert!(processor.pending.is_empty());
+
+        processor.parse(b"nozen.frame(nozen.getpos)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Not an FPGA command\n");
+
+        // Rejected before it runs, so the settings it would change are kept
+        processor.tick(ERROR_COALESCE_MS, 1);
+        processor.parse(b"nozen.frame(nozen.sensitivity(150))\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Not an FPGA command\n");
+        assert_eq!(processor.sensitivity, 100);
+
+        // Timed and nested commands aren't single frames and are refused
+        processor.tick(2 * ERROR_COALESCE_MS, 1);
+        processor.parse(b"nozen.frame(nozen.wander(5,100))\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Not an FPGA command\n");
+        assert!(processor.wander.is_none());
+        processor.tick(3 * ERROR_COALESCE_MS, 1);
+        processor.parse(b"nozen.frame(nozen.frame(nozen.left(1)))\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Not an FPGA command\n");
+
+        // Previewed commands aren't counted as sent
+        assert_eq!(processor.cmd_stats.moves, 0);
+        assert_eq!(processor.cmd_stats.clicks, 0);
+
+        processor.parse(b"nozen.frame(move(1,1))\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage"));
     }
-    
-    /// Handle descriptor.stats command
-    fn handle_descriptor_stats(&mut self, descriptor_cache: &DescriptorCache) -> CommandType {
-        let stats = descriptor_cache.get_stats();
+
+    #[test]
+    fn test_mode_save_and_restore() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.parse(b"nozen.mode.restore\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] No saved mode"));
+
+        processor.parse(b"nozen.sensitivity(150)\n", &mut cache);
+        processor.parse(b"nozen.invert(1,0)\n", &mut cache);
+        processor.parse(b"nozen.mode.save\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Mode saved\n");
+        let saved = processor.mode_snapshot();
+
+        processor.parse(b"nozen.sensitivity(50)\n", &mut cache);
+        processor.parse(b"nozen.invert(0,1)\n", &mut cache);
+        processor.parse(b"nozen.dryrun(1)\n", &mut cache);
+        processor.parse(b"nozen.human(high)\n", &mut cache);
+        processor.parse(b"nozen.deadzone(3)\n", &mut cache);
+        processor.parse(b"nozen.wheel.step(4)\n", &mut cache);
+        processor.parse(b"nozen.strict(1)\n", &mut cache);
+        processor.parse(b"nozen.track(0)\n", &mut cache);
+        processor.parse(b"nozen.reportid(2)\n", &mut cache);
+        assert_ne!(processor.mode_snapshot(), saved);
+
+        processor.parse(b"nozen.mode.restore\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Mode restored\n");
+        assert_eq!(processor.mode_snapshot(), saved);
+        assert_eq!(processor.sensitivity, 150);
+        assert_eq!(processor.invert, (true, false));
+        assert!(!processor.dry_run);
+        assert_eq!((processor.deadzone, processor.wheel_step, processor.report_id), (0, 1, 0));
+        assert!(!processor.strict && processor.track);
+
+        // The snapshot is used up
+        processor.tick(ERROR_COALESCE_MS, 1);
+        processor.parse(b"nozen.mode.restore\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] No saved mode"));
+    }
+
+    #[test]
+    fn test_deadman_releases_after_silence() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
         
-        self.response_len = 0;
-        let stats_str = stats.format();
-        write_str(&mut self.response_buffer[..], stats_str.as_bytes(), &mut self.response_len);
-        write_str(&mut self.response_buffer[..], b"\n", &mut self.response_len);
+        processor.parse(b"nozen.deadman(500)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Deadman: release after 500 ms idle\n");
+        processor.parse(b"nozen.key(4,1)\n", &mut cache);
+        processor.parse(b"nozen.left(1)\n", &mut cache);
+        assert!(processor.held.any());
         
-        CommandType::Response
+        // A command before the timeout restarts it
+        processor.tick(400, 1);
+        processor.parse(b"nozen.getpos()\n", &mut cache);
+        processor.tick(899, 1);
+        assert!(processor.held.any());
+        assert!(!processor.take_deadman_trip());
+        
+        processor.tick(900, 1);
+        assert!(!processor.held.any());
+        assert!(processor.take_deadman_trip());
+        assert!(!processor.take_deadman_trip());
+        let keyboard = processor.next_due().unwrap();
+        let mouse = processor.next_due().unwrap();
+        assert_eq!((keyboard.code, &keyboard.payload[..keyboard.length]), (0x10, &[0u8; 8][..]));
+        assert_eq!((mouse.code, &mouse.payload[..mouse.length]), (0x11, &[0u8; 5][..]));
+        assert!(processor.next_due().is_none());
+        
+        // Trips once per silence
+        processor.tick(5000, 1);
+        assert!(processor.pending.is_empty());
+        assert!(!processor.take_deadman_trip());
+        
+        processor.parse(b"nozen.deadman(0)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Deadman off\n");
+        processor.parse(b"nozen.deadman(50)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage"));
     }
-}
 
-/// Parse u8 from byte slice
-fn parse_u8_from_slice(data: &[u8]) -> Option<u8> {
-    let mut value = 0u8;
-    let mut idx = 0;
-    
-    while idx < data.len() && data[idx] >= b'0' && data[idx] <= b'9' {
-        value = value.wrapping_mul(10).wrapping_add(data[idx] - b'0');
-        idx += 1;
+    #[test]
+    fn test_deadman_stops_antiidle_and_wander() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        
+        processor.parse(b"nozen.deadman(500)\n", &mut cache);
+        processor.parse(b"nozen.antiidle(100,1)\n", &mut cache);
+        processor.parse(b"nozen.wander(3,10)\n", &mut cache);
+        processor.tick(500, 1);
+        assert!(processor.take_deadman_trip());
+        assert_eq!(processor.anti_idle_ms, 0);
+        assert!(processor.wander.is_none());
+        
+        // Only the neutral reports are left, and nothing is queued after them
+        assert_eq!(processor.pending.len(), 2);
+        while processor.next_due().is_some() {}
+        for now in (510..5000).step_by(10) {
+            processor.tick(now, 1);
+        }
+        assert!(processor.pending.is_empty());
     }
-    
-    if idx > 0 {
-        Some(value)
-    } else {
-        None
+
+    #[test]
+    fn test_antiidle_queues_jiggle_each_interval() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.parse(b"nozen.antiidle(1000,1)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Anti-idle every 1000 ms\n");
+        let start = processor.mouse_state.position();
+
+        processor.tick(999, 1);
+        assert!(processor.pending.is_empty());
+
+        for round in 1..=2u32 {
+            processor.tick(1000 * round, 1);
+            let out = processor.next_due().unwrap();
+            let back = processor.next_due().unwrap();
+            assert!(processor.next_due().is_none());
+            assert_eq!(&out.payload[..out.length], &[0, 1, 0, 0, 0]);
+            assert_eq!(&back.payload[..back.length], &[0, 0xFF, 0, 0, 0]);
+            assert_eq!(out.payload[1] as i8 + back.payload[1] as i8, 0);
+        }
+        assert_eq!(processor.mouse_state.position(), start);
+
+        processor.parse(b"nozen.antiidle(0,0)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Anti-idle off\n");
+        processor.tick(5000, 1);
+        assert!(processor.pending.is_empty());
+
+        // Releasing everything stops it too
+        processor.parse(b"nozen.antiidle(1000,1)\n", &mut cache);
+        processor.parse(b"nozen.releaseall\n", &mut cache);
+        assert_eq!(processor.anti_idle_ms, 0);
+        while processor.next_due().is_some() {}
+        processor.tick(7000, 1);
+        assert!(processor.pending.is_empty());
+
+        processor.parse(b"nozen.antiidle(50,1)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage: nozen.antiidle(100-30000,1)"));
     }
-}
 
-/// Convert hex character to nibble
-fn hex_to_nibble(c: u8) -> Option<u8> {
-    match c {
-        b'0'..=b'9' => Some(c - b'0'),
-        b'a'..=b'f' => Some(c - b'a' + 10),
-        b'A'..=b'F' => Some(c - b'A' + 10),
-        _ => None,
+    #[test]
+    fn test_wander_drifts_within_radius() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.parse(b"nozen.wander(3,10)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Wander: 3 px every 10 ms\n");
+        let mut sent = (0i32, 0i32);
+        let mut moves = 0;
+        for round in 1..=500u32 {
+            processor.tick(10 * round, 1);
+            while let Some(cmd) = processor.next_due() {
+                sent.0 += cmd.payload[1] as i8 as i32;
+                sent.1 += cmd.payload[2] as i8 as i32;
+                moves += 1;
+            }
+            assert!(sent.0 * sent.0 + sent.1 * sent.1 <= 9);
+            assert_eq!(processor.mouse_state.position(), (sent.0 as i16, sent.1 as i16));
+        }
+        assert!(moves > 100);
+
+        processor.parse(b"nozen.wander(0,0)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Wander off\n");
+        processor.tick(10_000, 1);
+        assert!(processor.pending.is_empty());
+
+        // Releasing everything stops it too
+        processor.parse(b"nozen.wander(3,10)\n", &mut cache);
+        processor.parse(b"nozen.reset\n", &mut cache);
+        assert!(processor.wander.is_none());
+        while processor.next_due().is_some() {}
+        processor.tick(20_000, 1);
+        assert!(processor.pending.is_empty());
+
+        processor.parse(b"nozen.wander(3,5)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage: nozen.wander(1-1000,10-30000)"));
     }
-}
 
-/// Write string to buffer
-fn write_str(buf: &mut [u8], data: &[u8], len: &mut usize) {
-    let copy_len = data.len().min(buf.len() - *len);
-    buf[*len..*len + copy_len].copy_from_slice(&data[..copy_len]);
-    *len += copy_len;
-}
+    #[test]
+    fn test_reportid_prefixes_injected_reports() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
 
+        processor.parse(b"nozen.reportid(3)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Report ID: 3 forced\n");
 
-fn hex_digit(nibble: u8) -> u8 {
-    match nibble & 0x0F {
-        0..=9 => b'0' + nibble,
-        10..=15 => b'A' + (nibble - 10),
-        _ => b'?',
+        match processor.parse(b"nozen.move(10,-5)\n", &mut cache) {
+            CommandType::FpgaCommand(cmd) => {
+                assert_eq!(cmd.code, 0x11);
+                assert_eq!(&cmd.payload[..cmd.length], &[3, 0x00, 0x0A, 0xFB, 0, 0]);
+            }
+            other => panic!("expected FpgaCommand, got {:?}", other),
+        }
+        assert_eq!(processor.bench_report().payload[0], 3);
+
+        processor.parse(b"nozen.reportid(0)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Report ID: default\n");
+        match processor.parse(b"nozen.move(10,-5)\n", &mut cache) {
+            CommandType::FpgaCommand(cmd) => assert_eq!(&cmd.payload[..cmd.length], &[0x00, 0x0A, 0xFB, 0, 0]),
+            other => panic!("expected FpgaCommand, got {:?}", other),
+        }
+
+        processor.parse(b"nozen.reportid(256)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Report ID must be 0-255\n");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_mode_reflects_dry_run_and_sensitivity() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.parse(b"nozen.mode\n", &mut cache);
+        assert_eq!(
+            response_text(&processor),
+            concat!("Mode:\n  Coordinates: relative\n  Dry-run: off\n  Humanizer: off\n  Sensitivity: 100%\n  Max speed: 127\n",
+                    "  Deadzone: 0\n  Wheel step: 1\n  Invert: x=off y=off\n  Canon: off\n  Strict: off\n  Track: on\n  Report ID: default\n")
+        );
+
+        processor.parse(b"nozen.dryrun(1)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Dry-run: on\n");
+        processor.parse(b"nozen.sensitivity(150)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Sensitivity: 150%\n");
+        processor.parse(b"nozen.invert(0,1)\n", &mut cache);
+
+        processor.parse(b"nozen.mode\n", &mut cache);
+        let text = response_text(&processor);
+        assert!(text.contains("  Dry-run: on\n"));
+        assert!(text.contains("  Sensitivity: 150%\n"));
+        assert!(text.contains("  Invert: x=off y=on\n"));
+
+        // An absolute-coordinate target switches the coordinate mode
+        processor.parse(&desc_line_with("[DESC:02:0]", ABS_MOUSE_DESC_HEX), &mut cache);
+        processor.parse(b"nozen.target(2,0)\n", &mut cache);
+        processor.parse(b"nozen.mode\n", &mut cache);
+        assert!(response_text(&processor).contains("  Coordinates: absolute\n"));
+
+        // and a relative mouse switches it back
+        processor.parse(&desc_line("[DESC:03:0]"), &mut cache);
+        processor.parse(b"nozen.target(3,0)\n", &mut cache);
+        processor.parse(b"nozen.mode\n", &mut cache);
+        assert!(response_text(&processor).contains("  Coordinates: relative\n"));
+    }
 
     #[test]
-    fn test_command_to_uart_frame_basic() {
-        let cmd = Command {
-            code: 0x11,
-            payload: [0x01, 0x02, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
-            length: 3,
-        };
+    fn test_dry_run_sends_nothing() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.dryrun(1)\n", &mut cache);
+
+        assert_eq!(processor.parse(b"nozen.move(10,5)\n", &mut cache), CommandType::Response);
+        assert_eq!(response_text(&processor), "[DRY] Not sent: CMD:11 LEN:5\n");
+        // State is still tracked
+        assert_eq!(processor.mouse_state.position(), (10, 5));
+
+        processor.parse(b"nozen.key.hold(4,100,20)\n", &mut cache);
+        processor.tick(200, 1);
+        assert!(processor.next_due().is_none());
+        assert!(processor.pending.is_empty());
+
+        processor.parse(b"nozen.dryrun(0)\n", &mut cache);
+        assert!(matches!(processor.parse(b"nozen.move(1,1)\n", &mut cache), CommandType::FpgaCommand(_)));
+    }
+
+    #[test]
+    fn test_canon_echoes_normalized_move() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.canon(on)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Canon: on\n");
+        processor.get_response();
+
+        let result = processor.parse(b"nozen.move( 10 , -5 )\n", &mut cache);
+        assert!(matches!(result, CommandType::FpgaCommand(_)));
+        assert_eq!(response_text(&processor), "[CANON] nozen.move(10,-5)\n");
+        processor.get_response();
+
+        processor.parse(b"nozen.canon(off)\n", &mut cache);
+        processor.get_response();
+        processor.parse(b"nozen.move(1,1)\n", &mut cache);
+        assert_eq!(processor.get_response(), None);
+    }
+
+    #[test]
+    fn test_codes_lists_every_fpga_code() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.parse_line(b"nozen.codes", &mut cache);
+        let text = response_text(&processor);
+        assert!(text.starts_with("[Codes] "));
+        assert!(text.contains("INJECT_MOUSE=0x11"));
+        assert!(text.contains("INJECT_KBD=0x10"));
+        assert!(!text.contains("0x12"));
+        for code in FpgaCode::ALL {
+            assert_eq!(FpgaCode::from_code(code as u8), Some(code));
+        }
+        assert_eq!(FpgaCode::from_code(0x00), None);
+    }
+
+    #[test]
+    fn test_canonical_forms() {
+        assert_eq!(Command::new(0x11, &[0x00, 0x0A, 0xFB, 0, 0]).canonical().as_str(), "nozen.move(10,-5)");
+        assert_eq!(Command::new(0x11, &[0x01, 0, 0, 0, 0]).canonical().as_str(), "nozen.mouse.buttons(0x01)");
+        assert_eq!(Command::new(0x11, &[0x05, 0x03, 0, 0, 0]).canonical().as_str(), "nozen.mouse.buttons(0x05) nozen.move(3,0)");
+        assert_eq!(Command::new(0x11, &[0, 0, 0, 0xFD, 0]).canonical().as_str(), "nozen.wheel(-3)");
+        assert_eq!(Command::new(0x10, &[0x02, 0, 0x04, 0x05, 0, 0, 0, 0]).canonical().as_str(), "nozen.mod(shift,1) nozen.key(4,1) nozen.key(5,1)");
+        assert_eq!(Command::new(0x10, &[0x11, 0, 0, 0, 0, 0, 0, 0]).canonical().as_str(), "nozen.mod(ctrl,1) nozen.mod(rctrl,1)");
+        assert_eq!(Command::new(0x10, &[0; 8]).canonical().as_str(), "nozen.releaseall");
+        assert_eq!(Command::new(0x20, &[1, 2]).canonical().as_str(), "[CMD:20] [LEN:2]");
+    }
+
+    #[test]
+    fn test_canonical_forms_replay_to_same_report() {
+        let mut cache = DescriptorCache::new();
+        let mut processor = CommandProcessor::new();
+        processor.parse(b"nozen.mod(shift,1)\n", &mut cache);
+        processor.parse(b"nozen.mod(ralt,1)\n", &mut cache);
+        let cases = [&b"nozen.key(4,1)\n"[..], b"nozen.left(1)\n", b"nozen.move(7,-2)\n", b"nozen.wheel(2)\n"];
+        for line in cases {
+            let sent = match processor.parse(line, &mut cache) {
+                CommandType::FpgaCommand(c) => c,
+                other => panic!("unexpected {:?}", other),
+            };
+
+            let mut replay = CommandProcessor::new();
+            let mut last = None;
+            for form in sent.canonical().split(' ') {
+                match replay.parse(format!("{}\n", form).as_bytes(), &mut cache) {
+                    CommandType::FpgaCommand(c) => last = Some(c),
+                    other => panic!("{} not parseable: {:?}", form, other),
+                }
+            }
+            assert_eq!(last.unwrap(), sent, "{}", sent.canonical());
+        }
+    }
+
+    #[test]
+    fn test_sensitivity_and_invert_scale_moves() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.sensitivity(150)\n", &mut cache);
+        processor.parse(b"nozen.invert(1,0)\n", &mut cache);
+
+        match processor.parse(b"nozen.move(10,-4)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.payload[1] as i8, -15);
+                assert_eq!(c.payload[2] as i8, -6);
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+        assert_eq!(processor.mouse_state.position(), (-15, -6));
+
+        processor.parse(b"nozen.sensitivity(0)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+        processor.parse(b"nozen.invert(2,0)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+        // 257 would wrap to 1 if parsed as an address byte
+        processor.tick(ERROR_COALESCE_MS, 1);
+        processor.parse(b"nozen.invert(0,257)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+        assert_eq!(processor.sensitivity, 150);
+        assert_eq!(processor.invert, (true, false));
+    }
+
+    #[test]
+    fn test_profile_times_without_sending() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
         
-        let frame = cmd.to_uart_frame();
+        assert_eq!(processor.parse(b"nozen.profile(nozen.move(10,5))\n", &mut cache), CommandType::Response);
+        assert_eq!(response_text(&processor), "[Profile] nozen.move(10,5): 0 cycles, 0 us\n");
         
-        // Check that frame starts with [CMD:
-        assert_eq!(&frame[0..5], b"[CMD:");
+        assert_eq!(processor.mouse_state.position(), (0, 0));
         
-        // Check command code is 11 (0x11)
-        assert_eq!(frame[5], b'1');
-        assert_eq!(frame[6], b'1');
+        // Steps the inner command queues are dropped too
+        processor.parse(b"nozen.maxspeed(20)\n", &mut cache);
+        processor.parse(b"nozen.profile(nozen.moveto(100,0))\n", &mut cache);
+        assert!(processor.pending.is_empty());
+        
+        // Held inputs, and steps queued before, come back as they were
+        processor.parse(b"nozen.left(1)\n", &mut cache);
+        processor.parse(b"nozen.keyseq(ctrl+k)\n", &mut cache);
+        processor.parse(b"nozen.profile(nozen.key.hold(4,100,20))\n", &mut cache);
+        assert_eq!(processor.held.buttons, 0x01);
+        assert_eq!(processor.pending.len(), 2);
+        
+        // Commands with effects beyond one frame are refused without running
+        processor.parse(b"nozen.profile(nozen.releaseall)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Not an FPGA command\n");
+        assert_eq!(processor.held.buttons, 0x01);
+        processor.tick(ERROR_COALESCE_MS, 1);
+        processor.parse(b"nozen.profile(nozen.usb.ids(1234,5678))\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Not an FPGA command\n");
+        assert!(!processor.config_dirty);
+        
+        // Profiled commands aren't counted as sent
+        assert_eq!(processor.cmd_stats.moves, 0);
+        
+        processor.parse(b"nozen.profile(nozen.move(1))\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Profile] nozen.move(1): 0 cycles, 0 us (not parsed)\n");
+        processor.parse(b"nozen.profile()\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage"));
     }
 
     #[test]
-    fn test_parse_int_positive() {
-        assert_eq!(parse_int(b"42"), Some(42));
-        assert_eq!(parse_int(b"0"), Some(0));
-        assert_eq!(parse_int(b"1234"), Some(1234));
+    fn test_deadzone_drops_small_moves() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.deadzone(2)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Deadzone: 2\n");
+        
+        assert_eq!(processor.parse(b"nozen.move(1,1)\n", &mut cache), CommandType::Response);
+        assert_eq!(response_text(&processor), "[OK] Inside deadzone, not sent\n");
+        assert_eq!(processor.mouse_state.position(), (0, 0));
+        
+        match processor.parse(b"nozen.move(3,0)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => {
+                assert_eq!(c.payload[1] as i8, 3);
+                assert_eq!(c.payload[2] as i8, 0);
+            }
+            _ => panic!("Expected FpgaCommand"),
+        }
+        assert_eq!(processor.mouse_state.position(), (3, 0));
+        
+        processor.parse(b"nozen.deadzone(128)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+        assert_eq!(processor.deadzone, 2);
     }
 
     #[test]
-    fn test_parse_int_negative() {
-        assert_eq!(parse_int(b"-42"), Some(-42));
-        assert_eq!(parse_int(b"-1"), Some(-1));
-        assert_eq!(parse_int(b"-999"), Some(-999));
+    fn test_device_sensitivity_follows_target() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(&desc_line("[DESC:02:00]"), &mut cache);
+        processor.parse(&desc_line("[DESC:03:00]"), &mut cache);
+        processor.parse(b"nozen.descriptor.sens(2,0,50)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Sensitivity addr=2 iface=0: 50%\n");
+        
+        let move_x = |processor: &mut CommandProcessor, cache: &mut DescriptorCache| {
+            match processor.parse(b"nozen.move(40,0)\n", cache) {
+                CommandType::FpgaCommand(c) => c.payload[1] as i8,
+                _ => panic!("Expected FpgaCommand"),
+            }
+        };
+        // No target: unscaled
+        assert_eq!(move_x(&mut processor, &mut cache), 40);
+        processor.parse(b"nozen.target(2,0)\n", &mut cache);
+        assert_eq!(move_x(&mut processor, &mut cache), 20);
+        // Combines with the global sensitivity
+        processor.parse(b"nozen.sensitivity(150)\n", &mut cache);
+        assert_eq!(move_x(&mut processor, &mut cache), 30);
+        processor.parse(b"nozen.target(3,0)\n", &mut cache);
+        assert_eq!(move_x(&mut processor, &mut cache), 60);
+        
+        processor.parse(b"nozen.descriptor.sens(9,0,50)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Descriptor not found\n");
+        processor.parse(b"nozen.descriptor.sens(2,0,0)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage"));
+        assert_eq!(cache.sensitivity(2, 0), Some(50));
     }
 
     #[test]
-    fn test_parse_int_with_whitespace() {
-        assert_eq!(parse_int(b"  42"), Some(42));
-        assert_eq!(parse_int(b"   -42"), Some(-42));
+    fn test_telemetry_covers_every_subsystem() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.tick(0, 0);
+        processor.tick(1000, 5000);
+        processor.latency.record(120);
+        processor.on_uart_line(b"[STATUS:OK]");
+        processor.parse(b"nozen.recoil.add(ak){0,1,10}\n", &mut cache);
+
+        let mut report = std::string::String::new();
+        let mut page = 1;
+        loop {
+            let cmd = std::format!("nozen.telemetry({})\n", page);
+            assert_eq!(processor.parse(cmd.as_bytes(), &mut cache), CommandType::Response);
+            assert!(processor.response_len <= processor.response_buffer.len());
+            let text = response_text(&processor);
+            assert!(text.starts_with("[Telemetry] ("));
+            report.push_str(text);
+            if !text.contains("More: nozen.telemetry(") {
+                break;
+            }
+            page += 1;
+        }
+        assert!(page > 1);
+
+        for section in ["Uptime: 1000 ms", "Loop: 5000 Hz", "Latency: min=120us", "Link: RX:1 lines/11B",
+                        "Cache: Devices:0/", "Recoil: 1/16 patterns"] {
+            assert!(report.contains(section), "missing {:?} in {:?}", section, report);
+        }
+
+        processor.parse(b"nozen.telemetry(9)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] No such telemetry page\n");
+    }
+
+    #[test]
+    fn test_uptime_reports_ms_and_hms() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        processor.tick(3_723_500, 1);
+        processor.parse(b"nozen.uptime\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Uptime: 3723500 ms (1:02:03)\n");
+
+        // The 32-bit ms clock wrapping doesn't reset uptime
+        processor.tick(u32::MAX, 2);
+        processor.tick(1000, 3);
+        processor.parse(b"nozen.uptime\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Uptime: 4294968296 ms (1193:02:48)\n");
+    }
+
+    #[test]
+    fn test_rtt_echoes_token_and_timestamp() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.tick(12_345, 1);
+
+        assert_eq!(processor.parse(b"nozen.rtt(a7f3)\n", &mut cache), CommandType::Response);
+        assert_eq!(response_text(&processor), "[RTT] a7f3 t=12345\n");
+        assert!(processor.pending.is_empty());
+
+        processor.parse(b"nozen.rtt\n", &mut cache);
+        assert_eq!(response_text(&processor), "[RTT] t=12345\n");
+
+        processor.parse(b"nozen.rtt(has space)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage: nozen.rtt"));
+    }
+
+    #[test]
+    fn test_human_profile_sets_parameters() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        assert_eq!(processor.humanizer.params().jitter, 0);
+
+        processor.parse(b"nozen.human(med)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Human: med (jitter=2 easing=8 variance=5ms)\n");
+        assert_eq!(processor.humanizer.profile, HumanProfile::Med);
+        assert_eq!(processor.humanizer.params().easing_steps, 8);
+
+        processor.parse(b"nozen.human(high)\n", &mut cache);
+        assert_eq!(processor.humanizer.params().jitter, 3);
+        assert_eq!(processor.humanizer.params().timing_variance_ms, 10);
+
+        processor.parse(b"nozen.human(extreme)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
+        assert_eq!(processor.humanizer.profile, HumanProfile::High);
+    }
+
+    #[test]
+    fn test_humanized_moveto_is_eased() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.human(low)\n", &mut cache);
+
+        let first = match processor.parse(b"nozen.moveto(100,0)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => c.payload[1] as i8 as i16,
+            _ => panic!("Expected FpgaCommand"),
+        };
+        assert_eq!(processor.pending.len(), 3);
+        let queued: i16 = processor.pending.iter().map(|s| s.data[1] as i8 as i16).sum();
+        assert_eq!(first + queued, 100);
+        assert_eq!(processor.mouse_state.position(), (100, 0));
+    }
+
+    #[test]
+    fn test_humanized_moveto_timing_varies() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.human(high)\n", &mut cache);
+        let variance = processor.humanizer.params().timing_variance_ms as u32;
+
+        processor.parse(b"nozen.moveto(120,0)\n", &mut cache);
+        let mut dues: Vec<u32> = processor.pending.iter().map(|s| s.due_ms).collect();
+        dues.sort();
+        let gaps: Vec<u32> = core::iter::once(dues[0]).chain(dues.windows(2).map(|w| w[1] - w[0])).collect();
+        assert!(gaps.iter().all(|&g| (EASING_INTERVAL_MS..=EASING_INTERVAL_MS + variance).contains(&g)));
+        assert!(gaps.iter().any(|&g| g != EASING_INTERVAL_MS));
+        assert_eq!(processor.mouse_state.position(), (120, 0));
     }
 
     #[test]
-    fn test_format_i16_positive() {
-        let mut buf = [0u8; 10];
-        let len = format_i16(123, &mut buf);
-        assert_eq!(&buf[..len], b"123");
-        
-        let len = format_i16(0, &mut buf);
-        assert_eq!(&buf[..len], b"0");
+    fn test_moveto_verify_corrects_acceleration() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+
+        let mut sent = match processor.parse(b"nozen.moveto.verify(100,-40)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => (c.payload[1] as i8 as i16, c.payload[2] as i8 as i16),
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(sent, (100, -40));
+
+        // The host only applies 80% of each move
+        for _ in 0..crate::settle::SETTLE_MAX_MOVES {
+            let line = format!("[DELTA:{},{}]", sent.0 * 4 / 5, sent.1 * 4 / 5);
+            processor.on_uart_line(line.as_bytes());
+            match processor.next_due() {
+                Some(c) => sent = (c.payload[1] as i8 as i16, c.payload[2] as i8 as i16),
+                None => break,
+            }
+        }
+        assert!(processor.settle.is_none());
+        assert_eq!(response_text(&processor), "[OK] Settled after 3 moves\n");
+        assert_eq!(processor.mouse_state.position(), (100, -40));
     }
 
     #[test]
-    fn test_format_i16_negative() {
-        let mut buf = [0u8; 10];
-        let len = format_i16(-123, &mut buf);
-        assert_eq!(&buf[..len], b"-123");
-        
-        let len = format_i16(-1, &mut buf);
-        assert_eq!(&buf[..len], b"-1");
+    fn test_maxspeed_splits_moveto() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(b"nozen.maxspeed(20)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Max speed: 20 px/report\n");
+
+        let first = match processor.parse(b"nozen.moveto(100,-30)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => (c.payload[1] as i8 as i16, c.payload[2] as i8 as i16),
+            _ => panic!("Expected FpgaCommand"),
+        };
+        let mut reports = vec![first];
+        reports.extend(processor.pending.iter().map(|s| (s.data[1] as i8 as i16, s.data[2] as i8 as i16)));
+        assert!(reports.len() >= 5);
+        assert!(reports.iter().all(|&(x, y)| x.abs() <= 20 && y.abs() <= 20));
+        assert_eq!(reports.iter().map(|r| r.0).sum::<i16>(), 100);
+        assert_eq!(reports.iter().map(|r| r.1).sum::<i16>(), -30);
+        assert_eq!(processor.mouse_state.position(), (100, -30));
+
+        // Eased segments are split too
+        processor.pending.clear();
+        processor.parse(b"nozen.human(low)\n", &mut cache);
+        let first = match processor.parse(b"nozen.moveto(0,-30)\n", &mut cache) {
+            CommandType::FpgaCommand(c) => c.payload[1] as i8 as i16,
+            _ => panic!("Expected FpgaCommand"),
+        };
+        assert!(processor.pending.len() >= 4);
+        let queued: Vec<i16> = processor.pending.iter().map(|s| s.data[1] as i8 as i16).collect();
+        assert!(queued.iter().chain([first].iter()).all(|x| x.abs() <= 20));
+        assert_eq!(first + queued.iter().sum::<i16>(), -100);
+
+        processor.parse(b"nozen.maxspeed(0)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR]"));
     }
 
     #[test]
-    fn test_command_processor_new() {
-        let processor = CommandProcessor::new();
-        assert_eq!(processor.index, 0);
-        assert_eq!(processor.response_len, 0);
+    fn test_recoil_run_queues_steps() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.tick(100, 0);
+        processor.parse(b"nozen.recoil.add(ak47){1,2,30,-3,4,50}\n", &mut cache);
+
+        processor.parse(b"nozen.recoil.run(ak47)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Running ak47 (2 steps)\n");
+        let steps: Vec<(u32, i8, i8)> = processor.pending.iter()
+            .map(|s| (s.due_ms, s.data[1] as i8, s.data[2] as i8))
+            .collect();
+        assert_eq!(steps, vec![(100, 1, 2), (130, -3, 4)]);
+
+        // With variance the gaps only grow, by at most the preset
+        processor.pending.clear();
+        processor.parse(b"nozen.human(high)\n", &mut cache);
+        processor.parse(b"nozen.recoil.run(ak47)\n", &mut cache);
+        let gap = processor.pending.iter().nth(1).unwrap().due_ms - 100;
+        assert!((30..=40).contains(&gap));
     }
 
     #[test]
-    fn test_parse_mouse_move() {
+    fn test_recoil_absolute_pattern_moves_to_targets() {
         let mut processor = CommandProcessor::new();
         let mut cache = DescriptorCache::new();
-        
-        let cmd = processor.parse(b"nozen.move(10,20)\n", &mut cache);
-        
-        match cmd {
-            CommandType::FpgaCommand(c) => {
-                assert_eq!(c.code, 0x11); // INJECT_MOUSE
-                assert_eq!(c.length, 5);
-                assert_eq!(c.payload[0], 0x00); // no buttons
-                assert_eq!(c.payload[1], 10); // x
-                assert_eq!(c.payload[2], 20); // y
-            }
-            _ => panic!("Expected FpgaCommand"),
-        }
-        
-        // Check that mouse state was updated
-        assert_eq!(processor.mouse_state.position(), (10, 20));
+        processor.tick(100, 0);
+        processor.parse(b"nozen.recoil.add(spray,abs){10,20,30,15,12,30,15,40,0}\n", &mut cache);
+        assert_eq!(response_text(&processor), "Recoil pattern added\n");
+        assert!(processor.recoil_manager.get_pattern("spray").unwrap().absolute);
+
+        // Jitter would pull the deltas off their targets; absolute steps skip it
+        processor.parse(b"nozen.human(high)\n", &mut cache);
+        processor.parse(b"nozen.recoil.run(spray)\n", &mut cache);
+        let deltas: Vec<(i8, i8)> = processor.pending.iter()
+            .map(|s| (s.data[1] as i8, s.data[2] as i8))
+            .collect();
+        assert_eq!(deltas, vec![(10, 20), (5, -8), (0, 28)]);
+        assert_eq!(processor.mouse_state.position(), (15, 40));
+
+        // A jump past max_speed is split like moveto instead of wrapping the i8
+        processor.pending.clear();
+        processor.parse(b"nozen.human(off)\n", &mut cache);
+        processor.parse(b"nozen.recoil.add(flick,abs){215,40,30,215,140,0}\n", &mut cache);
+        processor.parse(b"nozen.recoil.run(flick)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Running flick (2 steps)\n");
+        let steps: Vec<(u32, i8, i8)> = processor.pending.iter()
+            .map(|s| (s.due_ms, s.data[1] as i8, s.data[2] as i8))
+            .collect();
+        assert_eq!(steps, vec![(100, 100, 0), (100 + SPLIT_INTERVAL_MS, 100, 0), (130, 0, 100)]);
+        assert_eq!(processor.mouse_state.position(), (215, 140));
+
+        processor.parse(b"nozen.recoil.get(spray)\n", &mut cache);
+        assert_eq!(response_text(&processor), "spray,abs: {10,20,30,15,12,30,15,40,0}\n");
+
+        processor.parse(b"nozen.recoil.add(spray,rel){1,1,1}\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Invalid recoil.add format\n");
+        processor.parse(b"nozen.recoil.add(spray){1,1,1,2,2}\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Incomplete last step: only x,y given, need x,y,delay\n");
+        // Coalesced like any other error when repeated
+        assert_eq!(processor.parse(b"nozen.recoil.add(spray){1,1,1,2,2}\n", &mut cache), CommandType::NoOp);
     }
 
     #[test]
-    fn test_parse_mouse_move_negative() {
+    fn test_recoil_timeunit_scales_spacing() {
         let mut processor = CommandProcessor::new();
         let mut cache = DescriptorCache::new();
+        processor.tick(100, 0);
+        processor.parse(b"nozen.recoil.add(ak47){1,2,30,-3,4,50,5,6,0}\n", &mut cache);
         
-        let cmd = processor.parse(b"nozen.move(-5,-10)\n", &mut cache);
+        let dues = |processor: &mut CommandProcessor, cache: &mut DescriptorCache| {
+            processor.pending.clear();
+            processor.parse(b"nozen.recoil.run(ak47)\n", cache);
+            processor.pending.iter().map(|s| s.due_ms).collect::<Vec<u32>>()
+        };
+        assert_eq!(dues(&mut processor, &mut cache), vec![100, 130, 180]);
         
-        match cmd {
-            CommandType::FpgaCommand(c) => {
-                assert_eq!(c.code, 0x11);
-                assert_eq!(c.payload[1] as i8, -5);
-                assert_eq!(c.payload[2] as i8, -10);
-            }
-            _ => panic!("Expected FpgaCommand"),
-        }
+        processor.parse(b"nozen.recoil.timeunit(tick,2)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Recoil delays in ticks of 2 ms\n");
+        assert_eq!(dues(&mut processor, &mut cache), vec![100, 160, 260]);
         
-        assert_eq!(processor.mouse_state.position(), (-5, -10));
+        processor.parse(b"nozen.recoil.timeunit(tick)\n", &mut cache);
+        processor.parse(b"nozen.recoil.timeunit\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Recoil] Delays in ticks of 10 ms\n");
+        
+        processor.parse(b"nozen.recoil.timeunit(tick,0)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage"));
+        processor.parse(b"nozen.recoil.timeunit(ms)\n", &mut cache);
+        assert_eq!(dues(&mut processor, &mut cache), vec![100, 130, 180]);
     }
 
     #[test]
-    fn test_parse_mouse_moveto() {
+    fn test_recoil_chain_queues_patterns_back_to_back() {
         let mut processor = CommandProcessor::new();
         let mut cache = DescriptorCache::new();
-        
-        // Set initial position
-        processor.mouse_state.set_position(10, 20);
-        
-        // Move to absolute position
-        let cmd = processor.parse(b"nozen.moveto(50,100)\n", &mut cache);
-        
-        match cmd {
-            CommandType::FpgaCommand(c) => {
-                assert_eq!(c.code, 0x11);
-                // Should send delta: (50-10, 100-20) = (40, 80)
-                assert_eq!(c.payload[1], 40);
-                assert_eq!(c.payload[2], 80);
-            }
-            _ => panic!("Expected FpgaCommand"),
-        }
-        
-        // State should be updated to new position
-        assert_eq!(processor.mouse_state.position(), (50, 100));
+        processor.tick(100, 0);
+        processor.parse(b"nozen.recoil.add(a){1,2,30,3,4,50}\n", &mut cache);
+        processor.parse(b"nozen.recoil.add(b){5,6,10,7,8,20}\n", &mut cache);
+
+        processor.parse(b"nozen.recoil.chain(a,b)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Chained 2 patterns (4 steps)\n");
+        let steps: Vec<(u32, i8, i8)> = processor.pending.iter()
+            .map(|s| (s.due_ms, s.data[1] as i8, s.data[2] as i8))
+            .collect();
+        assert_eq!(steps, vec![(100, 1, 2), (130, 3, 4), (180, 5, 6), (190, 7, 8)]);
     }
 
     #[test]
-    fn test_parse_left_click_press() {
+    fn test_recoil_mirror_x_keeps_y_and_delay() {
         let mut processor = CommandProcessor::new();
         let mut cache = DescriptorCache::new();
-        
-        let cmd = processor.parse(b"nozen.left(1)\n", &mut cache);
-        
-        match cmd {
-            CommandType::FpgaCommand(c) => {
-                assert_eq!(c.code, 0x11);
-                assert_eq!(c.payload[0], 0x01); // left button mask
-                assert_eq!(c.payload[1], 0); // no movement
-                assert_eq!(c.payload[2], 0);
-            }
-            _ => panic!("Expected FpgaCommand"),
-        }
+        processor.parse(b"nozen.recoil.add(ak47){1,2,30,-4,5,60}\n", &mut cache);
+
+        processor.parse(b"nozen.recoil.mirror(ak47,x)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Mirrored x in ak47 (2 steps)\n");
+        let pattern = processor.recoil_manager.get_pattern("ak47").unwrap();
+        assert_eq!(pattern.step(0), Some((-1, 2, 30)));
+        assert_eq!(pattern.step(1), Some((4, 5, 60)));
+
+        processor.parse(b"nozen.recoil.mirror(ak47,z)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage"));
+        processor.parse(b"nozen.recoil.mirror(m4,y)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Pattern not found\n");
     }
 
     #[test]
-    fn test_parse_left_click_release() {
+    fn test_recoil_smooth_command() {
         let mut processor = CommandProcessor::new();
         let mut cache = DescriptorCache::new();
-        
-        let cmd = processor.parse(b"nozen.left(0)\n", &mut cache);
-        
-        match cmd {
-            CommandType::FpgaCommand(c) => {
-                assert_eq!(c.payload[0], 0x00); // no buttons
-            }
-            _ => panic!("Expected FpgaCommand"),
-        }
+        processor.parse(b"nozen.recoil.add(ak47){0,3,30,9,3,40,0,3,50}\n", &mut cache);
+
+        processor.parse(b"nozen.recoil.smooth(ak47,3)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Smoothed ak47 over 3 steps (3 steps)\n");
+        let pattern = processor.recoil_manager.get_pattern("ak47").unwrap();
+        assert_eq!(pattern.steps.as_slice(), &[3, 3, 30, 3, 3, 40, 3, 3, 50]);
+
+        processor.parse(b"nozen.recoil.smooth(ak47,0)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage"));
+        processor.parse(b"nozen.recoil.smooth(m4,3)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Pattern not found\n");
     }
 
     #[test]
-    fn test_parse_right_click() {
+    fn test_recoil_rescale_doubles_and_clamps() {
         let mut processor = CommandProcessor::new();
         let mut cache = DescriptorCache::new();
-        
-        let cmd = processor.parse(b"nozen.right(1)\n", &mut cache);
-        
-        match cmd {
-            CommandType::FpgaCommand(c) => {
-                assert_eq!(c.payload[0], 0x02); // right button mask
-            }
-            _ => panic!("Expected FpgaCommand"),
-        }
+        processor.parse(b"nozen.recoil.add(ak47){1,-2,30,20000,4,60}\n", &mut cache);
+
+        processor.parse(b"nozen.recoil.rescale(ak47,200)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Rescaled ak47 to 200% (1 values clamped)\n");
+        let pattern = processor.recoil_manager.get_pattern("ak47").unwrap();
+        assert_eq!(pattern.step(0), Some((2, -4, 30)));
+        assert_eq!(pattern.step(1), Some((i16::MAX, 8, 60)));
+
+        processor.parse(b"nozen.recoil.rescale(ak47,0)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage"));
     }
 
     #[test]
-    fn test_parse_middle_click() {
+    fn test_recoil_resample_interpolates_steps() {
         let mut processor = CommandProcessor::new();
         let mut cache = DescriptorCache::new();
-        
-        let cmd = processor.parse(b"nozen.middle(1)\n", &mut cache);
-        
-        match cmd {
-            CommandType::FpgaCommand(c) => {
-                assert_eq!(c.payload[0], 0x04); // middle button mask
-            }
-            _ => panic!("Expected FpgaCommand"),
-        }
+        processor.parse(b"nozen.recoil.add(ak47){0,10,20,4,-2,40}\n", &mut cache);
+
+        processor.parse(b"nozen.recoil.resample(ak47,2)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Resampled ak47: 2 -> 4 steps\n");
+        let pattern = processor.recoil_manager.get_pattern("ak47").unwrap();
+        let steps: Vec<_> = (0..4).map(|i| pattern.step(i).unwrap()).collect();
+        assert_eq!(steps, vec![(0, 6, 10), (0, 4, 10), (2, 0, 20), (2, -2, 20)]);
+
+        processor.parse(b"nozen.recoil.resample(ak47,6)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Pattern too long (4 steps)\n");
+        assert_eq!(processor.recoil_manager.get_pattern("ak47").unwrap().step_count(), 4);
     }
 
     #[test]
-    fn test_parse_wheel() {
+    fn test_recoil_chain_skips_missing_name() {
         let mut processor = CommandProcessor::new();
         let mut cache = DescriptorCache::new();
-        
-        let cmd = processor.parse(b"nozen.wheel(5)\n", &mut cache);
-        
-        match cmd {
-            CommandType::FpgaCommand(c) => {
-                assert_eq!(c.code, 0x11);
-                assert_eq!(c.payload[0], 0); // no buttons
-                assert_eq!(c.payload[1], 0); // no x movement
-                assert_eq!(c.payload[2], 0); // no y movement
-                assert_eq!(c.payload[3], 5); // wheel
-            }
-            _ => panic!("Expected FpgaCommand"),
-        }
+        processor.parse(b"nozen.recoil.add(a){1,2,30,3,4,50}\n", &mut cache);
+        processor.parse(b"nozen.recoil.add(b){5,6,10,7,8,20}\n", &mut cache);
+
+        processor.parse(b"nozen.recoil.chain(a,nope,b)\n", &mut cache);
+        assert_eq!(response_text(&processor),
+            "[WARN] Pattern not found: nope\n[OK] Chained 2 patterns (4 steps)\n");
+        let xs: Vec<i8> = processor.pending.iter().map(|s| s.data[1] as i8).collect();
+        assert_eq!(xs, vec![1, 3, 5, 7]);
     }
 
     #[test]
-    fn test_parse_wheel_negative() {
+    fn test_descriptor_expect_class() {
         let mut processor = CommandProcessor::new();
         let mut cache = DescriptorCache::new();
-        
-        let cmd = processor.parse(b"nozen.wheel(-3)\n", &mut cache);
-        
-        match cmd {
-            CommandType::FpgaCommand(c) => {
-                assert_eq!(c.payload[3] as i8, -3);
-            }
-            _ => panic!("Expected FpgaCommand"),
-        }
+        processor.parse(&desc_line("[DESC:01:0]"), &mut cache);
+
+        processor.parse(b"nozen.descriptor.expect(1,0,mouse)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] addr=1 iface=0 is mouse\n");
+
+        processor.parse(b"nozen.descriptor.expect(1,0,keyboard)\n", &mut cache);
+        let text = response_text(&processor);
+        assert!(text.starts_with("[ERROR] addr=1 iface=0 is not keyboard"));
+        assert!(text.contains("mouse"));
+
+        processor.parse(b"nozen.descriptor.expect(2,0,mouse)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Descriptor not found\n");
+        processor.parse(b"nozen.descriptor.expect(1,0,tablet)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Unknown class"));
     }
 
     #[test]
-    fn test_parse_getpos() {
+    fn test_uart_regs_response() {
         let mut processor = CommandProcessor::new();
         let mut cache = DescriptorCache::new();
-        
-        processor.mouse_state.set_position(100, 200);
-        
-        let cmd = processor.parse(b"nozen.getpos\n", &mut cache);
-        
-        match cmd {
-            CommandType::Response => {
-                assert!(processor.response_len > 0);
-                let response = &processor.response_buffer[..processor.response_len];
-                // Should contain "km.pos(100,200)\n"
-                assert!(response.starts_with(b"km.pos("));
-            }
-            _ => panic!("Expected Response"),
-        }
+
+        assert_eq!(processor.parse(b"nozen.uart.regs\n", &mut cache), CommandType::UartRegs);
+        let regs = SercomRegs { ctrla: 0x4031_0004, ctrlb: 0x0003_0000, baud: 0xFB15, status: 0x0004, intflag: 0x83 };
+        processor.finish_uart_regs(&regs);
+        assert_eq!(response_text(&processor),
+                   "[UART] CTRLA=0x40310004 CTRLB=0x00030000 BAUD=0xFB15 STATUS=0x0004 INTFLAG=0x83\n");
     }
 
     #[test]
-    fn test_parse_restart() {
+    fn test_keyseq_presses_and_releases_each_chord() {
+        use crate::hid::scancodes::*;
+
         let mut processor = CommandProcessor::new();
         let mut cache = DescriptorCache::new();
-        
-        let cmd = processor.parse(b"nozen.restart\n", &mut cache);
-        
-        match cmd {
-            CommandType::Restart => {}
-            _ => panic!("Expected Restart"),
-        }
+
+        processor.parse(b"nozen.keyseq(ctrl+k,ctrl+c)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Queued 2 chords\n");
+
+        let steps: heapless::Vec<(u32, [u8; 8]), 4> = processor.pending.iter()
+            .map(|s| (s.due_ms, s.data[..8].try_into().unwrap()))
+            .collect();
+        assert_eq!(&steps[..], &[
+            (0, [MOD_LCTRL, 0, K, 0, 0, 0, 0, 0]),
+            (CHORD_HOLD_MS, [0; 8]),
+            (CHORD_HOLD_MS + CHORD_GAP_MS, [MOD_LCTRL, 0, C, 0, 0, 0, 0, 0]),
+            (2 * CHORD_HOLD_MS + CHORD_GAP_MS, [0; 8]),
+        ]);
+
+        processor.parse(b"nozen.keyseq(ctrl+k,hyper+c)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[ERROR] Usage: nozen.keyseq"));
+        assert_eq!(processor.pending.len(), 4);
+
+        // Keys held with nozen.key stay down through the chord and its release
+        processor.pending.clear();
+        processor.parse(b"nozen.key(4,1)\n", &mut cache);
+        processor.parse(b"nozen.keyseq(shift+k)\n", &mut cache);
+        let steps: heapless::Vec<[u8; 8], 2> = processor.pending.iter()
+            .map(|s| s.data[..8].try_into().unwrap())
+            .collect();
+        assert_eq!(&steps[..], &[
+            [MOD_LSHIFT, 0, A, K, 0, 0, 0, 0],
+            [0, 0, A, 0, 0, 0, 0, 0],
+        ]);
     }
 
     #[test]
-    fn test_parse_unknown_command() {
+    fn test_strict_requires_cached_target() {
         let mut processor = CommandProcessor::new();
         let mut cache = DescriptorCache::new();
-        
-        let cmd = processor.parse(b"nozen.invalid()\n", &mut cache);
-        
-        match cmd {
-            CommandType::NoOp => {}
-            _ => panic!("Expected NoOp"),
-        }
+
+        processor.parse(b"nozen.strict(on)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[OK] Strict: on\n");
+
+        let result = processor.parse(b"nozen.move(10,5)\n", &mut cache);
+        assert_eq!(result, CommandType::Response);
+        assert!(response_text(&processor).starts_with("[ERROR] Strict: target descriptor not cached"));
+        assert_eq!(processor.mouse_state.position(), (0, 0));
+
+        // A target alone is not enough, its descriptor has to be cached
+        processor.parse(b"nozen.target(1,0)\n", &mut cache);
+        processor.tick(ERROR_COALESCE_MS, 1);
+        let result = processor.parse(b"nozen.move(10,5)\n", &mut cache);
+        assert_eq!(result, CommandType::Response);
+
+        processor.parse(&desc_line("[DESC:01:0]"), &mut cache);
+        let result = processor.parse(b"nozen.move(10,5)\n", &mut cache);
+        assert!(matches!(result, CommandType::FpgaCommand(_)));
+
+        // Off again, an uncached target injects as before
+        processor.parse(b"nozen.target(2,0)\n", &mut cache);
+        processor.parse(b"nozen.strict(off)\n", &mut cache);
+        let result = processor.parse(b"nozen.move(10,5)\n", &mut cache);
+        assert!(matches!(result, CommandType::FpgaCommand(_)));
     }
 
     #[test]
-    fn test_parse_multiline() {
+    fn test_strict_refuses_timed_injection() {
         let mut processor = CommandProcessor::new();
         let mut cache = DescriptorCache::new();
-        
-        // First line
-        let cmd1 = processor.parse(b"nozen.move(10,20)\n", &mut cache);
-        assert!(matches!(cmd1, CommandType::FpgaCommand(_)));
-        
-        // Second line
-        let cmd2 = processor.parse(b"nozen.left(1)\n", &mut cache);
-        assert!(matches!(cmd2, CommandType::FpgaCommand(_)));
+        processor.parse(b"nozen.strict(on)\n", &mut cache);
+
+        for (i, line) in [
+            &b"nozen.bench(10)\n"[..],
+            b"nozen.frame(nozen.move(10,5))\n",
+            b"nozen.wander(3,10)\n",
+            b"nozen.antiidle(1000,1)\n",
+        ].iter().enumerate() {
+            processor.tick(ERROR_COALESCE_MS * (i as u32 + 1), 1);
+            let result = processor.parse(line, &mut cache);
+            assert_eq!(result, CommandType::Response);
+            assert!(response_text(&processor).starts_with("[ERROR] Strict: target descriptor not cached"));
+        }
+        assert!(processor.wander.is_none());
+        assert_eq!(processor.anti_idle_ms, 0);
+        processor.tick(10_000, 1);
+        assert!(processor.pending.is_empty());
     }
 
     #[test]
-    fn test_parse_partial_then_complete() {
+    fn test_descriptor_get_shows_truncation() {
         let mut processor = CommandProcessor::new();
         let mut cache = DescriptorCache::new();
         
-        // Send partial command
-        let cmd1 = processor.parse(b"nozen.move(", &mut cache);
-        assert!(matches!(cmd1, CommandType::NoOp));
+        processor.parse(&desc_line("[DESC:01:0]"), &mut cache);
+        processor.parse(b"nozen.descriptor.get(1,0)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[Descriptor] addr=1 iface=0\n  Type: Mouse Gamepad \n  Fields: 5\n");
         
-        // Complete the command
-        let cmd2 = processor.parse(b"10,20)\n", &mut cache);
-        assert!(matches!(cmd2, CommandType::FpgaCommand(_)));
+        // 70 one-bit vendor fields, then X/Y
+        processor.parse(&desc_line_with("[DESC:02:0]", "0600FF0901750195468102050109300931750895028106"), &mut cache);
+        processor.parse(b"nozen.descriptor.get(2,0)\n", &mut cache);
+        assert_eq!(response_text(&processor),
+                   "[Descriptor] addr=2 iface=0\n  Type: Mouse \n  Fields: 64 (truncated, more declared)\n");
     }
 
     #[test]
-    fn test_hex_digit() {
-        assert_eq!(hex_digit(0), b'0');
-        assert_eq!(hex_digit(9), b'9');
-        assert_eq!(hex_digit(10), b'A');
-        assert_eq!(hex_digit(15), b'F');
-    }
+    fn test_descriptor_info_line() {
+        let mut processor = CommandProcessor::new();
+        let mut cache = DescriptorCache::new();
+        processor.parse(&desc_line("[DESC:01:0]"), &mut cache);
 
-    #[test]
-    fn test_hex_to_nibble() {
-        assert_eq!(hex_to_nibble(b'0'), Some(0));
-        assert_eq!(hex_to_nibble(b'9'), Some(9));
-        assert_eq!(hex_to_nibble(b'A'), Some(10));
-        assert_eq!(hex_to_nibble(b'F'), Some(15));
-        assert_eq!(hex_to_nibble(b'a'), Some(10));
-        assert_eq!(hex_to_nibble(b'f'), Some(15));
-        assert_eq!(hex_to_nibble(b'G'), None);
-    }
+        assert_eq!(cache.raw(1, 0).unwrap().len(), MOUSE_DESC_HEX.len() / 2);
+        assert_eq!(cache.get(1, 0).unwrap().fields.len(), 5);
+        processor.parse(b"nozen.descriptor.info(1,0)\n", &mut cache);
+        assert!(response_text(&processor).starts_with("[Info] addr=1 iface=0 bytes=50 fields=5 class=mouse"));
 
-    #[test]
-    fn test_parse_u8_from_slice() {
-        assert_eq!(parse_u8_from_slice(b"42"), Some(42));
-        assert_eq!(parse_u8_from_slice(b"0"), Some(0));
-        assert_eq!(parse_u8_from_slice(b"255"), Some(255));
-        assert_eq!(parse_u8_from_slice(b"abc"), None);
+        processor.parse(b"nozen.descriptor.info(2,0)\n", &mut cache);
+        assert_eq!(response_text(&processor), "[ERROR] Descriptor not found\n");
     }
 }
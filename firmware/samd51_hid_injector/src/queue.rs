@@ -0,0 +1,227 @@
+//! Pending Step Queue
+//! Holds reports scheduled for a later millisecond so timed sequences
+//! (key repeat, macros, ...) never block the main loop
+
+use heapless::Vec;
+
+use crate::codes::FpgaCode;
+
+/// Maximum number of steps that can be pending at once
+pub const MAX_QUEUE_DEPTH: usize = 64;
+
+/// Largest report payload a queued step can carry (keyboard report)
+pub const MAX_STEP_PAYLOAD: usize = 8;
+
+/// What a queued report does, as summarized by nozen.queue.list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepKind {
+    Move,
+    Wheel,
+    /// Buttons, keys or modifiers held
+    Press,
+    /// Empty mouse or keyboard report
+    Release,
+    Other,
+}
+
+impl StepKind {
+    pub const ALL: [StepKind; 5] = [StepKind::Move, StepKind::Wheel, StepKind::Press, StepKind::Release, StepKind::Other];
+
+    /// Kind of a report with this command code and payload
+    pub fn of(code: u8, payload: &[u8]) -> StepKind {
+        match (FpgaCode::from_code(code), payload) {
+            (Some(FpgaCode::InjectMouse), &[buttons, dx, dy, wheel, pan, ..]) => {
+                if dx != 0 || dy != 0 {
+                    StepKind::Move
+                } else if wheel != 0 || pan != 0 {
+                    StepKind::Wheel
+                } else if buttons != 0 {
+                    StepKind::Press
+                } else {
+                    StepKind::Release
+                }
+            }
+            (Some(FpgaCode::InjectKbd), _) if payload.iter().all(|&b| b == 0) => StepKind::Release,
+            (Some(FpgaCode::InjectKbd), _) => StepKind::Press,
+            _ => StepKind::Other,
+        }
+    }
+
+    /// Name for `count` steps of this kind, e.g. "1 move" / "3 moves"
+    pub fn name(self, count: usize) -> &'static str {
+        let (one, many) = match self {
+            StepKind::Move => ("move", "moves"),
+            StepKind::Wheel => ("wheel", "wheels"),
+            StepKind::Press => ("press", "presses"),
+            StepKind::Release => ("release", "releases"),
+            StepKind::Other => ("other", "other"),
+        };
+        if count == 1 { one } else { many }
+    }
+}
+
+/// A report waiting for its due time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PendingStep {
+    pub due_ms: u32,
+    pub code: u8,
+    pub data: [u8; MAX_STEP_PAYLOAD],
+    pub length: u8,
+    /// Set from the report when the step is made
+    pub kind: StepKind,
+}
+
+impl PendingStep {
+    pub fn new(due_ms: u32, code: u8, payload: &[u8]) -> Self {
+        let length = payload.len().min(MAX_STEP_PAYLOAD);
+        let mut data = [0u8; MAX_STEP_PAYLOAD];
+        data[..length].copy_from_slice(&payload[..length]);
+        PendingStep {
+            due_ms,
+            code,
+            data,
+            length: length as u8,
+            kind: StepKind::of(code, payload),
+        }
+    }
+
+    /// True once `now_ms` has reached the due time (wrap-safe)
+    pub fn is_due(&self, now_ms: u32) -> bool {
+        (now_ms.wrapping_sub(self.due_ms) as i32) >= 0
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum QueueError {
+    Full,
+}
+
+/// Steps kept in insertion order; each command enqueues its steps in due order
+#[derive(Clone, Default)]
+pub struct StepQueue {
+    steps: Vec<PendingStep, MAX_QUEUE_DEPTH>,
+    /// Pushes and reservations turned away because the queue was full
+    rejected: u32,
+}
+
+impl StepQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, step: PendingStep) -> Result<(), QueueError> {
+        let result = self.steps.push(step).map_err(|_| QueueError::Full);
+        if result.is_err() {
+            self.rejected = self.rejected.wrapping_add(1);
+        }
+        result
+    }
+
+    /// Check that `count` more steps fit before queueing any of them, so a
+    /// sequence is queued whole or not at all. A miss counts as rejected.
+    pub fn reserve(&mut self, count: usize) -> Result<(), QueueError> {
+        if count > self.free() {
+            self.rejected = self.rejected.wrapping_add(1);
+            return Err(QueueError::Full);
+        }
+        Ok(())
+    }
+
+    /// Pushes and reservations refused since boot
+    pub fn rejected(&self) -> u32 {
+        self.rejected
+    }
+
+    /// Remove and return the oldest step that is due at `now_ms`
+    pub fn pop_due(&mut self, now_ms: u32) -> Option<PendingStep> {
+        let index = self.steps.iter().position(|step| step.is_due(now_ms))?;
+        Some(self.steps.remove(index))
+    }
+
+    /// Number of steps that can still be queued
+    pub fn free(&self) -> usize {
+        MAX_QUEUE_DEPTH - self.steps.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PendingStep> {
+        self.steps.iter()
+    }
+
+    /// Steps of the given kind still queued
+    pub fn count(&self, kind: StepKind) -> usize {
+        self.steps.iter().filter(|step| step.kind == kind).count()
+    }
+
+    /// Drop steps queued after the first `len`
+    pub fn truncate(&mut self, len: usize) {
+        self.steps.truncate(len);
+    }
+
+    pub fn clear(&mut self) {
+        self.steps.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pop_due_in_order() {
+        let mut queue = StepQueue::new();
+        queue.push(PendingStep::new(20, 0x10, &[1])).unwrap();
+        queue.push(PendingStep::new(40, 0x10, &[2])).unwrap();
+
+        assert_eq!(queue.pop_due(10), None);
+        assert_eq!(queue.pop_due(45).unwrap().data[0], 1);
+        assert_eq!(queue.pop_due(45).unwrap().data[0], 2);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_due_across_wrap() {
+        let step = PendingStep::new(5, 0x10, &[]);
+        assert!(!step.is_due(u32::MAX - 5));
+        assert!(step.is_due(5));
+        assert!(step.is_due(6));
+    }
+
+    #[test]
+    fn test_steps_tagged_by_kind() {
+        let mut queue = StepQueue::new();
+        queue.push(PendingStep::new(0, 0x11, &[0, 3, 0xFD, 0, 0])).unwrap();
+        queue.push(PendingStep::new(0, 0x11, &[1, 3, 0, 0, 0])).unwrap();
+        queue.push(PendingStep::new(0, 0x11, &[0, 0, 0, 0xFF, 0])).unwrap();
+        queue.push(PendingStep::new(0, 0x11, &[1, 0, 0, 0, 0])).unwrap();
+        queue.push(PendingStep::new(0, 0x11, &[0; 5])).unwrap();
+        queue.push(PendingStep::new(0, 0x10, &[0, 0, 4, 0, 0, 0, 0, 0])).unwrap();
+        queue.push(PendingStep::new(0, 0x10, &[0; 8])).unwrap();
+        queue.push(PendingStep::new(0, 0x20, &[1])).unwrap();
+
+        let counts = StepKind::ALL.map(|kind| queue.count(kind));
+        assert_eq!(counts, [2, 1, 2, 2, 1]);
+        assert_eq!(StepKind::Press.name(2), "presses");
+        assert_eq!(StepKind::Move.name(1), "move");
+    }
+
+    #[test]
+    fn test_push_when_full() {
+        let mut queue = StepQueue::new();
+        for i in 0..MAX_QUEUE_DEPTH {
+            queue.push(PendingStep::new(i as u32, 0x10, &[])).unwrap();
+        }
+        assert_eq!(queue.free(), 0);
+        assert_eq!(queue.push(PendingStep::new(0, 0x10, &[])), Err(QueueError::Full));
+        assert_eq!(queue.reserve(1), Err(QueueError::Full));
+        assert_eq!(queue.reserve(0), Ok(()));
+        assert_eq!(queue.rejected(), 2);
+    }
+}
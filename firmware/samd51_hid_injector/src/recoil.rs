@@ -4,54 +4,225 @@
 use heapless::{String, Vec};
 use heapless::FnvIndexMap;
 
-const MAX_PATTERNS: usize = 16;
-const MAX_PATTERN_NAME_LEN: usize = 32;
-const MAX_PATTERN_STEPS: usize = 64;
+pub const MAX_PATTERNS: usize = 16;
+pub const MAX_PATTERN_NAME_LEN: usize = 32;
+pub const MAX_PATTERN_STEPS: usize = 64;
+
+/// Tick length assumed by nozen.recoil.timeunit(tick) when none is given
+pub const RECOIL_TICK_MS: u16 = 10;
+
+/// How step delays are read during playback
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeUnit {
+    /// Delays are milliseconds
+    Millis,
+    /// Delays count ticks of this many milliseconds
+    Ticks(u16),
+}
+
+impl TimeUnit {
+    /// A step delay in milliseconds; negative delays count as 0
+    pub fn to_ms(self, delay: i16) -> u32 {
+        let delay = delay.max(0) as u32;
+        match self {
+            TimeUnit::Millis => delay,
+            TimeUnit::Ticks(ms) => delay * ms as u32,
+        }
+    }
+}
+
+/// Why a pattern couldn't be added
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecoilError {
+    NotTriplets,
+    TooLong,
+    NameTooLong,
+    TooManySteps,
+    StorageFull,
+}
+
+impl RecoilError {
+    /// Message shown in command responses
+    pub fn message(self) -> &'static str {
+        match self {
+            RecoilError::NotTriplets => "Pattern must be x,y,delay triplets",
+            RecoilError::TooLong => "Pattern too long",
+            RecoilError::NameTooLong => "Name too long",
+            RecoilError::TooManySteps => "Too many steps",
+            RecoilError::StorageFull => "Pattern storage full",
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct RecoilPattern {
     pub name: String<MAX_PATTERN_NAME_LEN>,
     /// Pattern data: triplets of (x, y, delay_ms)
     pub steps: Vec<i16, MAX_PATTERN_STEPS>,
+    /// x/y are screen positions to move to rather than relative deltas
+    pub absolute: bool,
+}
+
+impl RecoilPattern {
+    /// Number of (x, y, delay) triplets
+    pub fn step_count(&self) -> usize {
+        self.steps.len() / 3
+    }
+
+    /// Triplet at `index`, if it exists
+    pub fn step(&self, index: usize) -> Option<(i16, i16, i16)> {
+        let base = index.checked_mul(3)?;
+        let triplet = self.steps.get(base..base + 3)?;
+        Some((triplet[0], triplet[1], triplet[2]))
+    }
+
+    /// Overwrite the triplet at `index` in place
+    pub fn set_step(&mut self, index: usize, x: i16, y: i16, delay: i16) -> Result<(), &'static str> {
+        if index >= self.step_count() {
+            return Err("Step index out of range");
+        }
+        let base = index * 3;
+        self.steps[base] = x;
+        self.steps[base + 1] = y;
+        self.steps[base + 2] = delay;
+        Ok(())
+    }
+
+    /// Scale every x and y by `percent`, rounding to nearest and clamping to i16
+    /// Delays are kept. Returns how many values had to be clamped.
+    pub fn rescale(&mut self, percent: u16) -> usize {
+        let mut clamped = 0;
+        for triplet in self.steps.chunks_exact_mut(3) {
+            for value in &mut triplet[..2] {
+                let scaled = *value as i32 * percent as i32;
+                let rounded = (scaled + scaled.signum() * 50) / 100;
+                if rounded < i16::MIN as i32 || rounded > i16::MAX as i32 {
+                    clamped += 1;
+                }
+                *value = rounded.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+            }
+        }
+        clamped
+    }
+
+    /// Replace each x and y with the average over `window` triplets centred on it,
+    /// rounding to nearest. Past either end the first or last triplet is
+    /// repeated. Delays and the step count are kept.
+    pub fn smooth(&mut self, window: usize) -> Result<(), &'static str> {
+        if window == 0 {
+            return Err("Window must be at least 1");
+        }
+        let original = self.steps.clone();
+        let count = self.step_count() as i32;
+        let before = (window as i32 - 1) / 2;
+        for (index, triplet) in self.steps.chunks_exact_mut(3).enumerate() {
+            for axis in 0..2 {
+                let sum: i32 = (0..window as i32)
+                    .map(|offset| (index as i32 + offset - before).clamp(0, count - 1))
+                    .map(|neighbour| original[neighbour as usize * 3 + axis] as i32)
+                    .sum();
+                let half = sum.signum() * window as i32 / 2;
+                triplet[axis] = ((sum + half) / window as i32) as i16;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace every triplet with `factor` smaller ones, interpolating x and y
+    /// between the neighbouring triplets so the speed ramps instead of stepping.
+    /// Each triplet's parts still sum to it and its delay is split evenly, so the
+    /// pattern covers the same distance in the same time.
+    pub fn resample(&mut self, factor: usize) -> Result<(), &'static str> {
+        if factor == 0 {
+            return Err("Factor must be at least 1");
+        }
+        if self.steps.len().saturating_mul(factor) > MAX_PATTERN_STEPS {
+            return Err("Pattern too long");
+        }
+
+        // Part k of triplet i is centred (2k + 1 - f) / 2f of a step away from the
+        // triplet's centre; x and y follow the line through the neighbouring
+        // triplets' values there. Working in units of 1/4f² keeps it exact, and
+        // rounding the running totals rather than each part keeps the sums.
+        let f = factor as i64;
+        let unit = 4 * f * f;
+        let count = self.step_count();
+        let value = |i: usize, axis: usize| self.steps[i * 3 + axis] as i64;
+
+        let mut resampled: Vec<i16, MAX_PATTERN_STEPS> = Vec::new();
+        let mut totals = [0i64; 3];
+        let mut emitted = [0i64; 3];
+        for i in 0..count {
+            let prev = i.saturating_sub(1);
+            let next = (i + 1).min(count - 1);
+            for k in 0..f {
+                for axis in 0..3 {
+                    let slope = if axis < 2 { value(next, axis) - value(prev, axis) } else { 0 };
+                    totals[axis] += 4 * f * value(i, axis) + (2 * k + 1 - f) * slope;
+                    let rounded = (2 * totals[axis] + unit).div_euclid(2 * unit);
+                    let _ = resampled.push((rounded - emitted[axis]) as i16);
+                    emitted[axis] = rounded;
+                }
+            }
+        }
+        self.steps = resampled;
+        Ok(())
+    }
+
+    /// Negate the x and/or y component of every triplet, keeping delays
+    pub fn mirror(&mut self, x: bool, y: bool) {
+        for triplet in self.steps.chunks_exact_mut(3) {
+            if x {
+                triplet[0] = triplet[0].saturating_neg();
+            }
+            if y {
+                triplet[1] = triplet[1].saturating_neg();
+            }
+        }
+    }
 }
 
 pub struct RecoilManager {
     patterns: FnvIndexMap<String<MAX_PATTERN_NAME_LEN>, RecoilPattern, MAX_PATTERNS>,
+    /// Unit of every pattern's delays, applied at playback
+    pub time_unit: TimeUnit,
 }
 
 impl RecoilManager {
     pub fn new() -> Self {
         RecoilManager {
             patterns: FnvIndexMap::new(),
+            time_unit: TimeUnit::Millis,
         }
     }
 
     /// Add or update a recoil pattern
-    pub fn add_pattern(&mut self, name: &str, steps: &[i16]) -> Result<(), &'static str> {
+    pub fn add_pattern(&mut self, name: &str, steps: &[i16]) -> Result<(), RecoilError> {
         // Validate pattern length (must be multiple of 3: x, y, delay)
         if steps.len() % 3 != 0 {
-            return Err("Pattern must be x,y,delay triplets");
+            return Err(RecoilError::NotTriplets);
         }
 
         if steps.len() > MAX_PATTERN_STEPS {
-            return Err("Pattern too long");
+            return Err(RecoilError::TooLong);
         }
 
         let mut pattern_name = String::new();
-        pattern_name.push_str(name).map_err(|_| "Name too long")?;
+        pattern_name.push_str(name).map_err(|_| RecoilError::NameTooLong)?;
 
         let mut pattern_steps = Vec::new();
         for &step in steps {
-            pattern_steps.push(step).map_err(|_| "Too many steps")?;
+            pattern_steps.push(step).map_err(|_| RecoilError::TooManySteps)?;
         }
 
         let pattern = RecoilPattern {
             name: pattern_name.clone(),
             steps: pattern_steps,
+            absolute: false,
         };
 
         self.patterns.insert(pattern_name, pattern)
-            .map_err(|_| "Pattern storage full")?;
+            .map_err(|_| RecoilError::StorageFull)?;
 
         Ok(())
     }
@@ -76,6 +247,16 @@ impl RecoilManager {
         }
     }
 
+    /// Get a pattern by name for in-place editing
+    pub fn get_pattern_mut(&mut self, name: &str) -> Option<&mut RecoilPattern> {
+        let mut key = String::new();
+        if key.push_str(name).is_ok() {
+            self.patterns.get_mut(&key)
+        } else {
+            None
+        }
+    }
+
     /// List all pattern names
     pub fn list_names(&self) -> impl Iterator<Item = &str> {
         self.patterns.keys().map(|s| s.as_str())
@@ -86,57 +267,90 @@ impl RecoilManager {
         self.patterns.values()
     }
 
+    /// Remove all patterns
+    pub fn clear(&mut self) {
+        self.patterns.clear();
+    }
+
     /// Get pattern count
     pub fn count(&self) -> usize {
         self.patterns.len()
     }
 }
 
+/// Returned by parse_recoil_add when the name or braces can't be found
+pub const RECOIL_ADD_FORMAT_ERROR: &str = "Invalid recoil.add format";
+
 /// Parse recoil pattern from command string
 /// Format: "nozen.recoil.add(name){x,y,delay,x,y,delay,...}"
-pub fn parse_recoil_add(line: &[u8]) -> Option<(&[u8], Vec<i16, MAX_PATTERN_STEPS>)> {
-    // Find the opening paren for name
-    let args_start = b"nozen.recoil.add(".len();
-    if line.len() < args_start {
-        return None;
-    }
-    
-    let args = &line[args_start..];
+/// Err names the problem: RECOIL_ADD_FORMAT_ERROR, or what is wrong with the values.
+pub fn parse_recoil_add(line: &[u8]) -> Result<(&[u8], Vec<i16, MAX_PATTERN_STEPS>), &'static str> {
+    let args = line.strip_prefix(b"nozen.recoil.add(").ok_or(RECOIL_ADD_FORMAT_ERROR)?;
     
     // Find closing paren for name
-    let name_end = args.iter().position(|&c| c == b')')?;
+    let name_end = args.iter().position(|&c| c == b')').ok_or(RECOIL_ADD_FORMAT_ERROR)?;
     let name = &args[..name_end];
     
     // Find opening brace for pattern data
-    let pattern_start = args[name_end+1..].iter().position(|&c| c == b'{')?;
+    let pattern_start = args[name_end+1..].iter().position(|&c| c == b'{').ok_or(RECOIL_ADD_FORMAT_ERROR)?;
     let pattern_data = &args[name_end + 1 + pattern_start + 1..];
     
     // Find closing brace
-    let pattern_end = pattern_data.iter().position(|&c| c == b'}')?;
+    let pattern_end = pattern_data.iter().position(|&c| c == b'}').ok_or(RECOIL_ADD_FORMAT_ERROR)?;
     let pattern_str = &pattern_data[..pattern_end];
     
-    // Parse comma-separated integers
+    Ok((name, parse_steps(pattern_str)?))
+}
+
+/// Split the "name" or "name,abs" argument of recoil.add into the name and
+/// whether the pattern is absolute; None for any other suffix
+pub fn split_pattern_flags(arg: &[u8]) -> Option<(&[u8], bool)> {
+    match arg.iter().position(|&c| c == b',') {
+        None => Some((arg, false)),
+        Some(comma) if &arg[comma + 1..] == b"abs" => Some((&arg[..comma], true)),
+        Some(_) => None,
+    }
+}
+
+/// Parse one "name:x,y,delay,..." segment of a recoil.batch command
+pub fn parse_batch_segment(segment: &[u8]) -> Option<(&[u8], Vec<i16, MAX_PATTERN_STEPS>)> {
+    let colon = segment.iter().position(|&c| c == b':')?;
+    let name = &segment[..colon];
+    if name.is_empty() {
+        return None;
+    }
+    Some((name, parse_steps(&segment[colon + 1..]).ok()?))
+}
+
+/// Parse comma-separated step values ("x,y,delay,...")
+/// Whitespace around values and a single trailing comma are ignored. A final
+/// step with fewer than three values is an error rather than being dropped.
+fn parse_steps(pattern_str: &[u8]) -> Result<Vec<i16, MAX_PATTERN_STEPS>, &'static str> {
     let mut steps = Vec::new();
-    let mut start = 0;
+    let pattern_str = pattern_str.trim_ascii();
+    let pattern_str = pattern_str.strip_suffix(b",").unwrap_or(pattern_str);
+    if pattern_str.trim_ascii().is_empty() {
+        return Ok(steps);
+    }
     
-    for i in 0..pattern_str.len() {
-        if pattern_str[i] == b',' || i == pattern_str.len() - 1 {
-            let end = if pattern_str[i] == b',' { i } else { i + 1 };
-            let num_str = &pattern_str[start..end];
-            
-            if let Some(value) = parse_i16(num_str) {
-                if steps.push(value).is_err() {
-                    return None; // Too many steps
-                }
-            } else {
-                return None; // Parse error
-            }
-            
-            start = i + 1;
+    for value in pattern_str.split(|&c| c == b',') {
+        let value = value.trim_ascii();
+        if value.is_empty() {
+            return Err("Empty value in pattern");
+        }
+        let digits = value.strip_prefix(b"-").unwrap_or(value);
+        if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+            return Err("Pattern values must be integers");
         }
+        let value = parse_i16(value).ok_or("Pattern values must be integers")?;
+        steps.push(value).map_err(|_| "Pattern too long")?;
     }
     
-    Some((name, steps))
+    match steps.len() % 3 {
+        1 => Err("Incomplete last step: only x given, need x,y,delay"),
+        2 => Err("Incomplete last step: only x,y given, need x,y,delay"),
+        _ => Ok(steps),
+    }
 }
 
 /// Parse recoil pattern name from delete/get/run command
@@ -215,7 +429,8 @@ mod tests {
         let invalid_steps = [10, -5, 100, 20]; // 4 elements, not divisible by 3
         let result = manager.add_pattern("invalid", &invalid_steps);
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Pattern must be x,y,delay triplets");
+        assert_eq!(result.unwrap_err(), RecoilError::NotTriplets);
+        assert_eq!(result.unwrap_err().message(), "Pattern must be x,y,delay triplets");
     }
 
     #[test]
@@ -303,12 +518,108 @@ mod tests {
         assert_eq!(manager.count(), 1); // Still only one pattern
     }
 
+    #[test]
+    fn test_step_read_and_set() {
+        let mut manager = RecoilManager::new();
+        manager.add_pattern("edit", &[1, 2, 30, 4, 5, 60]).unwrap();
+
+        let pattern = manager.get_pattern("edit").unwrap();
+        assert_eq!(pattern.step_count(), 2);
+        assert_eq!(pattern.step(1), Some((4, 5, 60)));
+        assert_eq!(pattern.step(2), None);
+
+        let pattern = manager.get_pattern_mut("edit").unwrap();
+        assert!(pattern.set_step(0, -7, 8, 90).is_ok());
+        assert_eq!(pattern.set_step(2, 0, 0, 0), Err("Step index out of range"));
+        assert_eq!(manager.get_pattern("edit").unwrap().step(0), Some((-7, 8, 90)));
+    }
+
+    #[test]
+    fn test_time_unit_to_ms() {
+        assert_eq!(TimeUnit::Millis.to_ms(30), 30);
+        assert_eq!(TimeUnit::Ticks(RECOIL_TICK_MS).to_ms(3), 30);
+        assert_eq!(TimeUnit::Ticks(1000).to_ms(i16::MAX), 32_767_000);
+        assert_eq!(TimeUnit::Ticks(2).to_ms(-5), 0);
+    }
+
+    #[test]
+    fn test_mirror_negates_chosen_axis() {
+        let mut manager = RecoilManager::new();
+        manager.add_pattern("m", &[1, -2, 30, -4, 5, 60, i16::MIN, 0, 10]).unwrap();
+
+        let pattern = manager.get_pattern_mut("m").unwrap();
+        pattern.mirror(true, false);
+        assert_eq!(pattern.steps.as_slice(), &[-1, -2, 30, 4, 5, 60, i16::MAX, 0, 10]);
+
+        pattern.mirror(true, true);
+        assert_eq!(pattern.step(0), Some((1, 2, 30)));
+        assert_eq!(pattern.step(1), Some((-4, -5, 60)));
+    }
+
+    #[test]
+    fn test_rescale_rounds_and_clamps() {
+        let mut manager = RecoilManager::new();
+        manager.add_pattern("s", &[3, -5, 30, 20000, -20000, 60]).unwrap();
+
+        let pattern = manager.get_pattern_mut("s").unwrap();
+        assert_eq!(pattern.rescale(200), 2);
+        assert_eq!(pattern.step(0), Some((6, -10, 30)));
+        assert_eq!(pattern.step(1), Some((i16::MAX, i16::MIN, 60)));
+
+        // 2.5 and -2.5 round away from zero
+        let mut manager = RecoilManager::new();
+        manager.add_pattern("h", &[5, -5, 10]).unwrap();
+        let pattern = manager.get_pattern_mut("h").unwrap();
+        assert_eq!(pattern.rescale(50), 0);
+        assert_eq!(pattern.step(0), Some((3, -3, 10)));
+    }
+
+    #[test]
+    fn test_resample_interpolates_between_steps() {
+        let mut manager = RecoilManager::new();
+        manager.add_pattern("r", &[4, -6, 30, 5, 3, 21]).unwrap();
+
+        let pattern = manager.get_pattern_mut("r").unwrap();
+        assert!(pattern.resample(2).is_ok());
+        assert_eq!(pattern.step_count(), 4);
+        assert_eq!(pattern.steps.as_slice(), &[2, -4, 15, 2, -2, 15, 2, 1, 11, 3, 2, 10]);
+
+        // 4 triplets x 6 = 72 values > 64
+        assert_eq!(pattern.resample(6), Err("Pattern too long"));
+        assert_eq!(pattern.step_count(), 4);
+        assert_eq!(pattern.resample(0), Err("Factor must be at least 1"));
+    }
+
+    #[test]
+    fn test_smooth_flattens_spikes() {
+        let mut manager = RecoilManager::new();
+        manager.add_pattern("s", &[0, 1, 10, 12, 1, 20, 0, 1, 30, 12, 1, 40, 0, 1, 50]).unwrap();
+        let variance = |pattern: &RecoilPattern| {
+            let xs = || (0..pattern.step_count()).map(|i| pattern.step(i).unwrap().0 as i32);
+            let n = pattern.step_count() as i32;
+            let mean = xs().sum::<i32>() / n;
+            xs().map(|x| (x - mean) * (x - mean)).sum::<i32>() / n
+        };
+
+        let pattern = manager.get_pattern_mut("s").unwrap();
+        let spiky = variance(pattern);
+        assert!(pattern.smooth(3).is_ok());
+        assert_eq!(pattern.step_count(), 5);
+        assert!(variance(pattern) < spiky);
+        // Edges repeat the end triplet: (0 + 0 + 12) / 3 = 4
+        assert_eq!(pattern.steps.as_slice(), &[4, 1, 10, 4, 1, 20, 8, 1, 30, 4, 1, 40, 4, 1, 50]);
+
+        assert!(pattern.smooth(1).is_ok());
+        assert_eq!(pattern.step(2), Some((8, 1, 30)));
+        assert_eq!(pattern.smooth(0), Err("Window must be at least 1"));
+    }
+
     #[test]
     fn test_parse_recoil_add_basic() {
         let line = b"nozen.recoil.add(ak47){10,-5,100,20,-10,150}";
         let result = parse_recoil_add(line);
         
-        assert!(result.is_some());
+        assert!(result.is_ok());
         let (name, steps) = result.unwrap();
         assert_eq!(name, b"ak47");
         assert_eq!(steps.len(), 6);
@@ -322,7 +633,7 @@ mod tests {
         let line = b"nozen.recoil.add(test){-10,5,-50}";
         let result = parse_recoil_add(line);
         
-        assert!(result.is_some());
+        assert!(result.is_ok());
         let (_name, steps) = result.unwrap();
         assert_eq!(steps[0], -10);
         assert_eq!(steps[1], 5);
@@ -338,16 +649,52 @@ mod tests {
         
         // Completely malformed command
         let line2 = b"not_a_valid_command";
-        assert!(parse_recoil_add(line2).is_none());
+        assert_eq!(parse_recoil_add(line2), Err(RECOIL_ADD_FORMAT_ERROR));
         
         // Empty pattern
         let line3 = b"nozen.recoil.add(test){}";
         let result3 = parse_recoil_add(line3);
-        if let Some((_name, steps)) = result3 {
+        if let Ok((_name, steps)) = result3 {
             assert_eq!(steps.len(), 0);
         }
     }
 
+    #[test]
+    fn test_parse_recoil_add_tolerates_trailing_comma_and_spaces() {
+        let (_, steps) = parse_recoil_add(b"nozen.recoil.add(ak47){1,-2,30,}").unwrap();
+        assert_eq!(steps.as_slice(), &[1, -2, 30]);
+        
+        let (_, steps) = parse_recoil_add(b"nozen.recoil.add(ak47){ 1 , -2,30,\t4, 5 ,60 , }").unwrap();
+        assert_eq!(steps.as_slice(), &[1, -2, 30, 4, 5, 60]);
+    }
+
+    #[test]
+    fn test_parse_recoil_add_rejects_partial_step() {
+        assert_eq!(parse_recoil_add(b"nozen.recoil.add(ak47){1,2,30,4,5}"),
+                   Err("Incomplete last step: only x,y given, need x,y,delay"));
+        assert_eq!(parse_recoil_add(b"nozen.recoil.add(ak47){1,2,30,4,}"),
+                   Err("Incomplete last step: only x given, need x,y,delay"));
+        assert_eq!(parse_recoil_add(b"nozen.recoil.add(ak47){1,,30}"), Err("Empty value in pattern"));
+        assert_eq!(parse_recoil_add(b"nozen.recoil.add(ak47){1,x,30}"), Err("Pattern values must be integers"));
+    }
+
+    #[test]
+    fn test_split_pattern_flags() {
+        assert_eq!(split_pattern_flags(b"ak47"), Some((&b"ak47"[..], false)));
+        assert_eq!(split_pattern_flags(b"ak47,abs"), Some((&b"ak47"[..], true)));
+        assert_eq!(split_pattern_flags(b"ak47,rel"), None);
+    }
+
+    #[test]
+    fn test_parse_batch_segment() {
+        let (name, steps) = parse_batch_segment(b"ak47:1,-2,30,4,5,60").unwrap();
+        assert_eq!(name, b"ak47");
+        assert_eq!(steps.as_slice(), &[1, -2, 30, 4, 5, 60]);
+
+        assert!(parse_batch_segment(b"nocolon").is_none());
+        assert!(parse_batch_segment(b":1,2,3").is_none());
+    }
+
     #[test]
     fn test_parse_recoil_name_basic() {
         let line = b"nozen.recoil.delete(mypattern)";
@@ -405,6 +752,6 @@ mod tests {
         
         // This should fail
         let result = manager.add_pattern("overflow", &[1, 2, 3]);
-        assert!(result.is_err());
+        assert_eq!(result, Err(RecoilError::StorageFull));
     }
 }
@@ -0,0 +1,107 @@
+//! Closed-loop Move Settling
+//! Drives a relative move towards its target using the movement the FPGA
+//! reports back, so pointer acceleration on the host that makes a move land
+//! short or long gets corrected.
+
+/// Counts either side of the target that count as arrived
+pub const SETTLE_TOLERANCE: i16 = 1;
+
+/// Moves sent for one settle, including the first
+pub const SETTLE_MAX_MOVES: u8 = 4;
+
+/// Target, observed movement and the moves still allowed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveSettle {
+    target: (i16, i16),
+    observed: (i16, i16),
+    moves: u8,
+}
+
+impl MoveSettle {
+    /// Settle on a relative movement of (dx, dy)
+    pub fn new(dx: i16, dy: i16) -> Self {
+        MoveSettle {
+            target: (dx, dy),
+            observed: (0, 0),
+            moves: 0,
+        }
+    }
+
+    /// Movement still needed to reach the target
+    pub fn remaining(&self) -> (i16, i16) {
+        (
+            self.target.0.saturating_sub(self.observed.0),
+            self.target.1.saturating_sub(self.observed.1),
+        )
+    }
+
+    /// Within SETTLE_TOLERANCE on both axes
+    pub fn is_settled(&self) -> bool {
+        let (x, y) = self.remaining();
+        x.abs() <= SETTLE_TOLERANCE && y.abs() <= SETTLE_TOLERANCE
+    }
+
+    /// Moves sent so far
+    pub fn moves(&self) -> u8 {
+        self.moves
+    }
+
+    /// Next move to send, clamped to one report
+    /// None once settled or after SETTLE_MAX_MOVES moves
+    pub fn next_move(&mut self) -> Option<(i16, i16)> {
+        if self.is_settled() || self.moves >= SETTLE_MAX_MOVES {
+            return None;
+        }
+        self.moves += 1;
+        let (x, y) = self.remaining();
+        Some((x.clamp(-127, 127), y.clamp(-127, 127)))
+    }
+
+    /// Record movement the FPGA reports as actually applied
+    pub fn observe(&mut self, dx: i16, dy: i16) {
+        self.observed.0 = self.observed.0.saturating_add(dx);
+        self.observed.1 = self.observed.1.saturating_add(dy);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Host acceleration that only applies 80% of each move
+    fn accelerated(dx: i16, dy: i16) -> (i16, i16) {
+        (dx * 4 / 5, dy * 4 / 5)
+    }
+
+    #[test]
+    fn test_converges_under_80_percent_acceleration() {
+        let mut settle = MoveSettle::new(100, -60);
+        while let Some((dx, dy)) = settle.next_move() {
+            let (x, y) = accelerated(dx, dy);
+            settle.observe(x, y);
+        }
+        assert!(settle.is_settled());
+        assert!(settle.moves() <= SETTLE_MAX_MOVES);
+    }
+
+    #[test]
+    fn test_gives_up_after_move_cap() {
+        // Nothing ever arrives
+        let mut settle = MoveSettle::new(50, 0);
+        let mut sent = 0;
+        while settle.next_move().is_some() {
+            sent += 1;
+        }
+        assert_eq!(sent, SETTLE_MAX_MOVES);
+        assert!(!settle.is_settled());
+        assert_eq!(settle.remaining(), (50, 0));
+    }
+
+    #[test]
+    fn test_moves_are_clamped_to_one_report() {
+        let mut settle = MoveSettle::new(300, -5);
+        assert_eq!(settle.next_move(), Some((127, -5)));
+        settle.observe(127, -5);
+        assert_eq!(settle.next_move(), Some((127, 0)));
+    }
+}
@@ -18,8 +18,9 @@ impl MouseState {
     }
 
     /// Calculate delta to reach absolute position
+    /// Clamped to the i16 range, so a far target is approached but not reached
     pub fn delta_to(&self, target_x: i16, target_y: i16) -> (i16, i16) {
-        (target_x - self.x, target_y - self.y)
+        (target_x.saturating_sub(self.x), target_y.saturating_sub(self.y))
     }
 
     /// Set absolute position (after moveto)
@@ -34,10 +35,146 @@ impl MouseState {
     }
 }
 
+/// Held Input State Tracking
+/// Tracks which injected buttons and keys are currently pressed so commands compose
+#[derive(Clone, Copy, Default)]
+pub struct HeldInput {
+    pub buttons: u8,
+    /// Keyboard modifier bits (same layout as the report's modifier byte)
+    pub modifiers: u8,
+    /// Held non-modifier scancodes, 0 = free slot
+    pub keys: [u8; 6],
+}
+
+impl HeldInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Press or release the buttons in `mask`, returning the new bitmap
+    pub fn set_buttons(&mut self, mask: u8, pressed: bool) -> u8 {
+        if pressed {
+            self.buttons |= mask;
+        } else {
+            self.buttons &= !mask;
+        }
+        self.buttons
+    }
+
+    /// Set or clear the modifier bits in `mask`, returning the new modifier byte
+    pub fn set_modifiers(&mut self, mask: u8, pressed: bool) -> u8 {
+        if pressed {
+            self.modifiers |= mask;
+        } else {
+            self.modifiers &= !mask;
+        }
+        self.modifiers
+    }
+
+    /// Press a key; modifier scancodes (0xE0-0xE7) set their modifier bit
+    /// Returns false if all six key slots are taken
+    pub fn press_key(&mut self, scancode: u8) -> bool {
+        if let Some(bit) = modifier_bit(scancode) {
+            self.modifiers |= bit;
+            return true;
+        }
+        if self.keys.contains(&scancode) {
+            return true;
+        }
+        match self.keys.iter_mut().find(|k| **k == 0) {
+            Some(slot) => {
+                *slot = scancode;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Release a key if it is held
+    pub fn release_key(&mut self, scancode: u8) {
+        if let Some(bit) = modifier_bit(scancode) {
+            self.modifiers &= !bit;
+        } else if let Some(slot) = self.keys.iter_mut().find(|k| **k == scancode) {
+            *slot = 0;
+        }
+    }
+
+    /// True if the key is held; modifier scancodes check their modifier bit
+    pub fn is_key_held(&self, scancode: u8) -> bool {
+        match modifier_bit(scancode) {
+            Some(bit) => self.modifiers & bit != 0,
+            None => scancode != 0 && self.keys.contains(&scancode),
+        }
+    }
+
+    /// Keyboard report for the currently held keys
+    pub fn keyboard_report(&self) -> [u8; 8] {
+        let mut report = [0u8; 8];
+        report[0] = self.modifiers;
+        for (slot, &key) in report[2..].iter_mut().zip(self.keys.iter().filter(|&&k| k != 0)) {
+            *slot = key;
+        }
+        report
+    }
+
+    /// Format as string for display, keys as decimal scancodes in slot order
+    pub fn format(&self) -> heapless::String<64> {
+        use core::fmt::Write;
+        let mut s = heapless::String::new();
+        let _ = write!(s, "Buttons:0x{:02X} Mods:0x{:02X} Keys:", self.buttons, self.modifiers);
+        let mut keys = self.keys.iter().filter(|&&k| k != 0).peekable();
+        if keys.peek().is_none() {
+            let _ = s.push_str("none");
+        }
+        for (i, key) in keys.enumerate() {
+            let _ = write!(s, "{}{}", if i > 0 { "," } else { "" }, key);
+        }
+        s
+    }
+
+    /// True if any input is still held
+    pub fn any(&self) -> bool {
+        self.buttons != 0 || self.modifiers != 0 || self.keys.iter().any(|&k| k != 0)
+    }
+
+    /// Release everything
+    pub fn clear(&mut self) {
+        self.buttons = 0;
+        self.modifiers = 0;
+        self.keys = [0; 6];
+    }
+}
+
+/// Modifier bit for the Left Control..Right GUI scancodes
+fn modifier_bit(scancode: u8) -> Option<u8> {
+    match scancode {
+        0xE0..=0xE7 => Some(1 << (scancode - 0xE0)),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_held_keys() {
+        let mut held = HeldInput::new();
+        assert!(held.press_key(0x04));
+        assert!(held.press_key(0xE1));  // Left Shift
+        assert_eq!(held.keyboard_report(), [0x02, 0, 0x04, 0, 0, 0, 0, 0]);
+
+        held.release_key(0x04);
+        assert!(held.any());
+        held.release_key(0xE1);
+        assert!(!held.any());
+
+        for key in 0x04..0x0A {
+            assert!(held.press_key(key));
+        }
+        assert!(!held.press_key(0x0A));
+    }
+
     #[test]
     fn test_mouse_state_new() {
         let state = MouseState::new();
@@ -121,6 +258,18 @@ mod tests {
         assert_eq!(dy, 0);
     }
 
+    #[test]
+    fn test_delta_to_clamps() {
+        let mut state = MouseState::new();
+        state.set_position(-30000, 30000);
+        
+        let (dx, dy) = state.delta_to(30000, -30000);
+        assert_eq!((dx, dy), (i16::MAX, i16::MIN));
+        
+        state.update_relative(dx, dy);
+        assert_eq!(state.position(), (2767, -2768));
+    }
+
     #[test]
     fn test_movement_sequence() {
         let mut state = MouseState::new();
@@ -160,4 +309,19 @@ mod tests {
         state.set_position(i16::MIN, i16::MIN);
         assert_eq!(state.position(), (i16::MIN, i16::MIN));
     }
+
+    #[test]
+    fn test_held_buttons_compose() {
+        let mut held = HeldInput::new();
+        assert!(!held.any());
+
+        assert_eq!(held.set_buttons(0x01, true), 0x01);
+        assert_eq!(held.set_buttons(0x02, true), 0x03);
+        assert_eq!(held.set_buttons(0x01, false), 0x02);
+        assert!(held.any());
+
+        held.clear();
+        assert_eq!(held.buttons, 0);
+        assert!(!held.any());
+    }
 }
@@ -0,0 +1,355 @@
+//! Millisecond Timebase
+//! Derives a millisecond clock from a free-running cycle counter (DWT CYCCNT)
+//! and measures how fast the main loop is spinning
+
+/// Window over which the loop rate is averaged
+pub const LOOP_RATE_WINDOW_MS: u32 = 1000;
+
+/// Millisecond clock driven by a 32-bit cycle counter
+/// The counter may wrap freely as long as it is sampled at least once per
+/// wrap period (~35s at 120MHz).
+pub struct Timebase {
+    cycles_per_ms: u32,
+    last_cycles: u32,
+    leftover_cycles: u32,
+    now_ms: u32,
+}
+
+impl Timebase {
+    pub fn new(cpu_hz: u32, start_cycles: u32) -> Self {
+        Timebase {
+            cycles_per_ms: (cpu_hz / 1000).max(1),
+            last_cycles: start_cycles,
+            leftover_cycles: 0,
+            now_ms: 0,
+        }
+    }
+
+    /// Advance the clock from a new counter reading and return the time in ms
+    pub fn update(&mut self, cycles: u32) -> u32 {
+        let elapsed = cycles.wrapping_sub(self.last_cycles) as u64 + self.leftover_cycles as u64;
+        self.last_cycles = cycles;
+
+        let cycles_per_ms = self.cycles_per_ms as u64;
+        self.now_ms = self.now_ms.wrapping_add((elapsed / cycles_per_ms) as u32);
+        self.leftover_cycles = (elapsed % cycles_per_ms) as u32;
+        self.now_ms
+    }
+
+    /// Milliseconds since the timebase was created
+    pub fn now_ms(&self) -> u32 {
+        self.now_ms
+    }
+}
+
+/// Reports per second for `count` reports sent over `elapsed_ms`
+/// Bursts shorter than the 1ms resolution are counted as 1ms, so the result
+/// is a lower bound for very short runs.
+pub fn reports_per_second(count: u32, elapsed_ms: u32) -> u32 {
+    (count as u64 * 1000 / elapsed_ms.max(1) as u64) as u32
+}
+
+/// Microseconds for `cycles` of a core running at `cpu_hz`, rounded down
+pub fn cycles_to_us(cycles: u32, cpu_hz: u32) -> u32 {
+    (cycles as u64 * 1_000_000 / cpu_hz.max(1) as u64) as u32
+}
+
+/// Times a single call with a cycle counter (DWT CYCCNT on the device)
+/// The counter is passed in so the timing can run against a fake in tests.
+#[derive(Clone, Copy)]
+pub struct CycleTimer {
+    read: fn() -> u32,
+    cpu_hz: u32,
+}
+
+impl CycleTimer {
+    pub fn new(read: fn() -> u32, cpu_hz: u32) -> Self {
+        CycleTimer { read, cpu_hz }
+    }
+
+    /// A timer whose counter never moves, until the main loop installs a real one
+    pub fn stopped() -> Self {
+        CycleTimer::new(|| 0, 1_000_000)
+    }
+
+    /// Run `f`, returning its result and the cycles it took
+    pub fn time<T>(&self, f: impl FnOnce() -> T) -> (T, u32) {
+        let start = (self.read)();
+        let result = f();
+        (result, (self.read)().wrapping_sub(start))
+    }
+
+    pub fn to_us(&self, cycles: u32) -> u32 {
+        cycles_to_us(cycles, self.cpu_hz)
+    }
+}
+
+/// Uptime that keeps counting past the 32-bit millisecond wrap (~49.7 days)
+/// Must be sampled at least once per wrap period.
+#[derive(Default)]
+pub struct Uptime {
+    wraps: u32,
+    last_ms: u32,
+}
+
+impl Uptime {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the current 32-bit millisecond time
+    pub fn sample(&mut self, now_ms: u32) {
+        if now_ms < self.last_ms {
+            self.wraps = self.wraps.wrapping_add(1);
+        }
+        self.last_ms = now_ms;
+    }
+
+    /// Milliseconds since boot
+    pub fn ms(&self) -> u64 {
+        ((self.wraps as u64) << 32) | self.last_ms as u64
+    }
+
+    /// Format as string for display
+    pub fn format(&self) -> heapless::String<64> {
+        use core::fmt::Write;
+        let mut s = heapless::String::new();
+        let _ = write!(s, "Uptime: {} ms ({})", self.ms(), format_hms(self.ms()));
+        s
+    }
+}
+
+/// Format a millisecond duration as h:mm:ss (hours are not wrapped at 24)
+pub fn format_hms(ms: u64) -> heapless::String<24> {
+    use core::fmt::Write;
+
+    let secs = ms / 1000;
+    let mut out = heapless::String::new();
+    let _ = write!(out, "{}:{:02}:{:02}", secs / 3600, (secs / 60) % 60, secs % 60);
+    out
+}
+
+/// Main loop rate meter
+/// Fed (loop_counter, now_ms) samples; recomputes iterations per second
+/// once every LOOP_RATE_WINDOW_MS.
+#[derive(Default)]
+pub struct LoopRate {
+    window_start: Option<(u32, u32)>,  // (loop_counter, now_ms)
+    hz: Option<u32>,
+}
+
+impl LoopRate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a loop counter sample taken at `now_ms`
+    pub fn sample(&mut self, count: u32, now_ms: u32) {
+        let (start_count, start_ms) = match self.window_start {
+            Some(start) => start,
+            None => {
+                self.window_start = Some((count, now_ms));
+                return;
+            }
+        };
+
+        let elapsed_ms = now_ms.wrapping_sub(start_ms);
+        if elapsed_ms < LOOP_RATE_WINDOW_MS {
+            return;
+        }
+
+        self.hz = Some(reports_per_second(count.wrapping_sub(start_count), elapsed_ms));
+        self.window_start = Some((count, now_ms));
+    }
+
+    /// Loop iterations per second, None until a full window has elapsed
+    pub fn hz(&self) -> Option<u32> {
+        self.hz
+    }
+
+    /// Format as string for display
+    pub fn format(&self) -> heapless::String<32> {
+        use core::fmt::Write;
+        let mut s = heapless::String::new();
+        match self.hz {
+            Some(hz) => {
+                let _ = write!(s, "Loop: {} Hz", hz);
+            }
+            None => {
+                let _ = write!(s, "Loop: not measured yet");
+            }
+        }
+        s
+    }
+}
+
+/// Time from a host command arriving to its frame leaving for the FPGA
+/// Kept in microseconds; the average is over every sample since boot.
+pub struct LatencyStats {
+    count: u32,
+    min_us: u32,
+    max_us: u32,
+    total_us: u64,
+}
+
+impl Default for LatencyStats {
+    fn default() -> Self {
+        LatencyStats {
+            count: 0,
+            min_us: u32::MAX,
+            max_us: 0,
+            total_us: 0,
+        }
+    }
+}
+
+impl LatencyStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one command's latency
+    pub fn record(&mut self, us: u32) {
+        self.count = self.count.saturating_add(1);
+        self.min_us = self.min_us.min(us);
+        self.max_us = self.max_us.max(us);
+        self.total_us += us as u64;
+    }
+
+    /// (min, avg, max) in microseconds, None before the first sample
+    pub fn summary(&self) -> Option<(u32, u32, u32)> {
+        if self.count == 0 {
+            return None;
+        }
+        Some((self.min_us, (self.total_us / self.count as u64) as u32, self.max_us))
+    }
+
+    /// Format as string for display
+    pub fn format(&self) -> heapless::String<64> {
+        use core::fmt::Write;
+        let mut s = heapless::String::new();
+        match self.summary() {
+            Some((min, avg, max)) => {
+                let _ = write!(s, "Latency: min={}us avg={}us max={}us n={}", min, avg, max, self.count);
+            }
+            None => {
+                let _ = write!(s, "Latency: no commands yet");
+            }
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timebase_counts_ms() {
+        let mut tb = Timebase::new(120_000_000, 0);
+        assert_eq!(tb.update(120_000), 1);
+        assert_eq!(tb.update(120_000 * 10), 10);
+        // Partial milliseconds carry over
+        assert_eq!(tb.update(120_000 * 10 + 60_000), 10);
+        assert_eq!(tb.update(120_000 * 11 + 1), 11);
+    }
+
+    #[test]
+    fn test_timebase_handles_counter_wrap() {
+        let start = u32::MAX - 60_000 + 1;
+        let mut tb = Timebase::new(120_000_000, start);
+        assert_eq!(tb.update(60_000), 1);
+        assert_eq!(tb.now_ms(), 1);
+    }
+
+    #[test]
+    fn test_cycles_to_us() {
+        assert_eq!(cycles_to_us(120, 120_000_000), 1);
+        assert_eq!(cycles_to_us(119, 120_000_000), 0);
+        assert_eq!(cycles_to_us(1_200_000, 120_000_000), 10_000);
+        // Full counter range without overflow: ~35.8s at 120MHz
+        assert_eq!(cycles_to_us(u32::MAX, 120_000_000), 35_791_394);
+    }
+
+    #[test]
+    fn test_cycle_timer_measures_call() {
+        use core::sync::atomic::{AtomicU32, Ordering};
+        static COUNTER: AtomicU32 = AtomicU32::new(u32::MAX - 100);
+        // Each read advances 600 cycles, wrapping on the second read
+        fn read() -> u32 {
+            COUNTER.fetch_add(600, Ordering::Relaxed)
+        }
+
+        let timer = CycleTimer::new(read, 120_000_000);
+        let (value, cycles) = timer.time(|| 7);
+        assert_eq!(value, 7);
+        assert_eq!(cycles, 600);
+        assert_eq!(timer.to_us(cycles), 5);
+    }
+
+    #[test]
+    fn test_format_hms() {
+        assert_eq!(format_hms(0).as_str(), "0:00:00");
+        assert_eq!(format_hms(999).as_str(), "0:00:00");
+        assert_eq!(format_hms(3_723_000).as_str(), "1:02:03");
+        assert_eq!(format_hms(100 * 3_600_000).as_str(), "100:00:00");
+        // One past the 32-bit rollover: 4294967296 ms
+        assert_eq!(format_hms(u32::MAX as u64 + 1).as_str(), "1193:02:47");
+    }
+
+    #[test]
+    fn test_uptime_counts_past_rollover() {
+        let mut uptime = Uptime::new();
+        uptime.sample(u32::MAX - 10);
+        assert_eq!(uptime.ms(), u32::MAX as u64 - 10);
+
+        uptime.sample(5);
+        assert_eq!(uptime.ms(), (1u64 << 32) + 5);
+        uptime.sample(20);
+        assert_eq!(uptime.ms(), (1u64 << 32) + 20);
+    }
+
+    #[test]
+    fn test_latency_min_avg_max() {
+        let mut latency = LatencyStats::new();
+        assert_eq!(latency.summary(), None);
+        assert_eq!(latency.format().as_str(), "Latency: no commands yet");
+
+        for us in [40, 10, 100] {
+            latency.record(us);
+        }
+        assert_eq!(latency.summary(), Some((10, 50, 100)));
+        assert_eq!(latency.format().as_str(), "Latency: min=10us avg=50us max=100us n=3");
+    }
+
+    #[test]
+    fn test_reports_per_second() {
+        assert_eq!(reports_per_second(1000, 1000), 1000);
+        assert_eq!(reports_per_second(500, 2000), 250);
+        assert_eq!(reports_per_second(1000, 3), 333_333);
+        // Sub-millisecond bursts count as 1ms
+        assert_eq!(reports_per_second(10, 0), 10_000);
+        assert_eq!(reports_per_second(0, 50), 0);
+    }
+
+    #[test]
+    fn test_loop_rate_from_counter_delta() {
+        let mut rate = LoopRate::new();
+        rate.sample(500, 100);
+        assert_eq!(rate.hz(), None);
+
+        // Not a full window yet
+        rate.sample(900, 600);
+        assert_eq!(rate.hz(), None);
+
+        // 2000 iterations over 1000ms
+        rate.sample(2500, 1100);
+        assert_eq!(rate.hz(), Some(2000));
+
+        // 3000 iterations over 1500ms, with the counter wrapping
+        let mut rate = LoopRate::new();
+        rate.sample(u32::MAX - 999, 0);
+        rate.sample(2000, 1500);
+        assert_eq!(rate.hz(), Some(2000));
+    }
+}
@@ -2,40 +2,157 @@
 /// Handles UART0 communication with FPGA
 
 use atsamd_hal as hal;
+use hal::gpio::{Pin, Reset, C, D, PA04, PA05, PA06, PA07, PA08, PA09, PA10, PA11};
 use hal::sercom::Sercom0;
+use samd51_hid_injector::uart_config::{
+    write_with_flow_control, FlowControl, SercomRegs, UartConfigError, UartPinout, UartTx,
+    CTS_WAIT_POLLS,
+};
+use samd51_hid_injector::uart_rx::RxRing;
+
+/// Pins that can carry the FPGA UART on SERCOM0
+/// Implemented for each supported (tx, rx) pair, and for (tx, rx, rts, cts)
+/// sets that also wire PAD2/PAD3 for flow control; see UartPinout.
+pub trait UartPins {
+    const PINOUT: UartPinout;
+    /// True if the set includes the RTS/CTS pins
+    const HAS_FLOW_PINS: bool;
+
+    /// Switch the pins to their SERCOM0 peripheral function
+    fn into_sercom(self);
+}
+
+/// Default Cynthion wiring
+pub type DefaultUartPins = (Pin<PA04, Reset>, Pin<PA05, Reset>);
+
+impl UartPins for (Pin<PA04, Reset>, Pin<PA05, Reset>) {
+    const PINOUT: UartPinout = UartPinout::Pa04Pa05;
+    const HAS_FLOW_PINS: bool = false;
+
+    fn into_sercom(self) {
+        let _tx = self.0.into_alternate::<D>();
+        let _rx = self.1.into_alternate::<D>();
+    }
+}
+
+impl UartPins for (Pin<PA04, Reset>, Pin<PA05, Reset>, Pin<PA06, Reset>, Pin<PA07, Reset>) {
+    const PINOUT: UartPinout = UartPinout::Pa04Pa05;
+    const HAS_FLOW_PINS: bool = true;
+
+    fn into_sercom(self) {
+        (self.0, self.1).into_sercom();
+        let _rts = self.2.into_alternate::<D>();
+        let _cts = self.3.into_alternate::<D>();
+    }
+}
+
+impl UartPins for (Pin<PA08, Reset>, Pin<PA09, Reset>) {
+    const PINOUT: UartPinout = UartPinout::Pa08Pa09;
+    const HAS_FLOW_PINS: bool = false;
+
+    fn into_sercom(self) {
+        let _tx = self.0.into_alternate::<C>();
+        let _rx = self.1.into_alternate::<C>();
+    }
+}
+
+impl UartPins for (Pin<PA08, Reset>, Pin<PA09, Reset>, Pin<PA10, Reset>, Pin<PA11, Reset>) {
+    const PINOUT: UartPinout = UartPinout::Pa08Pa09;
+    const HAS_FLOW_PINS: bool = true;
+
+    fn into_sercom(self) {
+        (self.0, self.1).into_sercom();
+        let _rts = self.2.into_alternate::<C>();
+        let _cts = self.3.into_alternate::<C>();
+    }
+}
 
 pub struct UartInterface {
     // UART peripheral (would be fully implemented with HAL)
+    pinout: UartPinout,
+    flow: FlowControl,
+    rx: RxRing,
 }
 
 impl UartInterface {
-    pub fn new(
+    /// RTS/CTS needs `pins` to include the PAD2/PAD3 pins; asking for it with
+    /// a two-pin set is an error rather than silently running without it
+    pub fn new<P: UartPins>(
         _sercom: Sercom0,
         _clocks: &mut hal::clock::GenericClockController,
         _baud: u32,
-        _tx_pin: hal::gpio::Pin<hal::gpio::PA04, hal::gpio::Reset>,
-        _rx_pin: hal::gpio::Pin<hal::gpio::PA05, hal::gpio::Reset>,
-    ) -> Self {
+        pins: P,
+        flow: FlowControl,
+    ) -> Result<Self, UartConfigError> {
+        let flow = flow.check_pins(P::HAS_FLOW_PINS)?;
+        pins.into_sercom();
+
         // TODO: Configure SERCOM0 as UART
         // - Set baud rate generator
         // - Configure 8N1 format
+        // - TXPO from flow.txpo(), RXPO from P::PINOUT.rx_pad()
         // - Enable TX/RX
-        // - Set up pins with correct SERCOM function
-        
-        UartInterface {}
-    }
-    
-    pub fn write(&self, _data: &[u8]) {
-        // TODO: Transmit data via UART
-        // - Wait for TX ready
-        // - Write bytes to DATA register
-    }
-    
-    pub fn read_line(&self) -> Option<[u8; 256]> {
-        // TODO: Read line from UART (terminated by \n)
-        // - Check RX ready flag
-        // - Read DATA register
-        // - Accumulate until newline
-        None
+
+        Ok(UartInterface { pinout: P::PINOUT, flow, rx: RxRing::new() })
+    }
+
+    /// Pin mapping this interface was built with
+    pub fn pinout(&self) -> UartPinout {
+        self.pinout
+    }
+
+    /// Flow control actually in effect
+    pub fn flow_control(&self) -> FlowControl {
+        self.flow
+    }
+
+    /// Transmit data, waiting on CTS when flow control is enabled
+    /// Returns the number of bytes sent
+    pub fn write(&mut self, data: &[u8]) -> usize {
+        let flow = self.flow;
+        write_with_flow_control(self, data, flow, CTS_WAIT_POLLS)
+    }
+
+    /// Copy the next complete line from the FPGA into `out`, returning its length
+    pub fn read_line(&mut self, out: &mut [u8]) -> Option<usize> {
+        // TODO: Move received bytes into the ring
+        // - While INTFLAG.RXC is set, read DATA into self.rx.push()
+        self.rx.read_line(out)
+    }
+
+    /// Current SERCOM0 USART register values, for nozen.uart.regs
+    /// Read straight from the peripheral; reading these has no side effects.
+    pub fn registers(&self) -> SercomRegs {
+        let usart = unsafe { &*hal::pac::SERCOM0::ptr() }.usart_int();
+        SercomRegs {
+            ctrla: usart.ctrla.read().bits(),
+            ctrlb: usart.ctrlb.read().bits(),
+            baud: usart.baud().read().bits(),
+            status: usart.status.read().bits(),
+            intflag: usart.intflag.read().bits(),
+        }
+    }
+
+    /// Discard buffered RX bytes and any partial line
+    /// Returns the number of bytes discarded
+    pub fn flush_rx(&mut self) -> usize {
+        // TODO: Also drain the DATA register so nothing stale follows
+        self.rx.flush()
+    }
+}
+
+impl UartTx for UartInterface {
+    fn cts_asserted(&self) -> bool {
+        // TODO: Read CTS state (SERCOM0 STATUS.CTS)
+        true
+    }
+
+    fn tx_ready(&self) -> bool {
+        // TODO: Check INTFLAG.DRE
+        true
+    }
+
+    fn write_byte(&mut self, _byte: u8) {
+        // TODO: Write byte to DATA register
     }
 }
@@ -0,0 +1,218 @@
+//! UART Pin Configuration
+//! Supported SERCOM0 pin/pad mappings and flow control for the FPGA UART.
+//! Kept free of HAL types so the mapping can be checked on the host;
+//! uart.rs ties each variant to concrete HAL pins.
+
+/// TX/RX pin pair carrying the FPGA UART
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum UartPinout {
+    /// PA04 (TX, PAD0) / PA05 (RX, PAD1), peripheral function D (Cynthion wiring)
+    #[default]
+    Pa04Pa05,
+    /// PA08 (TX, PAD0) / PA09 (RX, PAD1), peripheral function C
+    Pa08Pa09,
+}
+
+impl UartPinout {
+    /// Pin names as (tx, rx)
+    pub fn pin_names(&self) -> (&'static str, &'static str) {
+        match self {
+            UartPinout::Pa04Pa05 => ("PA04", "PA05"),
+            UartPinout::Pa08Pa09 => ("PA08", "PA09"),
+        }
+    }
+
+    /// Port multiplexer function that routes the pins to SERCOM0
+    pub fn peripheral_function(&self) -> char {
+        match self {
+            UartPinout::Pa04Pa05 => 'D',
+            UartPinout::Pa08Pa09 => 'C',
+        }
+    }
+
+    /// SERCOM pad driving TX (CTRLA.TXPO selects PAD0)
+    pub fn tx_pad(&self) -> u8 {
+        0
+    }
+
+    /// SERCOM pad sampled for RX (CTRLA.RXPO)
+    pub fn rx_pad(&self) -> u8 {
+        1
+    }
+
+    /// Pins on PAD2/PAD3 used as (rts, cts) when flow control is enabled
+    pub fn flow_pin_names(&self) -> (&'static str, &'static str) {
+        match self {
+            UartPinout::Pa04Pa05 => ("PA06", "PA07"),
+            UartPinout::Pa08Pa09 => ("PA10", "PA11"),
+        }
+    }
+}
+
+/// Hardware flow control mode
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FlowControl {
+    #[default]
+    None,
+    /// RTS on PAD2, CTS on PAD3
+    RtsCts,
+}
+
+impl FlowControl {
+    /// CTRLA.TXPO value: TX on PAD0, plus RTS/CTS on PAD2/PAD3 when enabled
+    pub fn txpo(&self) -> u8 {
+        match self {
+            FlowControl::None => 0,
+            FlowControl::RtsCts => 2,
+        }
+    }
+
+    /// Check the mode against the pins given: RTS/CTS needs the PAD2/PAD3 pins
+    pub fn check_pins(self, has_flow_pins: bool) -> Result<Self, UartConfigError> {
+        match self {
+            FlowControl::RtsCts if !has_flow_pins => Err(UartConfigError::NoFlowPins),
+            flow => Ok(flow),
+        }
+    }
+}
+
+/// UART configuration the chosen pins can't provide
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UartConfigError {
+    /// RTS/CTS requested with a two-pin (TX/RX only) set
+    NoFlowPins,
+}
+
+/// SERCOM USART registers read for nozen.uart.regs
+/// A plain copy of the values, so formatting can be checked on the host
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SercomRegs {
+    pub ctrla: u32,
+    pub ctrlb: u32,
+    pub baud: u16,
+    pub status: u16,
+    pub intflag: u8,
+}
+
+impl SercomRegs {
+    /// Each register in hex, zero-padded to its width
+    pub fn format(&self) -> heapless::String<80> {
+        use core::fmt::Write;
+        let mut s = heapless::String::new();
+        let _ = write!(s, "CTRLA=0x{:08X} CTRLB=0x{:08X} BAUD=0x{:04X} STATUS=0x{:04X} INTFLAG=0x{:02X}",
+            self.ctrla, self.ctrlb, self.baud, self.status, self.intflag);
+        s
+    }
+}
+
+/// Polls of a deasserted CTS before a write gives up
+pub const CTS_WAIT_POLLS: u32 = 100_000;
+
+/// Byte-level transmit side of the UART, abstracted so the flow control
+/// logic can be exercised on the host
+pub trait UartTx {
+    /// True when the FPGA is ready to receive (CTS asserted)
+    fn cts_asserted(&self) -> bool;
+    /// True when the data register can take another byte
+    fn tx_ready(&self) -> bool;
+    fn write_byte(&mut self, byte: u8);
+}
+
+/// Send `data`, holding each byte while CTS is deasserted if flow control is on
+/// Waits at most `max_polls` per byte and returns how many bytes went out.
+pub fn write_with_flow_control<T: UartTx>(tx: &mut T, data: &[u8], flow: FlowControl, max_polls: u32) -> usize {
+    for (sent, &byte) in data.iter().enumerate() {
+        let mut polls = 0;
+        while (flow == FlowControl::RtsCts && !tx.cts_asserted()) || !tx.tx_ready() {
+            if polls == max_polls {
+                return sent;
+            }
+            polls += 1;
+        }
+        tx.write_byte(byte);
+    }
+    data.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_cynthion_wiring() {
+        let pinout = UartPinout::default();
+        assert_eq!(pinout, UartPinout::Pa04Pa05);
+        assert_eq!(pinout.pin_names(), ("PA04", "PA05"));
+        assert_eq!(pinout.peripheral_function(), 'D');
+    }
+
+    #[test]
+    fn test_alternate_pad_set() {
+        let pinout = UartPinout::Pa08Pa09;
+        assert_eq!(pinout.pin_names(), ("PA08", "PA09"));
+        assert_eq!(pinout.peripheral_function(), 'C');
+        assert_eq!((pinout.tx_pad(), pinout.rx_pad()), (0, 1));
+        assert_eq!(pinout.flow_pin_names(), ("PA10", "PA11"));
+    }
+
+    /// Mock UART whose CTS line asserts after a number of polls
+    struct MockTx {
+        sent: std::vec::Vec<u8>,
+        cts_after_polls: Option<u32>,
+        polls: core::cell::Cell<u32>,
+    }
+
+    impl MockTx {
+        fn new(cts_after_polls: Option<u32>) -> Self {
+            MockTx { sent: std::vec::Vec::new(), cts_after_polls, polls: core::cell::Cell::new(0) }
+        }
+    }
+
+    impl UartTx for MockTx {
+        fn cts_asserted(&self) -> bool {
+            let polls = self.polls.get();
+            self.polls.set(polls + 1);
+            self.cts_after_polls.is_some_and(|after| polls >= after)
+        }
+        fn tx_ready(&self) -> bool {
+            true
+        }
+        fn write_byte(&mut self, byte: u8) {
+            self.sent.push(byte);
+        }
+    }
+
+    #[test]
+    fn test_write_blocks_while_cts_deasserted() {
+        let mut tx = MockTx::new(None);
+        assert_eq!(write_with_flow_control(&mut tx, b"abc", FlowControl::RtsCts, 50), 0);
+        assert!(tx.sent.is_empty());
+        assert!(tx.polls.get() > 50);
+
+        // CTS comes up partway through the wait
+        let mut tx = MockTx::new(Some(10));
+        assert_eq!(write_with_flow_control(&mut tx, b"abc", FlowControl::RtsCts, 50), 3);
+        assert_eq!(tx.sent, b"abc");
+    }
+
+    #[test]
+    fn test_sercom_regs_format() {
+        let regs = SercomRegs { ctrla: 0x4031_0004, ctrlb: 0x0003_0000, baud: 0xFB15, status: 0x0000, intflag: 0x01 };
+        assert_eq!(regs.format().as_str(), "CTRLA=0x40310004 CTRLB=0x00030000 BAUD=0xFB15 STATUS=0x0000 INTFLAG=0x01");
+        assert_eq!(SercomRegs::default().format().as_str(), "CTRLA=0x00000000 CTRLB=0x00000000 BAUD=0x0000 STATUS=0x0000 INTFLAG=0x00");
+    }
+
+    #[test]
+    fn test_rts_cts_needs_flow_pins() {
+        assert_eq!(FlowControl::RtsCts.check_pins(true), Ok(FlowControl::RtsCts));
+        assert_eq!(FlowControl::RtsCts.check_pins(false), Err(UartConfigError::NoFlowPins));
+        assert_eq!(FlowControl::None.check_pins(false), Ok(FlowControl::None));
+    }
+
+    #[test]
+    fn test_write_ignores_cts_without_flow_control() {
+        let mut tx = MockTx::new(None);
+        assert_eq!(write_with_flow_control(&mut tx, b"abc", FlowControl::None, 0), 3);
+        assert_eq!(tx.polls.get(), 0);
+    }
+}
@@ -0,0 +1,397 @@
+//! UART Receive Buffering
+//! Ring buffer for bytes received from the FPGA plus the accumulator that
+//! assembles them into newline-terminated lines.
+//! Kept free of HAL types so it can be tested on the host; uart.rs feeds it.
+
+/// Capacity of the RX ring and of the line accumulator
+pub const RX_RING_SIZE: usize = 256;
+
+/// Bytes of trace kept by a UartCapture; later lines are counted as dropped
+pub const CAPTURE_BUFFER_SIZE: usize = 512;
+
+/// Lines kept by UartHistory
+pub const HISTORY_LINES: usize = 8;
+
+/// Bytes kept per history line; eight of them plus a header fit one response
+pub const HISTORY_LINE_LEN: usize = 26;
+
+pub struct RxRing {
+    ring: [u8; RX_RING_SIZE],
+    head: usize,
+    len: usize,
+    line: [u8; RX_RING_SIZE],
+    line_len: usize,
+}
+
+impl Default for RxRing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RxRing {
+    pub fn new() -> Self {
+        RxRing {
+            ring: [0; RX_RING_SIZE],
+            head: 0,
+            len: 0,
+            line: [0; RX_RING_SIZE],
+            line_len: 0,
+        }
+    }
+
+    /// Queue a received byte; returns false (byte dropped) when the ring is full
+    pub fn push(&mut self, byte: u8) -> bool {
+        if self.len == RX_RING_SIZE {
+            return false;
+        }
+        self.ring[(self.head + self.len) % RX_RING_SIZE] = byte;
+        self.len += 1;
+        true
+    }
+
+    /// Oldest queued byte
+    pub fn pop(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let byte = self.ring[self.head];
+        self.head = (self.head + 1) % RX_RING_SIZE;
+        self.len -= 1;
+        Some(byte)
+    }
+
+    /// Bytes queued in the ring (not counting the partial line)
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bytes accumulated towards the next line
+    pub fn partial_len(&self) -> usize {
+        self.line_len
+    }
+
+    /// Move queued bytes into the line accumulator until a '\n' completes a line
+    /// The line (without "\r\n") is copied to `out` and its length returned.
+    /// Bytes past the accumulator's capacity are dropped.
+    pub fn read_line(&mut self, out: &mut [u8]) -> Option<usize> {
+        while let Some(byte) = self.pop() {
+            match byte {
+                b'\n' => {
+                    let len = self.line_len.min(out.len());
+                    out[..len].copy_from_slice(&self.line[..len]);
+                    self.line_len = 0;
+                    return Some(len);
+                }
+                b'\r' => {}
+                _ if self.line_len < RX_RING_SIZE => {
+                    self.line[self.line_len] = byte;
+                    self.line_len += 1;
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Discard everything queued and the partial line
+    /// Returns the number of bytes discarded
+    pub fn flush(&mut self) -> usize {
+        let discarded = self.len + self.line_len;
+        self.head = 0;
+        self.len = 0;
+        self.line_len = 0;
+        discarded
+    }
+}
+
+/// Collects the FPGA lines received during a time window
+/// Each line is stored as "+<ms since start>ms <line>\n".
+pub struct UartCapture {
+    window: Option<(u32, u32)>,  // (start_ms, duration_ms)
+    buf: [u8; CAPTURE_BUFFER_SIZE],
+    len: usize,
+    lines: u16,
+    dropped: u16,
+}
+
+impl Default for UartCapture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UartCapture {
+    pub fn new() -> Self {
+        UartCapture {
+            window: None,
+            buf: [0; CAPTURE_BUFFER_SIZE],
+            len: 0,
+            lines: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Start a new capture, discarding any previous trace
+    pub fn start(&mut self, now_ms: u32, duration_ms: u32) {
+        self.window = Some((now_ms, duration_ms));
+        self.len = 0;
+        self.lines = 0;
+        self.dropped = 0;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.window.is_some()
+    }
+
+    /// Record a line received at `now_ms`; returns false if it falls outside the window
+    pub fn record(&mut self, line: &[u8], now_ms: u32) -> bool {
+        use core::fmt::Write;
+
+        let (start, duration) = match self.window {
+            Some(window) => window,
+            None => return false,
+        };
+        let offset = now_ms.wrapping_sub(start);
+        if offset >= duration {
+            return false;
+        }
+
+        let mut stamp = heapless::String::<16>::new();
+        let _ = write!(stamp, "+{}ms ", offset);
+        let needed = stamp.len() + line.len() + 1;
+        if self.len + needed > CAPTURE_BUFFER_SIZE {
+            self.dropped = self.dropped.saturating_add(1);
+            return true;
+        }
+        for part in [stamp.as_bytes(), line, b"\n"] {
+            self.buf[self.len..self.len + part.len()].copy_from_slice(part);
+            self.len += part.len();
+        }
+        self.lines = self.lines.saturating_add(1);
+        true
+    }
+
+    /// Close the capture once its window has elapsed
+    /// Returns (lines kept, lines dropped) the first time, then None
+    pub fn finish(&mut self, now_ms: u32) -> Option<(u16, u16)> {
+        let (start, duration) = self.window?;
+        if now_ms.wrapping_sub(start) < duration {
+            return None;
+        }
+        self.window = None;
+        Some((self.lines, self.dropped))
+    }
+
+    /// Trace recorded so far
+    pub fn trace(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+/// The last HISTORY_LINES lines received from the FPGA
+/// Longer lines keep as much of their start as fits before a "..." marker.
+#[derive(Default)]
+pub struct UartHistory {
+    lines: [([u8; HISTORY_LINE_LEN], usize); HISTORY_LINES],
+    next: usize,
+    count: usize,
+}
+
+impl UartHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store a line, overwriting the oldest once full
+    pub fn push(&mut self, line: &[u8]) {
+        const MARKER: &[u8] = b"...";
+        let slot = &mut self.lines[self.next];
+        if line.len() > HISTORY_LINE_LEN {
+            let keep = HISTORY_LINE_LEN - MARKER.len();
+            slot.0[..keep].copy_from_slice(&line[..keep]);
+            slot.0[keep..].copy_from_slice(MARKER);
+            slot.1 = HISTORY_LINE_LEN;
+        } else {
+            slot.0[..line.len()].copy_from_slice(line);
+            slot.1 = line.len();
+        }
+        self.next = (self.next + 1) % HISTORY_LINES;
+        self.count = (self.count + 1).min(HISTORY_LINES);
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Stored lines, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> {
+        let start = (self.next + HISTORY_LINES - self.count) % HISTORY_LINES;
+        (0..self.count).map(move |i| {
+            let (line, len) = &self.lines[(start + i) % HISTORY_LINES];
+            &line[..*len]
+        })
+    }
+}
+
+/// Traffic counters for the FPGA UART link
+/// RX figures are fed by the command processor, TX and flush by the main loop.
+#[derive(Default)]
+pub struct LinkStats {
+    pub rx_lines: u32,
+    pub rx_bytes: u32,
+    pub tx_frames: u32,
+    /// Frames cut short by CTS staying deasserted
+    pub tx_stalls: u32,
+    /// Bytes discarded by nozen.uart.flush
+    pub flushed: u32,
+}
+
+impl LinkStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a line received from the FPGA
+    pub fn record_rx(&mut self, line: &[u8]) {
+        self.rx_lines = self.rx_lines.saturating_add(1);
+        self.rx_bytes = self.rx_bytes.saturating_add(line.len() as u32);
+    }
+
+    /// Format as string for display
+    pub fn format(&self) -> heapless::String<96> {
+        use core::fmt::Write;
+        let mut s = heapless::String::new();
+        let _ = write!(s, "Link: RX:{} lines/{}B TX:{} frames Stalls:{} Flushed:{}B",
+            self.rx_lines,
+            self.rx_bytes,
+            self.tx_frames,
+            self.tx_stalls,
+            self.flushed
+        );
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_line_assembles_across_pushes() {
+        let mut rx = RxRing::new();
+        let mut out = [0u8; 64];
+        for &b in b"[STA" {
+            rx.push(b);
+        }
+        assert_eq!(rx.read_line(&mut out), None);
+        assert_eq!(rx.partial_len(), 4);
+
+        for &b in b"TUS:OK]\r\nnext" {
+            rx.push(b);
+        }
+        let len = rx.read_line(&mut out).unwrap();
+        assert_eq!(&out[..len], b"[STATUS:OK]");
+        assert_eq!(rx.read_line(&mut out), None);
+        assert_eq!(rx.partial_len(), 4);
+    }
+
+    #[test]
+    fn test_flush_discards_queued_and_partial() {
+        let mut rx = RxRing::new();
+        let mut out = [0u8; 64];
+        for &b in b"gar" {
+            rx.push(b);
+        }
+        rx.read_line(&mut out);
+        for &b in b"bled\n" {
+            rx.push(b);
+        }
+
+        assert_eq!(rx.flush(), 8);
+        assert!(rx.is_empty());
+        assert_eq!(rx.partial_len(), 0);
+        assert_eq!(rx.flush(), 0);
+
+        // A fresh line after the flush comes through whole
+        for &b in b"ok\n" {
+            rx.push(b);
+        }
+        let len = rx.read_line(&mut out).unwrap();
+        assert_eq!(&out[..len], b"ok");
+    }
+
+    #[test]
+    fn test_capture_keeps_lines_inside_window() {
+        let mut capture = UartCapture::new();
+        assert!(!capture.record(b"early", 0));
+
+        capture.start(1000, 50);
+        assert!(capture.record(b"[STATUS:A]", 1000));
+        assert!(capture.record(b"[STATUS:B]", 1049));
+        assert!(!capture.record(b"[STATUS:late]", 1050));
+
+        assert_eq!(capture.finish(1049), None);
+        assert_eq!(capture.finish(1050), Some((2, 0)));
+        assert_eq!(capture.finish(1060), None);
+        assert!(!capture.is_active());
+        assert_eq!(capture.trace(), b"+0ms [STATUS:A]\n+49ms [STATUS:B]\n");
+    }
+
+    #[test]
+    fn test_capture_is_bounded() {
+        let mut capture = UartCapture::new();
+        capture.start(0, 100);
+        let line = [b'x'; 100];
+        for _ in 0..10 {
+            capture.record(&line, 1);
+        }
+        assert!(capture.trace().len() <= CAPTURE_BUFFER_SIZE);
+        assert_eq!(capture.finish(100), Some((4, 6)));
+    }
+
+    #[test]
+    fn test_history_keeps_last_lines_in_order() {
+        let mut history = UartHistory::new();
+        assert!(history.is_empty());
+        history.push(b"[STATUS:0]");
+        assert_eq!(history.iter().next(), Some(&b"[STATUS:0]"[..]));
+
+        let mut history = UartHistory::new();
+        for i in 0..10u8 {
+            history.push(&[b'L', b'0' + i]);
+        }
+        assert_eq!(history.len(), HISTORY_LINES);
+        let lines: std::vec::Vec<&[u8]> = history.iter().collect();
+        assert_eq!(lines, [&b"L2"[..], b"L3", b"L4", b"L5", b"L6", b"L7", b"L8", b"L9"]);
+
+        history.push(&[b'x'; 40]);
+        let last = history.iter().last().unwrap();
+        assert_eq!(last.len(), HISTORY_LINE_LEN);
+        assert!(last.ends_with(b"xx..."));
+
+        // A line that just fits is kept whole
+        history.push(&[b'y'; HISTORY_LINE_LEN]);
+        assert_eq!(history.iter().last().unwrap(), &[b'y'; HISTORY_LINE_LEN][..]);
+    }
+
+    #[test]
+    fn test_push_when_full() {
+        let mut rx = RxRing::new();
+        for i in 0..RX_RING_SIZE {
+            assert!(rx.push(i as u8));
+        }
+        assert!(!rx.push(0));
+        assert_eq!(rx.pop(), Some(0));
+        assert!(rx.push(0xAA));
+        assert_eq!(rx.len(), RX_RING_SIZE);
+    }
+}